@@ -1,14 +1,492 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::config;
+use crate::files;
+use crate::format::{self, Wrap};
+use crate::link_check;
+use crate::link_index::{self, LinkIndex};
+use crate::lint::{self, FileDiagnostics, Severity};
+use crate::markdown;
+use crate::serve;
 
 #[derive(Parser, Debug, Clone)]
 #[command(name = "mdox")]
 #[command(about = "A blazingly fast Markdown viewer and editor", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct CliArgs {
-    /// Path to the Markdown file to open
-    pub file: Option<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Markdown files or folders to open, each in its own window. Files may be given as
+    /// file.md:LINE or file.md#heading, and a folder becomes the workspace root.
+    /// Use "-" to read Markdown from stdin.
+    pub files: Vec<String>,
+
+    /// Read Markdown from stdin and open it as an unsaved document
+    #[arg(long, conflicts_with = "files")]
+    pub stdin: bool,
+
+    /// Line to jump to in the first opened file
+    #[arg(long)]
+    pub line: Option<usize>,
+
+    /// Start a separate app instead of handing the file to the one already running
+    #[arg(long)]
+    pub new_instance: bool,
+}
+
+/// A document to open from the command line, optionally at a line or heading
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenTarget {
+    pub path: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub heading: Option<String>,
+}
+
+impl CliArgs {
+    /// Whether a document should be read from stdin
+    pub fn reads_stdin(&self) -> bool {
+        self.stdin || self.files.iter().any(|file| file == "-")
+    }
+
+    /// The files and folders to open, with any `--line` applied to the first one
+    pub fn open_targets(&self) -> Vec<OpenTarget> {
+        let mut targets: Vec<OpenTarget> = self
+            .files
+            .iter()
+            .filter(|file| *file != "-")
+            .map(|file| parse_open_target(file))
+            .collect();
+
+        if let (Some(line), Some(target)) = (self.line, targets.first_mut()) {
+            target.line = Some(line);
+            target.column = None;
+        }
+        targets
+    }
+}
+
+impl OpenTarget {
+    /// Resolves a relative path against the current directory, so another
+    /// process with a different working directory can still open it
+    pub fn into_absolute(mut self) -> Self {
+        if !is_url(&self.path) {
+            if let Ok(path) = std::path::absolute(&self.path) {
+                self.path = path.to_string_lossy().to_string();
+            }
+        }
+        self
+    }
+}
+
+fn is_url(arg: &str) -> bool {
+    arg.starts_with("http://") || arg.starts_with("https://")
+}
+
+/// Splits `file.md:42`, `file.md:42:7` (as printed by `check` and `lint`) and
+/// `file.md#heading` into a path and location. Paths that exist are taken literally.
+pub fn parse_open_target(arg: &str) -> OpenTarget {
+    let mut target = OpenTarget {
+        path: arg.to_string(),
+        line: None,
+        column: None,
+        heading: None,
+    };
+
+    // URLs keep their port and fragment
+    if Path::new(arg).exists() || is_url(arg) {
+        return target;
+    }
+
+    if let Some((path, heading)) = arg.rsplit_once('#') {
+        if !path.is_empty() && !heading.is_empty() {
+            target.path = path.to_string();
+            target.heading = Some(heading.to_string());
+            return target;
+        }
+    }
+
+    let mut path = arg;
+    let mut numbers = Vec::new();
+    while numbers.len() < 2 {
+        match path.rsplit_once(':') {
+            Some((rest, number)) if !rest.is_empty() => match number.parse::<usize>() {
+                Ok(number) => {
+                    numbers.insert(0, number);
+                    path = rest;
+                }
+                Err(_) => break,
+            },
+            _ => break,
+        }
+    }
+
+    if let Some(&line) = numbers.first() {
+        target.path = path.to_string();
+        target.line = Some(line);
+        target.column = numbers.get(1).copied();
+    }
+
+    target
+}
+
+/// Subcommands that run headless, without opening a window
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Render a Markdown file to HTML
+    Render(RenderArgs),
+    /// Report broken local links, anchors and images
+    Check(CheckArgs),
+    /// Serve rendered Markdown over HTTP with live reload
+    Serve(ServeArgs),
+    /// Normalize Markdown formatting in place
+    Fmt(FmtArgs),
+    /// Report style problems using the rules configured in .mdox.toml
+    Lint(LintArgs),
+    /// Print the links between documents as a graph
+    Graph(GraphArgs),
+    /// Report documents nothing links to, and documents that link nowhere
+    Orphans(OrphansArgs),
+}
+
+/// How report-style subcommands print their results
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Human,
+    Json,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct RenderArgs {
+    /// Markdown file to render, or "-" for stdin
+    pub input: String,
+
+    /// Write HTML to this file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<String>,
+
+    /// Resolve relative image paths against this file or directory
+    /// instead of the input file's directory (or the current directory for stdin)
+    #[arg(long)]
+    pub base_path: Option<String>,
+
+    /// Escape raw HTML and drop javascript: style URLs
+    #[arg(long)]
+    pub sanitize: bool,
+
+    /// Emit only the rendered body instead of a full HTML page
+    #[arg(long)]
+    pub fragment: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CheckArgs {
+    /// Markdown file or directory to check
+    #[arg(default_value = ".")]
+    pub root: String,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+    pub format: OutputFormat,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ServeArgs {
+    /// Directory to serve
+    #[arg(default_value = ".")]
+    pub dir: String,
+
+    /// Port to listen on (localhost only)
+    #[arg(short, long, default_value_t = 4000)]
+    pub port: u16,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct FmtArgs {
+    /// Markdown files or directories to format, or "-" to format stdin to stdout
+    #[arg(default_value = ".")]
+    pub paths: Vec<String>,
+
+    /// Report files that would change instead of rewriting them
+    #[arg(long)]
+    pub check: bool,
+
+    /// Paragraph wrapping: "preserve", "never", or a column width. Overrides
+    /// `wrap` in .mdox.toml, which defaults to "preserve".
+    #[arg(long, value_parser = Wrap::from_str)]
+    pub wrap: Option<Wrap>,
+
+    /// Bullet list marker. Overrides `list-marker` in .mdox.toml, which
+    /// defaults to "-".
+    #[arg(long, value_parser = format::parse_list_marker)]
+    pub list_marker: Option<char>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct LintArgs {
+    /// Markdown files or directories to lint
+    #[arg(default_value = ".")]
+    pub paths: Vec<String>,
+
+    /// Exit non-zero for warnings too, not just errors
+    #[arg(long)]
+    pub deny_warnings: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+    pub format: OutputFormat,
+}
+
+/// How `graph` prints the link graph
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz DOT, e.g. for `dot -Tsvg`
+    Dot,
+    Json,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct GraphArgs {
+    /// Markdown file or directory to map
+    #[arg(default_value = ".")]
+    pub root: String,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+    pub format: GraphFormat,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct OrphansArgs {
+    /// Markdown directory to check
+    #[arg(default_value = ".")]
+    pub root: String,
+
+    /// File name or stem of documents readers start from, which are never
+    /// reported as orphans (repeatable)
+    #[arg(long = "entry", default_values_t = default_entry_points())]
+    pub entry_points: Vec<String>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+    pub format: OutputFormat,
+}
+
+fn default_entry_points() -> Vec<String> {
+    vec!["README".to_string(), "index".to_string()]
 }
 
 pub fn parse_args() -> CliArgs {
     CliArgs::parse()
 }
 
+/// Runs a headless subcommand and returns the process exit code
+pub fn run(command: Command) -> i32 {
+    let result = match command {
+        Command::Render(args) => render(&args),
+        Command::Check(args) => check(&args),
+        Command::Serve(args) => serve::serve(&args.dir, args.port).map(|_| 0),
+        Command::Fmt(args) => fmt(&args),
+        Command::Lint(args) => lint(&args),
+        Command::Graph(args) => graph(&args),
+        Command::Orphans(args) => orphans(&args),
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("mdox: {}", e);
+            1
+        }
+    }
+}
+
+fn render(args: &RenderArgs) -> Result<i32, String> {
+    let from_stdin = args.input == "-";
+    let content = if from_stdin {
+        files::read_stdin()?
+    } else {
+        files::read_file_contents(&args.input)?
+    };
+
+    // Image paths become absolute file:// URLs, so the base must be absolute too
+    let default_base = if from_stdin { "." } else { &args.input };
+    let base = args.base_path.as_deref().unwrap_or(default_base);
+    let base = Path::new(base)
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve base path {}: {}", base, e))?;
+    let base_dir = if base.is_dir() {
+        Some(base.as_path())
+    } else {
+        base.parent()
+    };
+
+    let body = markdown::render_html(&content, base_dir, args.sanitize);
+
+    let html = if args.fragment {
+        body
+    } else {
+        let title = markdown::first_heading(&content).unwrap_or_else(|| {
+            Path::new(&args.input)
+                .file_stem()
+                .filter(|_| !from_stdin)
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| "Untitled".to_string())
+        });
+        markdown::render_page(&title, &body)
+    };
+
+    match &args.output {
+        Some(path) => files::write_file_contents(path, &html)?,
+        None => print!("{}", html),
+    }
+
+    Ok(0)
+}
+
+fn check(args: &CheckArgs) -> Result<i32, String> {
+    let report = link_check::check_links(&args.root)?;
+
+    match args.format {
+        OutputFormat::Human => print!("{}", link_check::format_report(&report)),
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&report)
+                .map_err(|e| format!("Failed to serialize report: {}", e))?
+        ),
+    }
+
+    Ok(if report.broken.is_empty() { 0 } else { 1 })
+}
+
+fn fmt(args: &FmtArgs) -> Result<i32, String> {
+    // Each file follows the .mdox.toml closest to it, stdin the one for the
+    // current directory; options given on the command line win
+    let options_for = |path: &Path| {
+        let mut options = config::load_config_or_default(path).format;
+        options.wrap = args.wrap.unwrap_or(options.wrap);
+        options.list_marker = args.list_marker.unwrap_or(options.list_marker);
+        options
+    };
+
+    let mut changed = 0;
+    for path in &args.paths {
+        if path == "-" {
+            let options = options_for(Path::new("."));
+            let content = files::read_stdin()?;
+            let formatted = format::format_markdown(&content, &options);
+            if args.check {
+                if formatted != content {
+                    changed += 1;
+                    println!("Would reformat: <stdin>");
+                }
+            } else {
+                print!("{}", formatted);
+            }
+            continue;
+        }
+
+        for file in files::walk_markdown_files(path)? {
+            let options = options_for(&file);
+            let file = file.to_string_lossy();
+            let content = files::read_file_contents(&file)?;
+            let formatted = format::format_markdown(&content, &options);
+
+            if formatted == content {
+                continue;
+            }
+            changed += 1;
+
+            if args.check {
+                println!("Would reformat: {}", file);
+            } else {
+                files::write_file_contents(&file, &formatted)?;
+                println!("Formatted: {}", file);
+            }
+        }
+    }
+
+    Ok(if args.check && changed > 0 { 1 } else { 0 })
+}
+
+fn lint(args: &LintArgs) -> Result<i32, String> {
+    let mut files_checked = 0;
+    let mut results = Vec::new();
+
+    for path in &args.paths {
+        for file in files::walk_markdown_files(path)? {
+            // Each file follows the .mdox.toml closest to it
+            let config = config::load_config(&file)?;
+            let content = files::read_file_contents(&file.to_string_lossy())?;
+            files_checked += 1;
+
+            let diagnostics = lint::lint_markdown(&content, &config.lint);
+            if !diagnostics.is_empty() {
+                results.push(FileDiagnostics {
+                    file: file.to_string_lossy().to_string(),
+                    diagnostics,
+                });
+            }
+        }
+    }
+
+    match args.format {
+        OutputFormat::Human => print!("{}", lint::format_report(&results, files_checked)),
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&results)
+                .map_err(|e| format!("Failed to serialize diagnostics: {}", e))?
+        ),
+    }
+
+    // Rules warn unless .mdox.toml raises them to errors
+    let failed = results
+        .iter()
+        .flat_map(|result| &result.diagnostics)
+        .any(|d| d.severity == Severity::Error || args.deny_warnings);
+    Ok(if failed { 1 } else { 0 })
+}
+
+fn graph(args: &GraphArgs) -> Result<i32, String> {
+    let index = LinkIndex::build(&args.root)?;
+    let graph = index.graph();
+
+    match args.format {
+        GraphFormat::Dot => print!("{}", link_index::format_dot(&graph, index.root_dir())),
+        GraphFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&graph)
+                .map_err(|e| format!("Failed to serialize graph: {}", e))?
+        ),
+    }
+
+    Ok(0)
+}
+
+fn orphans(args: &OrphansArgs) -> Result<i32, String> {
+    let index = LinkIndex::build(&args.root)?;
+    let report = index.orphans(&args.entry_points);
+
+    match args.format {
+        OutputFormat::Human => print!(
+            "{}",
+            link_index::format_orphan_report(&report, index.root_dir())
+        ),
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&report)
+                .map_err(|e| format!("Failed to serialize report: {}", e))?
+        ),
+    }
+
+    // Leaf pages are normal, so only documents readers can't reach fail
+    Ok(if report.orphans.is_empty() && report.unreachable.is_empty() {
+        0
+    } else {
+        1
+    })
+}
@@ -1,13 +1,38 @@
+use crate::config;
 use crate::files;
+use crate::format;
+use crate::git_status::GitStatuses;
+use crate::history;
 use crate::markdown;
-use crate::link_discovery;
+use crate::link_check;
+use crate::lint;
+use crate::link_discovery::{self, DiscoveryCache};
+use crate::link_index::{self, LinkIndexes};
 use crate::remote;
+use crate::quick_open::{self, FileLists, TitleCache};
+use crate::rename;
+use crate::replace;
+use crate::search::{self, SearchIndexes};
+use crate::trash::{self, LastDelete};
+use crate::watcher::{self, Change, Watchers};
+use crate::windows::{PendingDocument, PendingDocuments};
+use std::path::{Path, PathBuf};
+use tauri::{Emitter, Manager, State};
 
 #[tauri::command]
 pub fn read_file(path: String) -> Result<String, String> {
     files::read_file_contents(&path)
 }
 
+/// The document this window was opened for, handed out once after it loads
+#[tauri::command]
+pub fn take_pending_document(
+    window: tauri::WebviewWindow,
+    pending: State<PendingDocuments>,
+) -> Option<PendingDocument> {
+    pending.take(window.label())
+}
+
 #[tauri::command]
 pub fn parse_markdown(content: String, base_path: Option<String>) -> Result<String, String> {
     match base_path {
@@ -21,9 +46,127 @@ pub fn resolve_file_path(base_path: String, relative_path: String) -> Result<Str
     files::resolve_relative_path(&base_path, &relative_path)
 }
 
+/// Saves a document, refusing with a conflict if the file changed since
+/// `base_content` was loaded from it
+#[tauri::command]
+pub fn save_file(
+    app: tauri::AppHandle,
+    path: String,
+    content: String,
+    base_content: Option<String>,
+) -> Result<(), files::SaveError> {
+    let config = config::load_config_or_default(Path::new(&path));
+    let store = history_store(&app)?;
+
+    // History is a safety net; a problem with it shouldn't stop the save
+    if let Err(e) = history::snapshot_file(&store, &path, &config.history) {
+        eprintln!("{}", e);
+    }
+    files::save_file_checked(&path, &content, base_content.as_deref(), &config.save)?;
+    if let Err(e) = history::snapshot(&store, &path, &content, &config.history) {
+        eprintln!("{}", e);
+    }
+
+    Ok(())
+}
+
+/// Writes a file the app changes on the user's behalf, such as a replace or a
+/// link rewrite, atomically and with local history on both sides of the change
+fn write_with_history(store: &Path, path: &str, content: &str) -> Result<(), String> {
+    let config = config::load_config_or_default(Path::new(path));
+    if let Err(e) = history::snapshot_file(store, path, &config.history) {
+        eprintln!("{}", e);
+    }
+    files::write_file_with_options(path, content, &config.save)?;
+    if let Err(e) = history::snapshot(store, path, content, &config.history) {
+        eprintln!("{}", e);
+    }
+    Ok(())
+}
+
+/// Where local history snapshots are kept
+fn history_store(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("history"))
+        .map_err(|e| format!("Failed to find app data folder: {}", e))
+}
+
+#[tauri::command]
+pub fn list_history(
+    app: tauri::AppHandle,
+    path: String,
+) -> Result<Vec<history::HistoryVersion>, String> {
+    history::list_versions(&history_store(&app)?, &path)
+}
+
+/// A unified diff from a saved version to the file as it is on disk now
+#[tauri::command]
+pub fn diff_history_version(
+    app: tauri::AppHandle,
+    path: String,
+    id: String,
+) -> Result<String, String> {
+    let current = files::read_file_contents(&path).unwrap_or_default();
+    history::diff_version(&history_store(&app)?, &path, &id, &current)
+}
+
+/// Writes a saved version back to the file and returns its content
+#[tauri::command]
+pub fn restore_history_version(
+    app: tauri::AppHandle,
+    path: String,
+    id: String,
+) -> Result<String, String> {
+    let config = config::load_config_or_default(Path::new(&path));
+    let store = history_store(&app)?;
+    let content = history::read_version(&store, &path, &id)?;
+
+    // Restoring is itself undoable: the replaced content becomes a version too
+    history::snapshot_file(&store, &path, &config.history)?;
+    files::write_file_with_options(&path, &content, &config.save)?;
+    history::snapshot(&store, &path, &content, &config.history)?;
+
+    Ok(content)
+}
+
+/// Formats a document's text with the `[format]` settings that apply to its
+/// path; saving it is left to `save_file`
 #[tauri::command]
-pub fn save_file(path: String, content: String) -> Result<(), String> {
-    files::write_file_contents(&path, &content)
+pub fn format_document(path: Option<String>, content: String) -> String {
+    let options = match path {
+        Some(path) => config::load_config_or_default(Path::new(&path)).format,
+        None => format::FormatOptions::default(),
+    };
+    format::format_markdown(&content, &options)
+}
+
+/// Lints a document with the `.mdox.toml` settings that apply to its path. A
+/// broken settings file is reported on the first line and the defaults apply.
+#[tauri::command]
+pub fn lint_document(path: Option<String>, content: String) -> Vec<lint::Diagnostic> {
+    let (config, problem) = match path.map(|path| config::load_config(Path::new(&path))) {
+        Some(Ok(config)) => (config, None),
+        Some(Err(e)) => (config::ProjectConfig::default(), Some(e)),
+        None => (config::ProjectConfig::default(), None),
+    };
+
+    let mut diagnostics = lint::lint_markdown(&content, &config.lint);
+    if let Some(message) = problem {
+        diagnostics.insert(
+            0,
+            lint::Diagnostic {
+                rule: "config".to_string(),
+                severity: lint::Severity::Error,
+                message,
+                line: 1,
+                column: 1,
+                end_line: 1,
+                end_column: 1,
+            },
+        );
+    }
+    diagnostics
 }
 
 #[tauri::command]
@@ -31,9 +174,14 @@ pub fn file_exists(path: String) -> bool {
     files::file_exists(&path)
 }
 
+/// Lists a folder; without `options`, hidden and ignored entries are left out
 #[tauri::command]
-pub fn read_directory(path: String) -> Result<Vec<files::FileEntry>, String> {
-    files::read_directory(&path)
+pub fn read_directory(
+    git_statuses: State<GitStatuses>,
+    path: String,
+    options: Option<files::ListOptions>,
+) -> Result<Vec<files::FileEntry>, String> {
+    files::read_directory(&path, &options.unwrap_or_default(), Some(&git_statuses))
 }
 
 #[tauri::command]
@@ -41,9 +189,34 @@ pub fn create_directory(path: String) -> Result<(), String> {
     files::create_directory(&path)
 }
 
+/// Moves a file or folder to the trash, or removes it for good when `permanent`
+/// is set. Returns whether `undo_delete` can put it back.
 #[tauri::command]
-pub fn delete_file_or_directory(path: String) -> Result<(), String> {
-    files::delete_file_or_directory(&path)
+pub fn delete_file_or_directory(
+    last_delete: State<LastDelete>,
+    path: String,
+    permanent: bool,
+) -> Result<bool, String> {
+    if permanent {
+        files::delete_file_or_directory(&path)?;
+        last_delete.set(None);
+        return Ok(false);
+    }
+
+    let item = trash::move_to_trash(Path::new(&path))?;
+    last_delete.set(trash::CAN_RESTORE.then_some(item));
+    Ok(trash::CAN_RESTORE)
+}
+
+/// Puts the last trashed file or folder back, returning its path
+#[tauri::command]
+pub fn undo_delete(last_delete: State<LastDelete>) -> Result<String, String> {
+    let item = last_delete.take().ok_or("Nothing to undo")?;
+    if let Err(e) = trash::restore(&item) {
+        last_delete.set(Some(item));
+        return Err(e);
+    }
+    Ok(item.original.to_string_lossy().to_string())
 }
 
 #[tauri::command]
@@ -51,9 +224,221 @@ pub fn rename_file_or_directory(old_path: String, new_path: String) -> Result<()
     files::rename_file_or_directory(&old_path, &new_path)
 }
 
+/// Lists the links under `root` that moving `old_path` to `new_path` would rewrite
+#[tauri::command]
+pub async fn preview_rename(
+    root: Option<String>,
+    old_path: String,
+    new_path: String,
+) -> Result<rename::RenamePlan, String> {
+    tokio::task::spawn_blocking(move || rename::plan_rename(root.as_deref(), &old_path, &new_path))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Moves a file or folder and rewrites the links under `root` that pointed at it
 #[tauri::command]
-pub fn discover_linked_documents(root_path: String, max_depth: usize) -> Result<Vec<link_discovery::LinkedDocument>, String> {
-    link_discovery::discover_linked_documents(&root_path, max_depth)
+pub async fn rename_with_links(
+    app: tauri::AppHandle,
+    root: Option<String>,
+    old_path: String,
+    new_path: String,
+) -> Result<rename::RenamePlan, String> {
+    let store = history_store(&app)?;
+    tokio::task::spawn_blocking(move || {
+        rename::apply_rename(root.as_deref(), &old_path, &new_path, |path, content| {
+            write_with_history(&store, path, content)
+        })
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Watches the window's open document and workspace root, sending it
+/// `file-changed`, `file-created`, `file-deleted` and `file-renamed` events
+/// for what the file tree shows with `options`
+#[tauri::command]
+pub fn watch_paths(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    watchers: State<Watchers>,
+    document: Option<String>,
+    root: Option<String>,
+    options: Option<files::ListOptions>,
+) -> Result<(), String> {
+    let label = window.label().to_string();
+
+    if document.is_none() && root.is_none() {
+        watchers.remove(&label);
+        return Ok(());
+    }
+
+    let target = label.clone();
+    let options = options.unwrap_or_default();
+    let file_watcher = watcher::watch(
+        document.as_deref(),
+        root.as_deref(),
+        &options,
+        move |change| {
+            for path in change.paths() {
+                app.state::<GitStatuses>().invalidate(path);
+                app.state::<SearchIndexes>().invalidate(path);
+                app.state::<LinkIndexes>().invalidate(path);
+                // Edits don't change which files there are
+                if !matches!(change, Change::Changed { .. }) {
+                    app.state::<FileLists>().invalidate(path);
+                }
+            }
+            if let Err(e) = app.emit_to(target.as_str(), change.event_name(), &change) {
+                eprintln!("Failed to emit '{}': {}", change.event_name(), e);
+            }
+        },
+    )?;
+    watchers.insert(&label, file_watcher);
+
+    Ok(())
+}
+
+/// Follows the Markdown links from `root_path`, reusing what earlier
+/// discoveries read from files that haven't changed since
+#[tauri::command]
+pub async fn discover_linked_documents(
+    app: tauri::AppHandle,
+    root_path: String,
+    max_depth: usize,
+) -> Result<Vec<link_discovery::LinkedDocument>, String> {
+    tokio::task::spawn_blocking(move || {
+        let cache = app.state::<DiscoveryCache>();
+        link_discovery::discover_linked_documents(&cache, &root_path, max_depth)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn check_links(root_path: String) -> Result<link_check::LinkReport, String> {
+    tokio::task::spawn_blocking(move || link_check::check_links(&root_path))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Searches the Markdown files under `root`, first re-reading those that
+/// changed since the watcher last reported a change
+#[tauri::command]
+pub async fn search_workspace(
+    app: tauri::AppHandle,
+    root: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<search::SearchResult>, String> {
+    tokio::task::spawn_blocking(move || {
+        app.state::<SearchIndexes>()
+            .search(&root, &query, limit.unwrap_or(50))
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Finds the links to `path` from the other Markdown files under `root`,
+/// first re-reading those that changed since the watcher last reported a change
+#[tauri::command]
+pub async fn find_backlinks(
+    app: tauri::AppHandle,
+    root: String,
+    path: String,
+) -> Result<Vec<link_index::Backlink>, String> {
+    tokio::task::spawn_blocking(move || app.state::<LinkIndexes>().backlinks(&root, &path))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// The Markdown files under `root` and the links between them
+#[tauri::command]
+pub async fn link_graph(
+    app: tauri::AppHandle,
+    root: String,
+) -> Result<link_index::LinkGraph, String> {
+    tokio::task::spawn_blocking(move || app.state::<LinkIndexes>().graph(&root))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Reports the Markdown files under `root` that no link leads to, other
+/// than the `entry_points`, and those that link nowhere
+#[tauri::command]
+pub async fn orphan_report(
+    app: tauri::AppHandle,
+    root: String,
+    entry_points: Vec<String>,
+) -> Result<link_index::OrphanReport, String> {
+    tokio::task::spawn_blocking(move || app.state::<LinkIndexes>().orphans(&root, &entry_points))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Fuzzy-matches `query` against the paths and titles of the Markdown files
+/// under `root`, ranking the `recent` files higher
+#[tauri::command]
+pub async fn quick_open(
+    app: tauri::AppHandle,
+    root: String,
+    query: String,
+    recent: Vec<String>,
+    limit: Option<usize>,
+) -> Result<Vec<quick_open::QuickOpenMatch>, String> {
+    tokio::task::spawn_blocking(move || {
+        let file_lists = app.state::<FileLists>();
+        let titles = app.state::<TitleCache>();
+        quick_open::quick_open(
+            &file_lists,
+            &titles,
+            &root,
+            &query,
+            &recent,
+            limit.unwrap_or(50),
+        )
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Finds text across the Markdown files under `root`, previewing what each
+/// match becomes when a `replacement` is given
+#[tauri::command]
+pub async fn find_in_workspace(
+    root: String,
+    options: replace::FindOptions,
+    replacement: Option<String>,
+) -> Result<replace::FindResult, String> {
+    tokio::task::spawn_blocking(move || {
+        replace::find_in_files(&root, &options, replacement.as_deref())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Replaces the selected matches of a workspace search, saving each file the
+/// way `save_file` does so the previous content stays in local history
+#[tauri::command]
+pub async fn replace_in_workspace(
+    app: tauri::AppHandle,
+    root: String,
+    options: replace::FindOptions,
+    replacement: String,
+    selections: Vec<replace::ReplaceSelection>,
+) -> Result<replace::ReplaceSummary, String> {
+    let store = history_store(&app)?;
+    tokio::task::spawn_blocking(move || {
+        replace::replace_in_files(
+            &root,
+            &options,
+            &replacement,
+            &selections,
+            |path, content| write_with_history(&store, path, content),
+        )
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
 }
 
 #[tauri::command]
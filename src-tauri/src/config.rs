@@ -0,0 +1,61 @@
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::files::{self, SaveOptions};
+use crate::format::FormatOptions;
+use crate::history::HistoryOptions;
+use crate::lint::LintConfig;
+
+/// Per-project settings file, looked up from a document's directory upwards
+pub const CONFIG_FILE_NAME: &str = ".mdox.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ProjectConfig {
+    pub lint: LintConfig,
+    pub format: FormatOptions,
+    pub save: SaveOptions,
+    pub history: HistoryOptions,
+}
+
+/// Finds the nearest `.mdox.toml` in the directory of `path` or any of its parents
+pub fn find_config_file(path: &Path) -> Option<PathBuf> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let dir = if path.is_dir() {
+        path.as_path()
+    } else {
+        path.parent()?
+    };
+
+    dir.ancestors()
+        .map(|ancestor| ancestor.join(CONFIG_FILE_NAME))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Loads the project config that applies to `path`, or the defaults if there is none
+pub fn load_config(path: &Path) -> Result<ProjectConfig, String> {
+    let file = match find_config_file(path) {
+        Some(file) => file,
+        None => return Ok(ProjectConfig::default()),
+    };
+
+    let text = files::read_file_contents(&file.to_string_lossy())?;
+    let config: ProjectConfig =
+        toml::from_str(&text).map_err(|e| format!("Failed to parse {}: {}", file.display(), e))?;
+    config
+        .lint
+        .validate()
+        .and_then(|_| config.format.validate())
+        .map_err(|e| format!("Invalid {}: {}", file.display(), e))?;
+
+    Ok(config)
+}
+
+/// Like `load_config`, but a broken `.mdox.toml` falls back to the defaults so
+/// it never stops a save. `lint_document` reports the problem to the user.
+pub fn load_config_or_default(path: &Path) -> ProjectConfig {
+    load_config(path).unwrap_or_else(|e| {
+        eprintln!("{}; using the default settings", e);
+        ProjectConfig::default()
+    })
+}
@@ -1,12 +1,94 @@
+use std::collections::HashSet;
 use std::fs;
-use std::path::Path;
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use ignore::WalkBuilder;
 use serde::{Deserialize, Serialize};
 
+use crate::git_status::{GitStatus, GitStatuses};
+use crate::gitignore::Ignore;
+
+/// How much of a Markdown file is read to find its title
+const TITLE_READ_LIMIT: u64 = 64 * 1024;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileEntry {
     pub name: String,
     pub path: String,
     pub is_directory: bool,
+    /// Size in bytes, for files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Milliseconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<u64>,
+    /// Milliseconds since the Unix epoch, where the file system records it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<u64>,
+    /// Where the entry points, if it is a symlink
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symlink_target: Option<String>,
+    /// How many entries a folder holds on disk, before any filtering
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub child_count: Option<usize>,
+    /// The front matter title or first heading of a Markdown file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_status: Option<GitStatus>,
+}
+
+impl FileEntry {
+    /// An entry with just a name and path, for listings that don't need metadata
+    pub fn new(name: String, path: String, is_directory: bool) -> FileEntry {
+        FileEntry {
+            name,
+            path,
+            is_directory,
+            size: None,
+            modified: None,
+            created: None,
+            symlink_target: None,
+            child_count: None,
+            title: None,
+            git_status: None,
+        }
+    }
+}
+
+/// Why a save didn't go through
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SaveError {
+    /// The file changed on disk after it was loaded
+    Conflict(SaveConflict),
+    Failed {
+        message: String,
+    },
+}
+
+impl From<String> for SaveError {
+    fn from(message: String) -> Self {
+        SaveError::Failed { message }
+    }
+}
+
+/// A file a batch edit couldn't write
+#[derive(Debug, Clone, Serialize)]
+pub struct WriteFailure {
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SaveConflict {
+    pub disk_content: String,
+    /// Our edits and the disk changes applied to the loaded content, with
+    /// conflict markers where both touched the same lines
+    pub merged: String,
+    pub has_conflicts: bool,
 }
 
 pub fn read_file_contents(path: &str) -> Result<String, String> {
@@ -23,10 +105,180 @@ pub fn read_file_contents(path: &str) -> Result<String, String> {
     fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))
 }
 
+/// Reads everything piped to standard input
+pub fn read_stdin() -> Result<String, String> {
+    let mut content = String::new();
+    std::io::stdin()
+        .read_to_string(&mut content)
+        .map_err(|e| format!("Failed to read stdin: {}", e))?;
+    Ok(content)
+}
+
+/// What to keep of a file's previous content when a save overwrites it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackupMode {
+    #[default]
+    None,
+    /// `notes.md.bak`, replaced on every save
+    Simple,
+    /// `notes.md.~1~`, `notes.md.~2~`, ... as GNU `cp --backup=numbered` does
+    Numbered,
+}
+
+/// The `[save]` section of `.mdox.toml`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct SaveOptions {
+    pub backup: BackupMode,
+}
+
 pub fn write_file_contents(path: &str, content: &str) -> Result<(), String> {
-    let file_path = Path::new(path);
+    write_file_with_options(path, content, &SaveOptions::default())
+}
+
+/// Writes to a temp file next to the target, flushes it to disk and renames it
+/// over the target, so a crash or full disk mid-save leaves the old file intact
+pub fn write_file_with_options(
+    path: &str,
+    content: &str,
+    options: &SaveOptions,
+) -> Result<(), String> {
+    // Saving through a symlink updates the file it points to and keeps the link
+    let target = resolve_symlinks(Path::new(path))?;
+    let existing = fs::metadata(&target).ok();
+
+    if existing.is_some() {
+        backup_file(&target, options.backup)?;
+    }
+
+    let dir = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(".mdox-save-");
+    // Temp files are private by default; new documents get the usual umask-based mode
+    #[cfg(unix)]
+    if existing.is_none() {
+        use std::os::unix::fs::PermissionsExt;
+        builder.permissions(fs::Permissions::from_mode(0o666));
+    }
 
-    fs::write(file_path, content).map_err(|e| format!("Failed to write file: {}", e))
+    let mut temp = builder
+        .tempfile_in(dir)
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+    temp.write_all(content.as_bytes())
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+
+    // Keep the permissions of the file being replaced
+    if let Some(metadata) = &existing {
+        fs::set_permissions(temp.path(), metadata.permissions())
+            .map_err(|e| format!("Failed to preserve permissions: {}", e))?;
+    }
+
+    temp.as_file()
+        .sync_all()
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+    temp.persist(&target)
+        .map_err(|e| format!("Failed to write file: {}", e.error))?;
+
+    // Make the rename itself durable
+    #[cfg(unix)]
+    if let Ok(dir) = fs::File::open(dir) {
+        let _ = dir.sync_all();
+    }
+
+    Ok(())
+}
+
+/// Follows a chain of symlinks to the path that holds the data, which may not exist yet
+fn resolve_symlinks(path: &Path) -> Result<PathBuf, String> {
+    let mut path = path.to_path_buf();
+
+    // Same limit as Linux uses for symlink loops
+    for _ in 0..40 {
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let link = fs::read_link(&path)
+                    .map_err(|e| format!("Failed to read symlink {}: {}", path.display(), e))?;
+                path = match path.parent() {
+                    Some(parent) => parent.join(link),
+                    None => link,
+                };
+            }
+            _ => return Ok(path),
+        }
+    }
+
+    Err(format!("Too many levels of symlinks: {}", path.display()))
+}
+
+/// Copies a file aside before it's overwritten
+fn backup_file(path: &Path, mode: BackupMode) -> Result<(), String> {
+    let backup = match mode {
+        BackupMode::None => return Ok(()),
+        BackupMode::Simple => append_to_file_name(path, ".bak"),
+        BackupMode::Numbered => {
+            let mut number = 1;
+            loop {
+                let candidate = append_to_file_name(path, &format!(".~{}~", number));
+                if !candidate.exists() {
+                    break candidate;
+                }
+                number += 1;
+            }
+        }
+    };
+
+    fs::copy(path, &backup)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to create backup {}: {}", backup.display(), e))
+}
+
+fn append_to_file_name(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Saves `content` unless the file no longer matches `base`, the content it had
+/// when it was loaded. Without a base the file is overwritten.
+pub fn save_file_checked(
+    path: &str,
+    content: &str,
+    base: Option<&str>,
+    options: &SaveOptions,
+) -> Result<(), SaveError> {
+    if let Some(base) = base {
+        match fs::read_to_string(path) {
+            Ok(disk) if disk != base && disk != content => {
+                return Err(SaveError::Conflict(merge_preview(base, content, disk)));
+            }
+            Ok(_) => {}
+            // A file deleted since it was loaded is simply recreated
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to read file: {}", e).into()),
+        }
+    }
+
+    write_file_with_options(path, content, options)?;
+    Ok(())
+}
+
+/// Three-way merges our edits and the disk copy against the loaded content
+fn merge_preview(base: &str, ours: &str, disk_content: String) -> SaveConflict {
+    let (merged, has_conflicts) = match diffy::merge(base, ours, &disk_content) {
+        Ok(merged) => (merged, false),
+        Err(merged) => (merged, true),
+    };
+
+    SaveConflict {
+        disk_content,
+        merged,
+        has_conflicts,
+    }
 }
 
 pub fn file_exists(path: &str) -> bool {
@@ -48,7 +300,55 @@ pub fn resolve_relative_path(base_path: &str, relative_path: &str) -> Result<Str
         .ok_or_else(|| "Failed to convert path to string".to_string())
 }
 
-pub fn read_directory(path: &str) -> Result<Vec<FileEntry>, String> {
+/// What `read_directory` leaves out
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ListOptions {
+    /// Include entries whose name starts with a dot
+    pub show_hidden: bool,
+    /// Skip what `.gitignore` and `.ignore` files exclude
+    pub respect_ignore_files: bool,
+    /// Only list Markdown files, besides folders
+    pub markdown_only: bool,
+    /// Only list folders that have a Markdown file somewhere below them
+    pub markdown_folders_only: bool,
+    /// Extra gitignore-style patterns to leave out, such as `node_modules` or `*.log`
+    pub exclude: Vec<String>,
+    pub sort_by: SortBy,
+    /// Read each Markdown file's title, which means opening every file listed
+    pub show_titles: bool,
+}
+
+/// The order of a listing; folders always come first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortBy {
+    #[default]
+    Name,
+    /// Most recently modified first
+    Modified,
+}
+
+impl Default for ListOptions {
+    fn default() -> Self {
+        ListOptions {
+            show_hidden: false,
+            respect_ignore_files: true,
+            markdown_only: false,
+            markdown_folders_only: false,
+            exclude: Vec::new(),
+            sort_by: SortBy::Name,
+            show_titles: false,
+        }
+    }
+}
+
+/// Lists a folder, with the Git status of each entry when `git_statuses` is given
+pub fn read_directory(
+    path: &str,
+    options: &ListOptions,
+    git_statuses: Option<&GitStatuses>,
+) -> Result<Vec<FileEntry>, String> {
     let dir_path = Path::new(path);
 
     if !dir_path.exists() {
@@ -59,6 +359,12 @@ pub fn read_directory(path: &str) -> Result<Vec<FileEntry>, String> {
         return Err(format!("Path is not a directory: {}", path));
     }
 
+    let ignore = if options.respect_ignore_files {
+        Ignore::for_directory(dir_path, &options.exclude)
+    } else {
+        Ignore::exclude_only(&options.exclude)
+    };
+    let git_status = git_statuses.map(|statuses| statuses.for_directory(dir_path));
     let mut entries = Vec::new();
 
     let read_dir = fs::read_dir(dir_path)
@@ -67,10 +373,16 @@ pub fn read_directory(path: &str) -> Result<Vec<FileEntry>, String> {
     for entry_result in read_dir {
         let entry = entry_result.map_err(|e| format!("Failed to read entry: {}", e))?;
         let entry_path = entry.path();
+        let is_directory = entry_path.is_dir();
+
+        if !is_listed(&entry_path, is_directory, &ignore, options) {
+            continue;
+        }
 
-        // Skip hidden files (starting with .)
-        if let Some(name) = entry_path.file_name() {
-            if name.to_string_lossy().starts_with('.') {
+        if is_directory && options.markdown_folders_only {
+            let mut visited = HashSet::new();
+            let below = ignore.descend(&entry_path);
+            if !contains_markdown(&entry_path, &below, options, &mut visited) {
                 continue;
             }
         }
@@ -86,20 +398,23 @@ pub fn read_directory(path: &str) -> Result<Vec<FileEntry>, String> {
             .ok_or("Failed to convert path to string")?
             .to_string();
 
-        let is_directory = entry_path.is_dir();
-
-        entries.push(FileEntry {
-            name,
-            path: path_str,
-            is_directory,
-        });
+        let mut file_entry = FileEntry::new(name, path_str, is_directory);
+        add_metadata(&mut file_entry, &entry_path, options.show_titles);
+        file_entry.git_status = git_status
+            .as_ref()
+            .and_then(|status| status.status(&entry_path, is_directory));
+        entries.push(file_entry);
     }
 
-    // Sort: directories first, then files, both alphabetically
+    // Sort: directories first, then files, both by name or newest first
     entries.sort_by(|a, b| {
         match (a.is_directory, b.is_directory) {
             (true, false) => std::cmp::Ordering::Less,
             (false, true) => std::cmp::Ordering::Greater,
+            _ if options.sort_by == SortBy::Modified => b
+                .modified
+                .cmp(&a.modified)
+                .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())),
             _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
         }
     });
@@ -107,6 +422,100 @@ pub fn read_directory(path: &str) -> Result<Vec<FileEntry>, String> {
     Ok(entries)
 }
 
+/// Fills in the size, times, symlink target and, if asked for, the Markdown title
+fn add_metadata(entry: &mut FileEntry, path: &Path, with_title: bool) {
+    if let Ok(link) = fs::symlink_metadata(path) {
+        if link.file_type().is_symlink() {
+            entry.symlink_target = fs::read_link(path)
+                .ok()
+                .map(|target| target.to_string_lossy().to_string());
+        }
+    }
+
+    // Follows symlinks, so a linked file reports the size of what it points to
+    if let Ok(metadata) = fs::metadata(path) {
+        if !entry.is_directory {
+            entry.size = Some(metadata.len());
+        } else if let Ok(children) = fs::read_dir(path) {
+            entry.child_count = Some(children.count());
+        }
+        entry.modified = metadata.modified().ok().and_then(millis_since_epoch);
+        entry.created = metadata.created().ok().and_then(millis_since_epoch);
+    }
+
+    if with_title && !entry.is_directory && is_markdown_file(path) {
+        entry.title = read_title(path);
+    }
+}
+
+fn millis_since_epoch(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|duration| duration.as_millis() as u64)
+}
+
+/// Reads the start of a Markdown file for its title
+pub fn read_title(path: &Path) -> Option<String> {
+    let mut head = Vec::new();
+    fs::File::open(path)
+        .ok()?
+        .take(TITLE_READ_LIMIT)
+        .read_to_end(&mut head)
+        .ok()?;
+    crate::markdown::document_title(&String::from_utf8_lossy(&head))
+}
+
+/// Applies the hidden, ignore and Markdown-only filters to one entry
+fn is_listed(path: &Path, is_directory: bool, ignore: &Ignore, options: &ListOptions) -> bool {
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+
+    // Git's own folder is never useful to browse
+    if name == ".git" || (!options.show_hidden && name.starts_with('.')) {
+        return false;
+    }
+    if !is_directory && options.markdown_only && !is_markdown_file(path) {
+        return false;
+    }
+
+    !ignore.is_ignored(path, is_directory)
+}
+
+/// Whether a listed Markdown file exists anywhere below `dir`
+fn contains_markdown(
+    dir: &Path,
+    ignore: &Ignore,
+    options: &ListOptions,
+    visited: &mut HashSet<PathBuf>,
+) -> bool {
+    // Symlinked folders can loop back on themselves
+    if !visited.insert(dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())) {
+        return false;
+    }
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return false;
+    };
+
+    let mut subdirectories = Vec::new();
+    for entry in read_dir.flatten() {
+        let entry_path = entry.path();
+        let is_directory = entry_path.is_dir();
+        if !is_listed(&entry_path, is_directory, ignore, options) {
+            continue;
+        }
+        if is_directory {
+            subdirectories.push(entry_path);
+        } else if is_markdown_file(&entry_path) {
+            return true;
+        }
+    }
+
+    subdirectories
+        .iter()
+        .any(|subdirectory| {
+            contains_markdown(subdirectory, &ignore.descend(subdirectory), options, visited)
+        })
+}
+
 pub fn create_directory(path: &str) -> Result<(), String> {
     fs::create_dir_all(path).map_err(|e| format!("Failed to create directory: {}", e))
 }
@@ -128,3 +537,73 @@ pub fn delete_file_or_directory(path: &str) -> Result<(), String> {
 pub fn rename_file_or_directory(old_path: &str, new_path: &str) -> Result<(), String> {
     fs::rename(old_path, new_path).map_err(|e| format!("Failed to rename: {}", e))
 }
+
+/// Returns true for paths with a `.md` or `.markdown` extension
+pub fn is_markdown_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("md") | Some("markdown")
+    )
+}
+
+/// Folders the workspace walk never enters, even outside a Git repository
+pub const SKIPPED_DIRECTORIES: [&str; 1] = ["node_modules"];
+
+/// Recursively collects Markdown files under `root`, skipping hidden and
+/// ignored entries the same way `read_directory` does. Symlinked folders are
+/// followed once, so links that loop back are not walked again. A file root
+/// yields just that file.
+pub fn walk_markdown_files(root: &str) -> Result<Vec<PathBuf>, String> {
+    let root_path = Path::new(root);
+
+    if !root_path.exists() {
+        return Err(format!("Path not found: {}", root));
+    }
+
+    if root_path.is_file() {
+        return Ok(vec![root_path.to_path_buf()]);
+    }
+
+    // A folder reached through several symlinks is only walked the first time
+    let visited = Arc::new(Mutex::new(HashSet::new()));
+    let walker = WalkBuilder::new(root_path)
+        .follow_links(true)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(move |entry| {
+            if !entry.file_type().is_some_and(|t| t.is_dir()) {
+                return true;
+            }
+            if SKIPPED_DIRECTORIES.contains(&entry.file_name().to_string_lossy().as_ref()) {
+                return false;
+            }
+            let canonical = entry
+                .path()
+                .canonicalize()
+                .unwrap_or_else(|_| entry.path().to_path_buf());
+            visited
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(canonical)
+        })
+        .build();
+
+    let mut found = Vec::new();
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            // Unreadable folders and symlink loops are reported and skipped
+            Err(e) => {
+                eprintln!("Skipping part of {}: {}", root, e);
+                continue;
+            }
+        };
+
+        let is_file = entry.file_type().is_some_and(|t| t.is_file());
+        if is_file && is_markdown_file(entry.path()) {
+            found.push(entry.into_path());
+        }
+    }
+
+    found.sort();
+    Ok(found)
+}
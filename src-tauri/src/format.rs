@@ -0,0 +1,1081 @@
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, Parser, Tag, TagEnd};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::markdown;
+
+/// How paragraph text is laid out. Written as "preserve", "never" or a width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "WrapSetting")]
+pub enum Wrap {
+    /// Keep the author's line breaks
+    Preserve,
+    /// Put each paragraph on a single line
+    Never,
+    /// Reflow paragraphs to fit within this many columns
+    Width(usize),
+}
+
+impl FromStr for Wrap {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Wrap, String> {
+        match value {
+            "preserve" => Ok(Wrap::Preserve),
+            "never" => Ok(Wrap::Never),
+            width => width.parse::<usize>().map(Wrap::Width).map_err(|_| {
+                format!(
+                    "expected \"preserve\", \"never\" or a width, got \"{}\"",
+                    width
+                )
+            }),
+        }
+    }
+}
+
+/// `wrap` in `.mdox.toml`, which may be a number or a string
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WrapSetting {
+    Width(usize),
+    Name(String),
+}
+
+impl TryFrom<WrapSetting> for Wrap {
+    type Error = String;
+
+    fn try_from(setting: WrapSetting) -> Result<Wrap, String> {
+        match setting {
+            WrapSetting::Width(width) => Ok(Wrap::Width(width)),
+            WrapSetting::Name(name) => name.parse(),
+        }
+    }
+}
+
+/// The `[format]` section of `.mdox.toml`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct FormatOptions {
+    pub list_marker: char,
+    pub wrap: Wrap,
+}
+
+impl FormatOptions {
+    /// Rejects list markers Markdown doesn't have
+    pub fn validate(&self) -> Result<(), String> {
+        parse_list_marker(&self.list_marker.to_string()).map(|_| ())
+    }
+}
+
+/// Reads a bullet list marker: `-`, `*` or `+`
+pub fn parse_list_marker(value: &str) -> Result<char, String> {
+    match value {
+        "-" | "*" | "+" => Ok(value.chars().next().unwrap()),
+        _ => Err(format!("expected -, * or +, got \"{}\"", value)),
+    }
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            list_marker: '-',
+            wrap: Wrap::Preserve,
+        }
+    }
+}
+
+/// Narrowest column budget used when nesting eats into the wrap width
+const MIN_WRAP_WIDTH: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Paragraph,
+    /// Text directly inside a tight list item
+    Inline,
+    List {
+        ordered: bool,
+        marker: char,
+    },
+    ThematicBreak,
+    Other,
+}
+
+struct Block {
+    kind: BlockKind,
+    lines: Vec<String>,
+}
+
+/// A container block the cursor is inside
+#[derive(Debug, Clone, Copy)]
+enum Container {
+    Quote,
+    /// A list item, with how many columns its content is indented past its parent's
+    Item(usize),
+}
+
+/// Rewrites Markdown with consistent block syntax without changing what it renders.
+///
+/// Block structure is rebuilt from the parse tree, while inline content is copied
+/// from the source so escapes, emphasis style and link syntax are left alone.
+pub fn format_markdown(content: &str, options: &FormatOptions) -> String {
+    let (front_matter, body) = markdown::split_front_matter(content);
+
+    let mut output = String::new();
+    if let Some(front_matter) = front_matter {
+        output.push_str(front_matter.trim_end());
+        output.push_str("\n\n");
+    }
+
+    let formatted = Formatter::new(body, *options).format();
+    output.push_str(&formatted);
+
+    if output.trim().is_empty() {
+        return String::new();
+    }
+
+    let mut output = output.trim_end().to_string();
+    output.push('\n');
+    output
+}
+
+struct Formatter<'a> {
+    source: &'a str,
+    events: Vec<(Event<'a>, Range<usize>)>,
+    pos: usize,
+    options: FormatOptions,
+    /// Offsets of newlines that end a hard line break
+    hard_breaks: HashSet<usize>,
+    /// Link reference definitions, which the parser reports separately from events
+    definitions: Vec<Range<usize>>,
+    /// The containers around the cursor, outermost first, with the offset
+    /// where each one's content starts on its first line
+    containers: Vec<(Container, usize)>,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(source: &'a str, options: FormatOptions) -> Self {
+        let parser = Parser::new_ext(source, markdown::parser_options()).into_offset_iter();
+
+        let mut definitions: Vec<Range<usize>> = parser
+            .reference_definitions()
+            .iter()
+            .map(|(_, definition)| definition.span.clone())
+            .collect();
+        definitions.sort_by_key(|span| span.start);
+
+        let events: Vec<(Event<'a>, Range<usize>)> = parser.collect();
+
+        let hard_breaks = events
+            .iter()
+            .filter(|(event, _)| matches!(event, Event::HardBreak))
+            .map(|(_, range)| range.end - 1)
+            .collect();
+
+        Formatter {
+            source,
+            events,
+            pos: 0,
+            options,
+            hard_breaks,
+            definitions,
+            containers: Vec::new(),
+        }
+    }
+
+    fn format(mut self) -> String {
+        let width = self.wrap_width(0);
+        let mut blocks = Vec::new();
+        let mut next_definition = 0;
+
+        while self.pos < self.events.len() {
+            let start = self.events[self.pos].1.start;
+
+            // Keep reference definitions roughly where they were written
+            let pending = self.definitions[next_definition..]
+                .iter()
+                .take_while(|span| span.start < start)
+                .count();
+            if pending > 0 {
+                blocks.push(self.definition_block(next_definition..next_definition + pending));
+                next_definition += pending;
+            }
+
+            let previous = blocks.last().map(|block: &Block| block.kind);
+            match self.block(width, previous) {
+                Some(block) => blocks.push(block),
+                None => self.pos += 1,
+            }
+        }
+
+        if next_definition < self.definitions.len() {
+            blocks.push(self.definition_block(next_definition..self.definitions.len()));
+        }
+
+        join_blocks(blocks, false).join("\n")
+    }
+
+    fn wrap_width(&self, indent: usize) -> usize {
+        match self.options.wrap {
+            Wrap::Width(width) => width.saturating_sub(indent).max(MIN_WRAP_WIDTH),
+            _ => usize::MAX,
+        }
+    }
+
+    fn definition_block(&self, range: Range<usize>) -> Block {
+        let lines = self.definitions[range]
+            .iter()
+            .flat_map(|span| self.clean_lines(span.clone()))
+            .collect();
+
+        Block {
+            kind: BlockKind::Other,
+            lines,
+        }
+    }
+
+    /// Renders the block starting at the cursor, or None if the cursor is not at one
+    fn block(&mut self, width: usize, previous: Option<BlockKind>) -> Option<Block> {
+        let (event, range) = self.events[self.pos].clone();
+
+        if is_inline(&event) {
+            let lines = self.inline_run(self.options.wrap, width);
+            return Some(Block {
+                kind: BlockKind::Inline,
+                lines,
+            });
+        }
+
+        let tag = match event {
+            Event::Start(tag) => tag,
+            Event::Rule => {
+                self.pos += 1;
+                return Some(Block {
+                    kind: BlockKind::ThematicBreak,
+                    lines: vec!["---".to_string()],
+                });
+            }
+            _ => return None,
+        };
+
+        self.pos += 1;
+        let block = match tag {
+            Tag::Paragraph => {
+                let lines = self.inline_run(self.options.wrap, width);
+                self.expect_end();
+                Block {
+                    kind: BlockKind::Paragraph,
+                    lines,
+                }
+            }
+            Tag::Heading {
+                level,
+                id,
+                classes,
+                attrs,
+            } => {
+                // Heading IDs come from the text, so its spacing is left alone
+                let text = self.inline_run(Wrap::Never, usize::MAX).join(" ");
+                self.expect_end();
+
+                let mut line = "#".repeat(heading_level(level));
+                if !text.is_empty() {
+                    line.push(' ');
+                    line.push_str(&text);
+                }
+
+                // Heading attributes are not inline content, so rebuild them
+                let mut attributes: Vec<String> = Vec::new();
+                if let Some(id) = id {
+                    attributes.push(format!("#{}", id));
+                }
+                attributes.extend(classes.iter().map(|class| format!(".{}", class)));
+                attributes.extend(attrs.iter().map(|(key, value)| match value {
+                    Some(value) => format!("{}={}", key, value),
+                    None => key.to_string(),
+                }));
+                if !attributes.is_empty() {
+                    line.push_str(&format!(" {{{}}}", attributes.join(" ")));
+                }
+
+                Block {
+                    kind: BlockKind::Other,
+                    lines: vec![line],
+                }
+            }
+            Tag::BlockQuote(_) => {
+                self.enter_quote(range.start);
+                let inner = self.blocks_until_end(width.saturating_sub(2).max(MIN_WRAP_WIDTH));
+                self.containers.pop();
+                self.expect_end();
+
+                let lines = join_blocks(inner, false)
+                    .into_iter()
+                    .map(|line| {
+                        if line.is_empty() {
+                            ">".to_string()
+                        } else {
+                            format!("> {}", line)
+                        }
+                    })
+                    .collect();
+
+                Block {
+                    kind: BlockKind::Other,
+                    lines,
+                }
+            }
+            Tag::CodeBlock(kind) => {
+                let mut code = String::new();
+                while let Some((Event::Text(text), _)) = self.events.get(self.pos) {
+                    code.push_str(text);
+                    self.pos += 1;
+                }
+                self.expect_end();
+
+                let info = match kind {
+                    CodeBlockKind::Fenced(info) => info.trim().to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+
+                Block {
+                    kind: BlockKind::Other,
+                    lines: fenced_code(&code, &info),
+                }
+            }
+            Tag::HtmlBlock => {
+                let mut html = String::new();
+                // Leading indentation arrives as a Text event before the first line
+                while let Some((Event::Html(text) | Event::Text(text), _)) =
+                    self.events.get(self.pos)
+                {
+                    html.push_str(text);
+                    self.pos += 1;
+                }
+                self.expect_end();
+
+                Block {
+                    kind: BlockKind::Other,
+                    lines: html
+                        .trim_end()
+                        .lines()
+                        .map(|l| l.trim_end().to_string())
+                        .collect(),
+                }
+            }
+            Tag::List(start) => self.list(start, width, previous),
+            Tag::Table(alignments) => self.table(&alignments),
+            _ => {
+                // Constructs this formatter does not restructure are copied verbatim
+                self.skip_to_end();
+                Block {
+                    kind: BlockKind::Other,
+                    lines: self.clean_lines(range),
+                }
+            }
+        };
+
+        Some(block)
+    }
+
+    /// Renders blocks until the End event closing the current container
+    fn blocks_until_end(&mut self, width: usize) -> Vec<Block> {
+        let mut blocks = Vec::new();
+
+        while let Some((event, _)) = self.events.get(self.pos) {
+            if matches!(event, Event::End(_)) {
+                break;
+            }
+            let previous = blocks.last().map(|block: &Block| block.kind);
+            match self.block(width, previous) {
+                Some(block) => blocks.push(block),
+                None => self.pos += 1,
+            }
+        }
+
+        blocks
+    }
+
+    fn list(&mut self, start: Option<u64>, width: usize, previous: Option<BlockKind>) -> Block {
+        let ordered = start.is_some();
+
+        // Adjacent lists only stay separate if their markers differ
+        let marker = match (ordered, previous) {
+            (
+                true,
+                Some(BlockKind::List {
+                    ordered: true,
+                    marker: '.',
+                }),
+            ) => ')',
+            (true, _) => '.',
+            (
+                false,
+                Some(BlockKind::List {
+                    ordered: false,
+                    marker,
+                }),
+            ) if marker == self.options.list_marker => {
+                if self.options.list_marker == '-' {
+                    '*'
+                } else {
+                    '-'
+                }
+            }
+            (false, _) => self.options.list_marker,
+        };
+
+        let mut number = start.unwrap_or(1);
+        let mut items: Vec<(String, Vec<Block>)> = Vec::new();
+
+        while let Some((event, range)) = self.events.get(self.pos) {
+            match event {
+                Event::Start(Tag::Item) => {
+                    self.enter_item(range.start);
+                    self.pos += 1;
+                    let prefix = if ordered {
+                        format!("{}{}", number, marker)
+                    } else {
+                        marker.to_string()
+                    };
+                    number += 1;
+
+                    let blocks = self.blocks_until_end(
+                        width.saturating_sub(prefix.len() + 1).max(MIN_WRAP_WIDTH),
+                    );
+                    self.containers.pop();
+                    self.expect_end();
+                    items.push((prefix, blocks));
+                }
+                Event::End(TagEnd::List(_)) => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => self.pos += 1,
+            }
+        }
+
+        // The parser only emits paragraphs inside items of loose lists
+        let loose = items.iter().any(|(_, blocks)| {
+            blocks
+                .iter()
+                .any(|block| block.kind == BlockKind::Paragraph)
+        });
+
+        let mut lines = Vec::new();
+        for (index, (prefix, blocks)) in items.into_iter().enumerate() {
+            if loose && index > 0 {
+                lines.push(String::new());
+            }
+
+            let indent = " ".repeat(prefix.len() + 1);
+            let item_lines = join_blocks(blocks, !loose);
+
+            if item_lines.is_empty() {
+                lines.push(prefix);
+                continue;
+            }
+
+            for (line_index, line) in item_lines.into_iter().enumerate() {
+                if line_index == 0 {
+                    lines.push(format!("{} {}", prefix, line));
+                } else if line.is_empty() {
+                    lines.push(line);
+                } else {
+                    lines.push(format!("{}{}", indent, line));
+                }
+            }
+        }
+
+        Block {
+            kind: BlockKind::List { ordered, marker },
+            lines,
+        }
+    }
+
+    fn table(&mut self, alignments: &[Alignment]) -> Block {
+        let mut rows: Vec<Vec<String>> = Vec::new();
+
+        while let Some((event, _)) = self.events.get(self.pos) {
+            match event {
+                Event::Start(Tag::TableHead) | Event::Start(Tag::TableRow) => {
+                    rows.push(Vec::new());
+                    self.pos += 1;
+                }
+                Event::Start(Tag::TableCell) => {
+                    self.pos += 1;
+                    let cell = self.inline_run(Wrap::Never, usize::MAX).join(" ");
+                    self.expect_end();
+                    if let Some(row) = rows.last_mut() {
+                        row.push(cell);
+                    }
+                }
+                Event::End(TagEnd::Table) => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => self.pos += 1,
+            }
+        }
+
+        let columns = alignments
+            .len()
+            .max(rows.iter().map(|row| row.len()).max().unwrap_or(0));
+        let mut widths = vec![3; columns];
+        for row in &rows {
+            for (column, cell) in row.iter().enumerate() {
+                widths[column] = widths[column].max(cell.chars().count());
+            }
+        }
+
+        let render_row = |row: &[String]| {
+            let cells: Vec<String> = (0..columns)
+                .map(|column| {
+                    let cell = row.get(column).map(String::as_str).unwrap_or("");
+                    let alignment = alignments.get(column).copied().unwrap_or(Alignment::None);
+                    pad_cell(cell, widths[column], alignment)
+                })
+                .collect();
+            format!("| {} |", cells.join(" | "))
+        };
+
+        let delimiter: Vec<String> = (0..columns)
+            .map(|column| {
+                let width = widths[column];
+                match alignments.get(column).copied().unwrap_or(Alignment::None) {
+                    Alignment::None => "-".repeat(width),
+                    Alignment::Left => format!(":{}", "-".repeat(width - 1)),
+                    Alignment::Right => format!("{}:", "-".repeat(width - 1)),
+                    Alignment::Center => format!(":{}:", "-".repeat(width - 2)),
+                }
+            })
+            .collect();
+
+        let mut lines = Vec::new();
+        for (index, row) in rows.iter().enumerate() {
+            lines.push(render_row(row));
+            if index == 0 {
+                lines.push(format!("| {} |", delimiter.join(" | ")));
+            }
+        }
+
+        Block {
+            kind: BlockKind::Other,
+            lines,
+        }
+    }
+
+    /// Consumes consecutive inline events and returns their source text laid out in lines
+    fn inline_run(&mut self, wrap: Wrap, width: usize) -> Vec<String> {
+        let first = self.pos;
+        while let Some((event, _)) = self.events.get(self.pos) {
+            if !is_inline(event) {
+                break;
+            }
+            self.pos += 1;
+        }
+
+        if first == self.pos {
+            return Vec::new();
+        }
+
+        let mut start = self.events[first].1.start;
+        // An escaped character's text event starts after its backslash
+        if start > 0 && self.source.as_bytes()[start - 1] == b'\\' {
+            start -= 1;
+        }
+        let range = start..self.events[self.pos - 1].1.end;
+
+        // Split into segments at hard breaks; soft breaks are free to move
+        let mut segments: Vec<Vec<String>> = vec![Vec::new()];
+        let mut offset = range.start;
+        for (index, line) in self.source[range.clone()].split('\n').enumerate() {
+            let newline = offset + line.len();
+            offset = newline + 1;
+
+            // The first line starts at the content, past any container markers
+            let line = if index == 0 {
+                line
+            } else {
+                self.strip_container_prefix(line)
+            };
+            let mut text = line.trim_end().to_string();
+            if self.hard_breaks.contains(&newline) {
+                if text.ends_with('\\') {
+                    text.pop();
+                }
+                segments
+                    .last_mut()
+                    .unwrap()
+                    .push(text.trim_end().to_string());
+                segments.push(Vec::new());
+            } else {
+                segments.last_mut().unwrap().push(text);
+            }
+        }
+
+        let mut lines = Vec::new();
+        let count = segments.len();
+        for (index, segment) in segments.into_iter().enumerate() {
+            let mut segment_lines = match wrap {
+                Wrap::Preserve => keep_lines(segment),
+                Wrap::Never => vec![segment.join(" ")],
+                Wrap::Width(_) => reflow(&segment.join(" "), width),
+            };
+
+            // Hard breaks are always written with a backslash, never trailing spaces
+            if index + 1 < count {
+                if let Some(last) = segment_lines.last_mut() {
+                    last.push('\\');
+                }
+            }
+            lines.append(&mut segment_lines);
+        }
+
+        lines.retain(|line| !line.is_empty());
+        lines
+    }
+
+    /// Removes the blockquote markers and indentation that belong to enclosing
+    /// containers. Indentation beyond them can be content, such as the spaces
+    /// inside a code span that continues on the line.
+    fn strip_container_prefix<'s>(&self, line: &'s str) -> &'s str {
+        let mut line = line;
+        for (container, _) in &self.containers {
+            let spaces = line.len() - line.trim_start_matches(' ').len();
+            match container {
+                Container::Quote => match line[spaces..].strip_prefix('>') {
+                    Some(rest) if spaces <= 3 => line = rest.strip_prefix(' ').unwrap_or(rest),
+                    // A lazy continuation line leaves the markers out
+                    _ => break,
+                },
+                Container::Item(indent) => line = &line[spaces.min(*indent)..],
+            }
+        }
+        line
+    }
+
+    /// Enters the blockquote whose `>` is at or after `start`
+    fn enter_quote(&mut self, start: usize) {
+        let marker = start + self.source[start..].find('>').unwrap_or(0);
+        let mut content = marker + 1;
+        if self.source[content..].starts_with(' ') {
+            content += 1;
+        }
+        self.containers.push((Container::Quote, content));
+    }
+
+    /// Enters the list item whose marker is at `start`, after any indentation
+    fn enter_item(&mut self, start: usize) {
+        let line_start = self.source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = self.source[start..]
+            .find('\n')
+            .map_or(self.source.len(), |i| start + i);
+
+        let line = &self.source[start..line_end];
+        let marker = start + line.len() - line.trim_start_matches(' ').len();
+        let digits = self.source[marker..line_end]
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .count();
+        let after_marker = self
+            .source
+            .get(marker + digits + 1..line_end)
+            .unwrap_or("")
+            .trim_end_matches('\r');
+        let spaces = after_marker.len() - after_marker.trim_start_matches(' ').len();
+        // Content after more than four spaces is indented code one column in
+        let padding = if spaces == after_marker.len() || spaces > 4 {
+            1
+        } else {
+            spaces
+        };
+        let content = marker + digits + 1 + padding;
+
+        // Where the parent's content starts on this line
+        let parent = match self.containers.last() {
+            Some(&(_, parent)) if parent >= line_start => parent,
+            _ => {
+                let line = &self.source[line_start..line_end];
+                line_start + line.len() - self.strip_container_prefix(line).len()
+            }
+        };
+        // An empty first line leaves nothing of the item on it
+        self.containers.push((
+            Container::Item(content.saturating_sub(parent)),
+            content.min(line_end),
+        ));
+    }
+
+    fn clean_lines(&self, range: Range<usize>) -> Vec<String> {
+        self.source[range]
+            .trim_end()
+            .lines()
+            .enumerate()
+            .map(|(index, line)| {
+                if index == 0 {
+                    line.trim_end().to_string()
+                } else {
+                    self.strip_container_prefix(line).trim_end().to_string()
+                }
+            })
+            .collect()
+    }
+
+    fn expect_end(&mut self) {
+        if let Some((Event::End(_), _)) = self.events.get(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_to_end(&mut self) {
+        let mut depth = 1;
+        while let Some((event, _)) = self.events.get(self.pos) {
+            self.pos += 1;
+            match event {
+                Event::Start(_) => depth += 1,
+                Event::End(_) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn is_inline(event: &Event) -> bool {
+    match event {
+        // Inline HTML spanning lines comes as `Html`; HTML blocks are read whole
+        Event::Text(_)
+        | Event::Code(_)
+        | Event::InlineHtml(_)
+        | Event::Html(_)
+        | Event::InlineMath(_)
+        | Event::FootnoteReference(_)
+        | Event::SoftBreak
+        | Event::HardBreak
+        | Event::TaskListMarker(_) => true,
+        Event::Start(tag) => matches!(
+            tag,
+            Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Link { .. } | Tag::Image { .. }
+        ),
+        Event::End(tag) => matches!(
+            tag,
+            TagEnd::Emphasis
+                | TagEnd::Strong
+                | TagEnd::Strikethrough
+                | TagEnd::Link
+                | TagEnd::Image
+        ),
+        _ => false,
+    }
+}
+
+/// Joins rendered blocks, separating them with blank lines unless `tight`
+fn join_blocks(blocks: Vec<Block>, tight: bool) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut previous: Option<BlockKind> = None;
+
+    for mut block in blocks {
+        if block.lines.is_empty() {
+            continue;
+        }
+
+        if previous.is_some() && !tight {
+            lines.push(String::new());
+        }
+
+        // "---" right under text would turn it into a setext heading
+        if tight && block.kind == BlockKind::ThematicBreak && previous == Some(BlockKind::Inline) {
+            block.lines = vec!["***".to_string()];
+        }
+
+        previous = Some(block.kind);
+        lines.append(&mut block.lines);
+    }
+
+    lines
+}
+
+fn heading_level(level: HeadingLevel) -> usize {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+fn fenced_code(code: &str, info: &str) -> Vec<String> {
+    // Tildes are needed when the info string itself contains a backtick
+    let fence_char = if info.contains('`') { '~' } else { '`' };
+
+    let longest_run = code
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            trimmed.len() - trimmed.trim_start_matches(fence_char).len()
+        })
+        .max()
+        .unwrap_or(0);
+    let fence = fence_char.to_string().repeat(longest_run.max(2) + 1);
+
+    let mut lines = vec![format!("{}{}", fence, info)];
+    lines.extend(
+        code.strip_suffix('\n')
+            .unwrap_or(code)
+            .split('\n')
+            .map(str::to_string),
+    );
+    if code.is_empty() {
+        lines.pop();
+    }
+    lines.push(fence);
+    lines
+}
+
+fn pad_cell(cell: &str, width: usize, alignment: Alignment) -> String {
+    let padding = width.saturating_sub(cell.chars().count());
+    match alignment {
+        Alignment::Right => format!("{}{}", " ".repeat(padding), cell),
+        Alignment::Center => format!(
+            "{}{}{}",
+            " ".repeat(padding / 2),
+            cell,
+            " ".repeat(padding - padding / 2)
+        ),
+        _ => format!("{}{}", cell, " ".repeat(padding)),
+    }
+}
+
+/// Keeps the author's line breaks, except where trimming a continuation line
+/// would let it start a new block
+fn keep_lines(segment: Vec<String>) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for line in segment {
+        match lines.last_mut() {
+            Some(previous) if !can_start_line(&line) => {
+                previous.push(' ');
+                previous.push_str(&line);
+            }
+            _ => lines.push(line),
+        }
+    }
+
+    lines
+}
+
+/// Fills lines up to `width` columns without breaking inside code spans or
+/// `<...>` destinations, and without starting a line that would parse as a new block
+fn reflow(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    for word in split_words(text) {
+        // A lone tag on the first line would turn the paragraph into an HTML block
+        let lone_tag = lines.is_empty() && current.starts_with('<') && !current.contains(' ');
+
+        if current.is_empty() {
+            current = word;
+        } else if current.chars().count() + 1 + word.chars().count() <= width
+            || !can_start_line(&word)
+            || lone_tag
+        {
+            current.push(' ');
+            current.push_str(&word);
+        } else {
+            lines.push(std::mem::take(&mut current));
+            current = word;
+        }
+    }
+
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    lines
+}
+
+fn split_words(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '\\' && i + 1 < chars.len() {
+            word.push(c);
+            word.push(chars[i + 1]);
+            i += 2;
+            continue;
+        }
+
+        if c.is_whitespace() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            i += 1;
+            continue;
+        }
+
+        // Keep code spans whole; their inner spacing is significant
+        if c == '`' {
+            let run = chars[i..].iter().take_while(|&&ch| ch == '`').count();
+            if let Some(end) = find_backtick_run(&chars, i + run, run) {
+                word.extend(&chars[i..end + run]);
+                i = end + run;
+                continue;
+            }
+            word.extend(&chars[i..i + run]);
+            i += run;
+            continue;
+        }
+
+        // Autolinks, inline HTML tags and <...> link destinations
+        if c == '<' {
+            if let Some(close) = chars[i..].iter().position(|&ch| ch == '>') {
+                word.extend(&chars[i..i + close + 1]);
+                i += close + 1;
+                continue;
+            }
+        }
+
+        word.push(c);
+        i += 1;
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+fn find_backtick_run(chars: &[char], from: usize, run: usize) -> Option<usize> {
+    let mut i = from;
+    while i < chars.len() {
+        if chars[i] == '`' {
+            let length = chars[i..].iter().take_while(|&&ch| ch == '`').count();
+            if length == run {
+                return Some(i);
+            }
+            i += length;
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+/// Returns false for words that would start a list, heading, quote or other block
+fn can_start_line(word: &str) -> bool {
+    let first = match word.chars().next() {
+        Some(first) => first,
+        None => return true,
+    };
+
+    if matches!(
+        first,
+        '#' | '>' | '-' | '+' | '*' | '=' | '|' | '`' | '~' | '<' | '_'
+    ) {
+        return false;
+    }
+
+    let digits = word.chars().take_while(|c| c.is_ascii_digit()).count();
+    !(digits > 0 && matches!(word[digits..].chars().next(), Some('.') | Some(')')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENTS: [&str; 12] = [
+        // Lists
+        "* one\n* two\n    * nested\n    * items\n\n+ other marker\n",
+        "1) first\n2) second\n\n   continued paragraph\n\n10. ten\n11. eleven\n",
+        "-   wide marker\n    continued\n-\n    empty first line\n",
+        "- item\n\n      indented code\n- [ ] task\n- [x] done\n",
+        // Block quotes
+        "> quote\nlazy line\n> > nested\n> > quote\n>\n> - list\n>   in quote\n",
+        "  > indented\n  > quote\n",
+        // Tables
+        "| a | b |\n|:--|--:|\n| `x \\| y` | **z** |\n",
+        // Fences
+        "```rust\nfn main() {\n    ```\n}\n```\n\n~~~\n``` inside\n~~~\n",
+        "- list\n\n  ```\n    kept indent\n  ```\n\n> ```\n>   quoted\n> ```\n",
+        // Code spans and inline HTML across lines
+        "a `code\n   span` b\n",
+        "- a `code\n     span` b <span\n       title=x>c</span>\n",
+        "> a `code\n>    span` b\n",
+    ];
+
+    fn format(content: &str, wrap: Wrap) -> String {
+        format_markdown(
+            content,
+            &FormatOptions {
+                wrap,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Line breaks inside paragraphs become spaces when wrapping
+    fn collapse_whitespace(html: &str) -> String {
+        html.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    #[test]
+    fn formatting_twice_changes_nothing() {
+        for wrap in [Wrap::Preserve, Wrap::Never, Wrap::Width(20)] {
+            for document in DOCUMENTS {
+                let once = format(document, wrap);
+                assert_eq!(format(&once, wrap), once, "{:?} with {:?}", document, wrap);
+            }
+        }
+    }
+
+    #[test]
+    fn formatting_keeps_the_rendered_html() {
+        for document in DOCUMENTS {
+            let original = markdown::parse_to_html(document);
+            let formatted = format(document, Wrap::Preserve);
+            assert_eq!(
+                markdown::parse_to_html(&formatted),
+                original,
+                "{}",
+                formatted
+            );
+
+            for wrap in [Wrap::Never, Wrap::Width(20)] {
+                let formatted = format(document, wrap);
+                assert_eq!(
+                    collapse_whitespace(&markdown::parse_to_html(&formatted)),
+                    collapse_whitespace(&original),
+                    "{}",
+                    formatted
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn code_spans_keep_their_continuation_indent() {
+        assert_eq!(
+            format("- a `x\n     y` b\n", Wrap::Preserve),
+            "- a `x\n     y` b\n"
+        );
+        assert_eq!(
+            format("> a `x\n>    y` b\n", Wrap::Never),
+            "> a `x    y` b\n"
+        );
+    }
+}
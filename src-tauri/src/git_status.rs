@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// How a file differs from the last commit, as `git status` reports it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GitStatus {
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Untracked,
+    Conflicted,
+}
+
+/// The `git status` of each repository, kept until the file watcher sees a
+/// change in it or Git rewrites its index, e.g. on a commit
+#[derive(Default)]
+pub struct GitStatuses(Mutex<HashMap<PathBuf, CachedStatus>>);
+
+struct CachedStatus {
+    index_modified: Option<SystemTime>,
+    status: Arc<StatusMap>,
+}
+
+impl GitStatuses {
+    /// The status of the repository `dir` is in. Empty when `dir` isn't in a
+    /// Git repository or Git isn't installed.
+    pub fn for_directory(&self, dir: &Path) -> Arc<StatusMap> {
+        let dir = std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf());
+        let Some(root) = dir
+            .ancestors()
+            .find(|ancestor| ancestor.join(".git").exists())
+        else {
+            return Arc::default();
+        };
+        let index_modified = fs::metadata(root.join(".git/index"))
+            .and_then(|metadata| metadata.modified())
+            .ok();
+
+        // Held while Git runs, so folders expanded together share one run
+        let mut statuses = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(cached) = statuses.get(root) {
+            if cached.index_modified == index_modified {
+                return cached.status.clone();
+            }
+        }
+
+        let status = Arc::new(StatusMap::for_repository(root));
+        statuses.insert(
+            root.to_path_buf(),
+            CachedStatus {
+                index_modified,
+                status: status.clone(),
+            },
+        );
+        status
+    }
+
+    /// Forgets the status of the repository `path` is in, after it changed on disk
+    pub fn invalidate(&self, path: &Path) {
+        let mut statuses = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        statuses.retain(|root, _| !path.starts_with(root));
+    }
+}
+
+/// The changed files in one repository
+#[derive(Debug, Default)]
+pub struct StatusMap {
+    changes: HashMap<PathBuf, GitStatus>,
+}
+
+impl StatusMap {
+    /// Runs `git status` for the whole repository at `root`
+    fn for_repository(root: &Path) -> StatusMap {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(["status", "--porcelain=v1", "-z", "--untracked-files=all"])
+            .output();
+        let output = match output {
+            Ok(output) if output.status.success() => output.stdout,
+            _ => return StatusMap::default(),
+        };
+
+        StatusMap {
+            changes: parse_porcelain(&String::from_utf8_lossy(&output), root),
+        }
+    }
+
+    /// The status of a file, or for a folder a summary of what changed inside it:
+    /// untracked if everything in it is new, modified otherwise
+    pub fn status(&self, path: &Path, is_directory: bool) -> Option<GitStatus> {
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        if !is_directory {
+            return self.changes.get(&path).copied();
+        }
+
+        let mut inside = self
+            .changes
+            .iter()
+            .filter(|(changed, _)| changed.starts_with(&path))
+            .map(|(_, &status)| status)
+            .peekable();
+        inside.peek()?;
+        if inside.all(|status| status == GitStatus::Untracked) {
+            Some(GitStatus::Untracked)
+        } else {
+            Some(GitStatus::Modified)
+        }
+    }
+}
+
+/// Parses `git status --porcelain -z`, whose paths are relative to the repository root
+fn parse_porcelain(output: &str, root: &Path) -> HashMap<PathBuf, GitStatus> {
+    let mut changes = HashMap::new();
+    let mut records = output.split('\0');
+
+    while let Some(record) = records.next() {
+        if record.len() < 4 {
+            continue;
+        }
+        let (code, path) = record.split_at(3);
+        let mut code = code.chars();
+        let (index, worktree) = (code.next().unwrap_or(' '), code.next().unwrap_or(' '));
+
+        let status = match (index, worktree) {
+            ('?', '?') => GitStatus::Untracked,
+            ('!', '!') => continue,
+            ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D') => GitStatus::Conflicted,
+            ('R', _) | ('C', _) => {
+                // Renames and copies are followed by the path they came from
+                records.next();
+                GitStatus::Renamed
+            }
+            ('A', _) => GitStatus::Added,
+            ('D', _) | (_, 'D') => GitStatus::Deleted,
+            _ => GitStatus::Modified,
+        };
+        changes.insert(root.join(path), status);
+    }
+
+    changes
+}
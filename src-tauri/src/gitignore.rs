@@ -0,0 +1,223 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::files::SKIPPED_DIRECTORIES;
+
+/// Ignore files read in each directory. `.ignore` is listed last so it
+/// overrides `.gitignore`, as in ripgrep.
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+
+/// The ignore rules that apply inside one directory: those from its own
+/// ignore files and its ancestors', plus the user's exclude list
+#[derive(Debug, Clone)]
+pub struct Ignore {
+    /// Lowest precedence first
+    rules: Vec<Arc<Gitignore>>,
+    exclude: Option<Arc<Gitignore>>,
+    /// `.gitignore` files only count inside a Git repository
+    in_repository: bool,
+    /// Off when only the exclude list applies
+    read_files: bool,
+}
+
+impl Ignore {
+    /// Just the exclude list: gitignore-style patterns that apply at any depth
+    pub fn exclude_only(exclude: &[String]) -> Ignore {
+        Ignore {
+            rules: Vec::new(),
+            exclude: build_exclude(exclude).map(Arc::new),
+            in_repository: false,
+            read_files: false,
+        }
+    }
+
+    /// Collects the rules from the ignore files in `dir` and every directory
+    /// above it, plus the exclude list
+    pub fn for_directory(dir: &Path, exclude: &[String]) -> Ignore {
+        let dir = std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf());
+        let mut ancestors: Vec<&Path> = dir.ancestors().collect();
+        ancestors.reverse();
+
+        let repository = ancestors
+            .iter()
+            .rposition(|ancestor| ancestor.join(".git").exists());
+
+        let mut ignore = Ignore::exclude_only(exclude);
+        ignore.read_files = true;
+
+        for (index, ancestor) in ancestors.iter().enumerate() {
+            if Some(index) == repository {
+                ignore.in_repository = true;
+                ignore.add_file(ancestor, &ancestor.join(".git/info/exclude"));
+            }
+            ignore.add_directory(ancestor);
+        }
+        ignore
+    }
+
+    /// The rules for a subdirectory, adding its own ignore files
+    pub fn descend(&self, dir: &Path) -> Ignore {
+        let mut ignore = self.clone();
+        if !ignore.read_files {
+            return ignore;
+        }
+        if dir.join(".git").exists() {
+            ignore.in_repository = true;
+            ignore.add_file(dir, &dir.join(".git/info/exclude"));
+        }
+        ignore.add_directory(dir);
+        ignore
+    }
+
+    /// Whether `path`, a direct child of the directory these rules are for, is ignored
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+
+        if let Some(exclude) = &self.exclude {
+            if exclude.matched(&path, is_dir).is_ignore() {
+                return true;
+            }
+        }
+
+        // The last matching pattern of the most specific file wins
+        self.rules
+            .iter()
+            .rev()
+            .find_map(|rules| match rules.matched(&path, is_dir) {
+                Match::None => None,
+                Match::Ignore(_) => Some(true),
+                Match::Whitelist(_) => Some(false),
+            })
+            .unwrap_or(false)
+    }
+
+    fn add_directory(&mut self, dir: &Path) {
+        for name in IGNORE_FILES {
+            if name == ".gitignore" && !self.in_repository {
+                continue;
+            }
+            self.add_file(dir, &dir.join(name));
+        }
+    }
+
+    /// Adds the patterns of `file`, relative to `base`
+    fn add_file(&mut self, base: &Path, file: &Path) {
+        if !file.is_file() {
+            return;
+        }
+
+        let mut builder = GitignoreBuilder::new(base);
+        if let Some(e) = builder.add(file) {
+            eprintln!("Problem in {}: {}", file.display(), e);
+        }
+        match builder.build() {
+            Ok(rules) if !rules.is_empty() => self.rules.push(Arc::new(rules)),
+            Ok(_) => {}
+            Err(e) => eprintln!("Failed to read {}: {}", file.display(), e),
+        }
+    }
+}
+
+/// Decides whether paths anywhere below a workspace root are left out of it,
+/// for code that sees paths one at a time, like the file watcher. Hidden
+/// entries and `SKIPPED_DIRECTORIES` always are.
+pub struct IgnoreTree {
+    root: PathBuf,
+    exclude: Vec<String>,
+    respect_ignore_files: bool,
+    /// The rules of each folder seen so far
+    rules: HashMap<PathBuf, Ignore>,
+}
+
+impl IgnoreTree {
+    pub fn new(root: &Path, exclude: &[String], respect_ignore_files: bool) -> IgnoreTree {
+        IgnoreTree {
+            root: root.to_path_buf(),
+            exclude: exclude.to_vec(),
+            respect_ignore_files,
+            rules: HashMap::new(),
+        }
+    }
+
+    /// Whether `path` or a folder between it and the root is left out. Paths
+    /// outside the root are not.
+    pub fn is_ignored(&mut self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+
+        let mut dir = self.root.clone();
+        let mut components = relative.components().peekable();
+        while let Some(component) = components.next() {
+            let name = component.as_os_str().to_string_lossy();
+            let child = dir.join(component);
+            // Only the last component may be a file; a deleted one counts as a file
+            let is_dir = components.peek().is_some() || child.is_dir();
+
+            if name.starts_with('.') || (is_dir && SKIPPED_DIRECTORIES.contains(&name.as_ref())) {
+                return true;
+            }
+            if self.rules_for(&dir).is_ignored(&child, is_dir) {
+                return true;
+            }
+            dir = child;
+        }
+        false
+    }
+
+    /// Forgets the rules read so far, after an ignore file changed
+    pub fn reload(&mut self) {
+        self.rules.clear();
+    }
+
+    fn rules_for(&mut self, dir: &Path) -> &Ignore {
+        if !self.rules.contains_key(dir) {
+            let rules = match dir.parent() {
+                Some(parent) if dir != self.root => self.rules_for(parent).descend(dir),
+                _ if self.respect_ignore_files => Ignore::for_directory(dir, &self.exclude),
+                _ => Ignore::exclude_only(&self.exclude),
+            };
+            self.rules.insert(dir.to_path_buf(), rules);
+        }
+        &self.rules[dir]
+    }
+}
+
+/// Whether a change to `path` can change what is ignored
+pub fn is_ignore_file(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| IGNORE_FILES.iter().any(|file| name == *file))
+}
+
+/// Compiles the exclude list. Unlike an ignore file it has no directory of its
+/// own, so patterns with a slash may start at any directory.
+fn build_exclude(exclude: &[String]) -> Option<Gitignore> {
+    let mut builder = GitignoreBuilder::new("");
+    for line in exclude {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (negation, pattern) = match line.strip_prefix('!') {
+            Some(pattern) => ("!", pattern),
+            None => ("", line),
+        };
+        let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+        let anchored = pattern.trim_end_matches('/').contains('/');
+        let line = if anchored && !pattern.starts_with("**/") {
+            format!("{}**/{}", negation, pattern)
+        } else {
+            format!("{}{}", negation, pattern)
+        };
+
+        if let Err(e) = builder.add_line(None, &line) {
+            eprintln!("Skipping exclude pattern {}: {}", line, e);
+        }
+    }
+
+    builder.build().ok().filter(|exclude| !exclude.is_empty())
+}
@@ -0,0 +1,248 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Extension of the gzip-compressed snapshot files
+const SNAPSHOT_EXTENSION: &str = ".md.gz";
+
+/// The `[history]` section of `.mdox.toml`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct HistoryOptions {
+    pub enabled: bool,
+    /// Versions kept per file; the oldest are dropped first
+    pub max_versions: usize,
+    /// Versions older than this are dropped, except the newest one
+    pub max_age_days: u64,
+}
+
+impl Default for HistoryOptions {
+    fn default() -> Self {
+        HistoryOptions {
+            enabled: true,
+            max_versions: 50,
+            max_age_days: 30,
+        }
+    }
+}
+
+/// A saved version of a file
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryVersion {
+    pub id: String,
+    /// Milliseconds since the Unix epoch
+    pub timestamp: u64,
+    /// Size of the uncompressed content in bytes
+    pub size: u64,
+}
+
+/// Snapshots what is on disk at `path`, dated by its modification time. Called
+/// before a save so the version it replaces can always be restored.
+pub fn snapshot_file(store: &Path, path: &str, options: &HistoryOptions) -> Result<(), String> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Ok(());
+    };
+    let modified = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .unwrap_or_else(|_| SystemTime::now());
+
+    record(store, path, &content, millis(modified), options)
+}
+
+/// Snapshots `content` as the newest version of `path`
+pub fn snapshot(
+    store: &Path,
+    path: &str,
+    content: &str,
+    options: &HistoryOptions,
+) -> Result<(), String> {
+    record(store, path, content, millis(SystemTime::now()), options)
+}
+
+fn record(
+    store: &Path,
+    path: &str,
+    content: &str,
+    timestamp: u64,
+    options: &HistoryOptions,
+) -> Result<(), String> {
+    if !options.enabled {
+        return Ok(());
+    }
+
+    let dir = file_dir(store, path);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create history folder: {}", e))?;
+    // Lets the store be browsed by hand
+    fs::write(dir.join("path.txt"), path)
+        .map_err(|e| format!("Failed to write history index: {}", e))?;
+
+    write_snapshot(&dir, content, timestamp)?;
+    prune(&dir, options)
+}
+
+/// Lists the saved versions of `path`, newest first
+pub fn list_versions(store: &Path, path: &str) -> Result<Vec<HistoryVersion>, String> {
+    let dir = file_dir(store, path);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(&dir).map_err(|e| format!("Failed to read history: {}", e))?;
+
+    let mut versions = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(id) = name.strip_suffix(SNAPSHOT_EXTENSION) else {
+            continue;
+        };
+        let Some((timestamp, _)) = parse_id(id) else {
+            continue;
+        };
+
+        versions.push(HistoryVersion {
+            id: id.to_string(),
+            timestamp,
+            size: read_snapshot(&entry.path())
+                .map(|content| content.len() as u64)
+                .unwrap_or(0),
+        });
+    }
+
+    versions.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then(b.id.cmp(&a.id)));
+    Ok(versions)
+}
+
+/// Reads the content of one version of `path`
+pub fn read_version(store: &Path, path: &str, id: &str) -> Result<String, String> {
+    if parse_id(id).is_none() {
+        return Err(format!("Invalid history version: {}", id));
+    }
+
+    let snapshot = file_dir(store, path).join(format!("{}{}", id, SNAPSHOT_EXTENSION));
+    if !snapshot.is_file() {
+        return Err(format!("History version not found: {}", id));
+    }
+
+    read_snapshot(&snapshot)
+}
+
+/// A unified diff from a saved version to `current`
+pub fn diff_version(store: &Path, path: &str, id: &str, current: &str) -> Result<String, String> {
+    let version = read_version(store, path, id)?;
+    Ok(diffy::create_patch(&version, current).to_string())
+}
+
+/// History is kept per file, in a folder named after a hash of its path
+fn file_dir(store: &Path, path: &str) -> PathBuf {
+    let path = Path::new(path)
+        .canonicalize()
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|_| path.to_string());
+
+    let digest = Sha256::digest(path.as_bytes());
+    store.join(hex(&digest[..16]))
+}
+
+/// Snapshot ids are `<milliseconds>-<content hash>`, so unchanged content isn't stored twice
+fn write_snapshot(dir: &Path, content: &str, timestamp: u64) -> Result<(), String> {
+    let content_hash = hex(&Sha256::digest(content.as_bytes())[..4]);
+
+    let newest = newest_id(dir)?;
+    if newest.as_deref().and_then(parse_id).map(|(_, hash)| hash) == Some(content_hash.as_str()) {
+        return Ok(());
+    }
+
+    let snapshot = dir.join(format!(
+        "{}-{}{}",
+        timestamp, content_hash, SNAPSHOT_EXTENSION
+    ));
+    let file = fs::File::create(&snapshot)
+        .map_err(|e| format!("Failed to write history snapshot: {}", e))?;
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    encoder
+        .write_all(content.as_bytes())
+        .and_then(|_| encoder.finish().map(|_| ()))
+        .map_err(|e| format!("Failed to write history snapshot: {}", e))
+}
+
+fn read_snapshot(snapshot: &Path) -> Result<String, String> {
+    let file =
+        fs::File::open(snapshot).map_err(|e| format!("Failed to read history snapshot: {}", e))?;
+    let mut content = String::new();
+    GzDecoder::new(file)
+        .read_to_string(&mut content)
+        .map_err(|e| format!("Failed to read history snapshot: {}", e))?;
+    Ok(content)
+}
+
+fn newest_id(dir: &Path) -> Result<Option<String>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read history: {}", e))?;
+
+    Ok(entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let id = name.strip_suffix(SNAPSHOT_EXTENSION)?.to_string();
+            parse_id(&id).map(|(timestamp, _)| (timestamp, id.clone()))
+        })
+        .max()
+        .map(|(_, id)| id))
+}
+
+/// Drops versions beyond the configured count or age, always keeping the newest
+fn prune(dir: &Path, options: &HistoryOptions) -> Result<(), String> {
+    // An age too large to represent never prunes anything
+    let cutoff = SystemTime::now()
+        .checked_sub(Duration::from_secs(
+            options.max_age_days.saturating_mul(24 * 60 * 60),
+        ))
+        .map(millis)
+        .unwrap_or(0);
+
+    let versions = fs::read_dir(dir).map_err(|e| format!("Failed to read history: {}", e))?;
+    let mut versions: Vec<(u64, PathBuf)> = versions
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let (timestamp, _) = parse_id(name.strip_suffix(SNAPSHOT_EXTENSION)?)?;
+            Some((timestamp, entry.path()))
+        })
+        .collect();
+    versions.sort_by(|a, b| b.cmp(a));
+
+    for (index, (timestamp, snapshot)) in versions.iter().enumerate() {
+        let keep = index == 0 || (index < options.max_versions && *timestamp >= cutoff);
+        if !keep {
+            fs::remove_file(snapshot)
+                .map_err(|e| format!("Failed to remove old history snapshot: {}", e))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Splits an id into its timestamp and content hash, rejecting anything else
+/// so ids from the frontend can't point outside the history folder
+fn parse_id(id: &str) -> Option<(u64, &str)> {
+    let (timestamp, hash) = id.split_once('-')?;
+    if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some((timestamp.parse().ok()?, hash))
+}
+
+fn millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
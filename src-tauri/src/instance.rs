@@ -0,0 +1,174 @@
+#[cfg(not(windows))]
+use interprocess::local_socket::GenericFilePath;
+#[cfg(windows)]
+use interprocess::local_socket::GenericNamespaced;
+use interprocess::local_socket::{prelude::*, Listener, ListenerOptions, Name, Stream};
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::cli::OpenTarget;
+
+/// What a new `mdox` process asks the running one to open
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OpenRequest {
+    pub targets: Vec<OpenTarget>,
+    pub stdin: Option<String>,
+}
+
+impl OpenRequest {
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty() && self.stdin.is_none()
+    }
+}
+
+/// How long a connecting instance has to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The socket the running instance listens on, one per user
+#[cfg(windows)]
+fn socket_name() -> io::Result<Name<'static>> {
+    let user = std::env::var("USERNAME").unwrap_or_else(|_| "default".to_string());
+    format!("mdox-{}.sock", user).to_ns_name::<GenericNamespaced>()
+}
+
+/// The socket the running instance listens on, in a folder only this user can open
+#[cfg(not(windows))]
+fn socket_name() -> io::Result<Name<'static>> {
+    private_directory()?
+        .join("mdox.sock")
+        .to_fs_name::<GenericFilePath>()
+}
+
+/// The per-user runtime directory, or else a folder of our own in the shared
+/// temp directory. Anyone can create that folder first, so it is only used
+/// if it belongs to this user and no one else can open it.
+#[cfg(not(windows))]
+fn private_directory() -> io::Result<std::path::PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+        return Ok(dir.into());
+    }
+
+    // SAFETY: geteuid has no preconditions and can't fail
+    let uid = unsafe { libc::geteuid() };
+    let dir = std::env::temp_dir().join(format!("mdox-{}", uid));
+    match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e),
+    }
+
+    let metadata = std::fs::symlink_metadata(&dir)?;
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+        return Err(io::Error::new(
+            ErrorKind::PermissionDenied,
+            format!("{} is not private to this user", dir.display()),
+        ));
+    }
+    Ok(dir)
+}
+
+/// Hands a request to an already running instance. Returns false if none is running.
+pub fn forward(request: &OpenRequest) -> bool {
+    let Ok(name) = socket_name() else {
+        return false;
+    };
+    let Ok(stream) = Stream::connect(name) else {
+        return false;
+    };
+    let Ok(message) = serde_json::to_string(request) else {
+        return false;
+    };
+
+    let mut stream = BufReader::new(stream);
+    if stream
+        .get_mut()
+        .write_all(format!("{}\n", message).as_bytes())
+        .is_err()
+    {
+        return false;
+    }
+
+    // Only report success once the running instance has taken the request
+    let mut reply = String::new();
+    matches!(stream.read_line(&mut reply), Ok(read) if read > 0)
+}
+
+/// Claims the instance socket so later invocations forward to this process.
+/// Returns None if another instance holds it.
+pub fn listen() -> Option<Listener> {
+    let name = match socket_name() {
+        Ok(name) => name,
+        Err(e) => {
+            eprintln!("Failed to name instance socket: {}", e);
+            return None;
+        }
+    };
+
+    let mut listener = ListenerOptions::new().name(name.clone()).create_sync();
+
+    // A socket left by a crashed instance doesn't answer; one that does belongs
+    // to an instance that started since we tried to forward
+    if matches!(&listener, Err(e) if e.kind() == ErrorKind::AddrInUse)
+        && Stream::connect(name.clone()).is_err()
+    {
+        listener = ListenerOptions::new()
+            .name(name)
+            .try_overwrite(true)
+            .create_sync();
+    }
+
+    match listener {
+        Ok(listener) => Some(listener),
+        Err(e) => {
+            eprintln!("Failed to listen for other instances: {}", e);
+            None
+        }
+    }
+}
+
+/// Passes requests from later invocations to `on_request`. Each connection
+/// is read on its own thread, so a client that stalls holds up no one else.
+pub fn accept_requests<F>(listener: Listener, on_request: F)
+where
+    F: Fn(OpenRequest) + Send + Sync + 'static,
+{
+    let on_request = Arc::new(on_request);
+    thread::spawn(move || {
+        for connection in listener.incoming() {
+            let connection = match connection {
+                Ok(connection) => connection,
+                Err(e) => {
+                    eprintln!("Failed to accept instance connection: {}", e);
+                    continue;
+                }
+            };
+
+            let on_request = on_request.clone();
+            thread::spawn(move || {
+                if let Err(e) = connection.set_recv_timeout(Some(REQUEST_TIMEOUT)) {
+                    eprintln!("Failed to set a timeout for open requests: {}", e);
+                }
+
+                let mut reader = BufReader::new(connection);
+                let mut line = String::new();
+                if let Err(e) = reader.read_line(&mut line) {
+                    eprintln!("Failed to read open request: {}", e);
+                    return;
+                }
+
+                match serde_json::from_str::<OpenRequest>(&line) {
+                    Ok(request) => {
+                        let _ = reader.get_mut().write_all(b"ok\n");
+                        on_request(request);
+                    }
+                    Err(e) => eprintln!("Ignoring malformed open request: {}", e),
+                }
+            });
+        }
+    });
+}
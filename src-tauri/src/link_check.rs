@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::files;
+use crate::link_discovery;
+use crate::markdown;
+
+/// What a broken link points at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BrokenLinkKind {
+    File,
+    Anchor,
+    Image,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrokenLink {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub target: String,
+    pub kind: BrokenLinkKind,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkReport {
+    pub files_checked: usize,
+    pub links_checked: usize,
+    pub broken: Vec<BrokenLink>,
+}
+
+/// Checks every local link, `#fragment` and image in the Markdown files under `root`
+pub fn check_links(root: &str) -> Result<LinkReport, String> {
+    let documents = files::walk_markdown_files(root)?;
+    let root_path = Path::new(root);
+    let root_dir = if root_path.is_dir() {
+        root_path
+    } else {
+        root_path.parent().unwrap_or(Path::new("."))
+    };
+
+    let mut report = LinkReport {
+        files_checked: documents.len(),
+        links_checked: 0,
+        broken: Vec::new(),
+    };
+    // Heading IDs per target document, so each file is parsed at most once
+    let mut anchors: HashMap<PathBuf, Vec<String>> = HashMap::new();
+
+    for document in &documents {
+        let content = match files::read_file_contents(&document.to_string_lossy()) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Skipping {}: {}", document.display(), e);
+                continue;
+            }
+        };
+        let base_dir = document.parent().unwrap_or(Path::new("."));
+
+        for link in link_discovery::extract_document_links(&content) {
+            if link.url.is_empty() || link_discovery::is_external_link(&link.url) {
+                continue;
+            }
+            report.links_checked += 1;
+
+            let (link_path, fragment) = link_discovery::split_link_target(&link.url);
+
+            // Anchor-only links point into the document itself
+            let target = if link_path.is_empty() {
+                document.clone()
+            } else if let Some(absolute) = link_path.strip_prefix('/') {
+                root_dir.join(absolute)
+            } else {
+                base_dir.join(&link_path)
+            };
+
+            let broken = |kind, message: String| BrokenLink {
+                file: document.to_string_lossy().to_string(),
+                line: link.line,
+                column: link.column,
+                target: link.url.clone(),
+                kind,
+                message,
+            };
+
+            if !target.exists() {
+                let (kind, what) = if link.is_image {
+                    (BrokenLinkKind::Image, "Image")
+                } else {
+                    (BrokenLinkKind::File, "File")
+                };
+                report
+                    .broken
+                    .push(broken(kind, format!("{} not found: {}", what, link_path)));
+                continue;
+            }
+
+            let fragment = match fragment {
+                Some(fragment) if !fragment.is_empty() && !link.is_image => fragment,
+                _ => continue,
+            };
+
+            if !files::is_markdown_file(&target) {
+                continue;
+            }
+
+            let ids = anchors.entry(target.clone()).or_insert_with(|| {
+                std::fs::read_to_string(&target)
+                    .map(|text| markdown::heading_ids(&text))
+                    .unwrap_or_default()
+            });
+
+            if !ids.iter().any(|id| *id == fragment.to_lowercase()) {
+                report.broken.push(broken(
+                    BrokenLinkKind::Anchor,
+                    format!("No heading matches #{}", fragment),
+                ));
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Formats a report as `file:line:column: message` lines followed by a summary
+pub fn format_report(report: &LinkReport) -> String {
+    let mut output = String::new();
+
+    for broken in &report.broken {
+        output.push_str(&format!(
+            "{}:{}:{}: {} ({})\n",
+            broken.file, broken.line, broken.column, broken.message, broken.target
+        ));
+    }
+
+    output.push_str(&format!(
+        "Checked {} links in {} files, {} broken\n",
+        report.links_checked,
+        report.files_checked,
+        report.broken.len()
+    ));
+
+    output
+}
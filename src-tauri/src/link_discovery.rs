@@ -1,103 +1,351 @@
 use serde::{Deserialize, Serialize};
-use std::collections::{HashSet, VecDeque};
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
-use pulldown_cmark::{Parser, Event, Tag, TagEnd};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::SystemTime;
+use pulldown_cmark::{Parser, Event, LinkType, Tag, TagEnd};
+
+use crate::markdown::{self, LineIndex};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LinkedDocument {
     pub path: String,
     pub title: String,
+    /// The discovered documents (or the root) that link here, starting with
+    /// the one it was first reached from
+    pub parents: Vec<String>,
+    /// Links followed from the root to reach it, starting at 1
+    pub depth: usize,
+    /// The text of the link it was first reached through
+    pub link_text: String,
+    /// Whether following links from it can lead back to it. Loops through
+    /// documents deeper than the search went are not seen.
+    pub cyclic: bool,
 }
 
-/// Discovers all linked Markdown documents starting from a root file
-pub fn discover_linked_documents(root_path: &str, max_depth: usize) -> Result<Vec<LinkedDocument>, String> {
+/// Discovers all linked Markdown documents starting from a root file, in the
+/// order they are reached. `parents` and `depth` describe the tree. Each level
+/// of the search is read in parallel, and files that haven't changed since an
+/// earlier discovery come from `cache`.
+pub fn discover_linked_documents(
+    cache: &DiscoveryCache,
+    root_path: &str,
+    max_depth: usize,
+) -> Result<Vec<LinkedDocument>, String> {
     let root_path = PathBuf::from(root_path);
     
     if !root_path.exists() {
         return Err(format!("File does not exist: {}", root_path.display()));
     }
+    // Links resolve to canonical paths, so the root must match them
+    let root_path = root_path.canonicalize().unwrap_or(root_path);
     
-    let mut discovered = Vec::new();
-    let mut visited = HashSet::new();
-    let mut queue = VecDeque::new();
+    let mut discovered: Vec<LinkedDocument> = Vec::new();
+    // Where each document is in `discovered`; the root has none
+    let mut visited: HashMap<PathBuf, Option<usize>> = HashMap::new();
+    // Discovered documents that link back to the root
+    let mut root_parents = Vec::new();
     
     // Start with the root document
-    queue.push_back((root_path.clone(), 0));
-    visited.insert(root_path.clone());
+    visited.insert(root_path.clone(), None);
+    let mut frontier = vec![root_path.clone()];
     
-    while let Some((current_path, depth)) = queue.pop_front() {
-        if depth >= max_depth {
-            continue;
+    for depth in 0..=max_depth {
+        if frontier.is_empty() {
+            break;
+        }
+        let parsed = cache.parse_all(&frontier);
+        
+        // Documents are read once, for their title when they are discovered
+        // and for their links when the next level is found
+        for (path, document) in frontier.iter().zip(&parsed) {
+            let title = document.as_ref().and_then(|document| document.title.clone());
+            if let (Some(&Some(index)), Some(title)) = (visited.get(path), title) {
+                discovered[index].title = title;
+            }
         }
         
-        // Extract links from the current document
-        match extract_links(&current_path) {
-            Ok(links) => {
-                for link_path in links {
-                    if !visited.contains(&link_path) {
-                        visited.insert(link_path.clone());
+        // The last level's links only count for what is already discovered,
+        // so loops that close there are still marked
+        let mut next = Vec::new();
+        for (current_path, document) in frontier.iter().zip(parsed) {
+            let Some(document) = document else {
+                continue;
+            };
+            let current = current_path.to_string_lossy().to_string();
+            
+            for (link_path, link_text) in &document.links {
+                if link_path == current_path {
+                    continue;
+                }
+                
+                match visited.get(link_path) {
+                    // Seen before: only record the extra parent
+                    Some(&Some(index)) => {
+                        if !discovered[index].parents.contains(&current) {
+                            discovered[index].parents.push(current.clone());
+                        }
+                    }
+                    // Links back to the root only matter for finding loops
+                    Some(None) => root_parents.push(current.clone()),
+                    None if depth == max_depth => {}
+                    None => {
+                        visited.insert(link_path.clone(), Some(discovered.len()));
                         
-                        // Extract title from the document
-                        let title = extract_title(&link_path)
-                            .unwrap_or_else(|| {
-                                link_path
-                                    .file_name()
-                                    .and_then(|n| n.to_str())
-                                    .unwrap_or("Untitled")
-                                    .to_string()
-                            });
+                        // Replaced by the document's heading once it is read
+                        let title = link_path
+                            .file_name()
+                            .and_then(|n| n.to_str())
+                            .unwrap_or("Untitled")
+                            .to_string();
                         
                         discovered.push(LinkedDocument {
                             path: link_path.to_string_lossy().to_string(),
                             title,
+                            parents: vec![current.clone()],
+                            depth: depth + 1,
+                            link_text: link_text.clone(),
+                            cyclic: false,
                         });
-                        
-                        // Add to queue for traversal
-                        queue.push_back((link_path, depth + 1));
+                        next.push(link_path.clone());
                     }
                 }
             }
-            Err(e) => {
-                eprintln!("Error extracting links from {}: {}", current_path.display(), e);
-            }
         }
+        frontier = next;
     }
     
+    mark_cycles(&mut discovered, &root_path.to_string_lossy(), &root_parents);
     Ok(discovered)
 }
 
-/// Extracts all local Markdown file links from a document
-fn extract_links(file_path: &Path) -> Result<Vec<PathBuf>, String> {
+/// The links and title of a document, read in one pass
+#[derive(Debug, Default)]
+struct ParsedDocument {
+    /// Local Markdown files it links to, with the link text
+    links: Vec<(PathBuf, String)>,
+    title: Option<String>,
+}
+
+/// A document's modification time when it was parsed, and what it contained
+type CachedDocument = (Option<SystemTime>, Arc<ParsedDocument>);
+
+/// Most documents `DiscoveryCache` keeps before it starts over
+const MAX_CACHED_DOCUMENTS: usize = 10_000;
+
+/// Parsed documents, kept until the file's modification time changes or
+/// the file is gone
+#[derive(Default)]
+pub struct DiscoveryCache(Mutex<HashMap<PathBuf, CachedDocument>>);
+
+impl DiscoveryCache {
+    /// Parses each of `paths`, spreading the files that aren't cached over
+    /// several threads. Files that can't be read give `None`.
+    fn parse_all(&self, paths: &[PathBuf]) -> Vec<Option<Arc<ParsedDocument>>> {
+        let modified: Vec<Option<SystemTime>> = paths
+            .iter()
+            .map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+            .collect();
+
+        let mut parsed: Vec<Option<Arc<ParsedDocument>>> = {
+            let mut cache = self.0.lock().unwrap_or_else(|e| e.into_inner());
+            for (path, modified) in paths.iter().zip(&modified) {
+                if modified.is_none() {
+                    cache.remove(path);
+                }
+            }
+            paths
+                .iter()
+                .zip(&modified)
+                .map(|(path, modified)| match cache.get(path) {
+                    Some((cached_modified, document))
+                        if modified.is_some() && cached_modified == modified =>
+                    {
+                        Some(document.clone())
+                    }
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let missing: Vec<usize> = (0..paths.len()).filter(|&i| parsed[i].is_none()).collect();
+        if missing.is_empty() {
+            return parsed;
+        }
+
+        let threads = thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(missing.len());
+        let chunk_size = missing.len().div_ceil(threads);
+        let results: Vec<(usize, Result<ParsedDocument, String>)> = thread::scope(|scope| {
+            let workers: Vec<_> = missing
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|&i| (i, parse_document(&paths[i])))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap_or_default())
+                .collect()
+        });
+
+        let mut cache = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if cache.len() + missing.len() > MAX_CACHED_DOCUMENTS {
+            cache.clear();
+        }
+        for (i, result) in results {
+            match result {
+                Ok(document) => {
+                    let document = Arc::new(document);
+                    cache.insert(paths[i].clone(), (modified[i], document.clone()));
+                    parsed[i] = Some(document);
+                }
+                Err(e) => {
+                    cache.remove(&paths[i]);
+                    eprintln!("Error extracting links from {}: {}", paths[i].display(), e);
+                }
+            }
+        }
+
+        parsed
+    }
+}
+
+/// Marks the documents that lie on a loop of links, by finding the groups
+/// of documents that can all reach each other (Kosaraju's algorithm).
+/// Document `i` is node `i + 1`; node 0 is the root.
+fn mark_cycles(discovered: &mut [LinkedDocument], root: &str, root_parents: &[String]) {
+    let mut nodes: HashMap<&str, usize> = HashMap::new();
+    nodes.insert(root, 0);
+    for (index, document) in discovered.iter().enumerate() {
+        nodes.insert(&document.path, index + 1);
+    }
+
+    let count = discovered.len() + 1;
+    let mut outgoing = vec![Vec::new(); count];
+    let mut incoming = vec![Vec::new(); count];
+    let links = discovered
+        .iter()
+        .enumerate()
+        .flat_map(|(index, document)| document.parents.iter().map(move |parent| (parent, index + 1)))
+        .chain(root_parents.iter().map(|parent| (parent, 0)));
+    for (parent, to) in links {
+        if let Some(&from) = nodes.get(parent.as_str()) {
+            outgoing[from].push(to);
+            incoming[to].push(from);
+        }
+    }
+
+    // Order the nodes by when their depth-first search finishes
+    let mut finished = Vec::with_capacity(count);
+    let mut seen = vec![false; count];
+    for start in 0..count {
+        if seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        let mut stack = vec![(start, 0)];
+        while let Some((node, next)) = stack.pop() {
+            if let Some(&child) = outgoing[node].get(next) {
+                stack.push((node, next + 1));
+                if !seen[child] {
+                    seen[child] = true;
+                    stack.push((child, 0));
+                }
+            } else {
+                finished.push(node);
+            }
+        }
+    }
+
+    // Going backwards from the last to finish collects one group at a time
+    let mut group = vec![usize::MAX; count];
+    let mut sizes = Vec::new();
+    for &start in finished.iter().rev() {
+        if group[start] != usize::MAX {
+            continue;
+        }
+        let id = sizes.len();
+        let mut size = 0;
+        let mut stack = vec![start];
+        group[start] = id;
+        while let Some(node) = stack.pop() {
+            size += 1;
+            for &parent in &incoming[node] {
+                if group[parent] == usize::MAX {
+                    group[parent] = id;
+                    stack.push(parent);
+                }
+            }
+        }
+        sizes.push(size);
+    }
+
+    for (index, document) in discovered.iter_mut().enumerate() {
+        document.cyclic = sizes[group[index + 1]] > 1;
+    }
+}
+
+/// Reads a document's local Markdown file links, with their text, and its
+/// title (the first heading)
+fn parse_document(file_path: &Path) -> Result<ParsedDocument, String> {
     let content = std::fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file: {}", e))?;
     
     let parser = Parser::new(&content);
-    let mut links = Vec::new();
+    let mut document = ParsedDocument::default();
     let base_dir = file_path.parent().unwrap_or(Path::new("."));
     
-    let mut in_link = false;
-    let mut link_url = String::new();
+    let mut link_url: Option<String> = None;
+    let mut link_text = String::new();
+    let mut in_heading = false;
+    let mut heading = String::new();
     
     for event in parser {
         match event {
             Event::Start(Tag::Link { dest_url, .. }) => {
-                in_link = true;
-                link_url = dest_url.to_string();
+                link_url = Some(dest_url.to_string());
             }
             Event::End(TagEnd::Link) => {
-                if in_link {
-                    if let Some(resolved) = resolve_link(&link_url, base_dir) {
-                        links.push(resolved);
-                    }
-                    in_link = false;
-                    link_url.clear();
+                if let Some(resolved) = link_url.take().and_then(|url| resolve_link(&url, base_dir)) {
+                    document.links.push((resolved, link_text.trim().to_string()));
                 }
+                link_text.clear();
+            }
+            Event::Start(Tag::Heading { .. }) if document.title.is_none() => {
+                in_heading = true;
+            }
+            Event::End(TagEnd::Heading(_)) if in_heading => {
+                if !heading.is_empty() {
+                    document.title = Some(heading.trim().to_string());
+                }
+                in_heading = false;
+            }
+            Event::Text(text) => {
+                if link_url.is_some() {
+                    link_text.push_str(&text);
+                }
+                if in_heading {
+                    heading.push_str(&text);
+                }
+            }
+            Event::Code(text) if link_url.is_some() => {
+                link_text.push_str(&text);
             }
             _ => {}
         }
     }
     
-    Ok(links)
+    Ok(document)
 }
 
 /// Resolves a markdown link to an absolute path if it's a local .md file
@@ -139,32 +387,347 @@ fn resolve_link(link: &str, base_dir: &Path) -> Option<PathBuf> {
     None
 }
 
-/// Extracts the title from a Markdown document (first heading or filename)
-fn extract_title(file_path: &Path) -> Option<String> {
-    let content = std::fs::read_to_string(file_path).ok()?;
-    let parser = Parser::new(&content);
-    
-    let mut in_heading = false;
-    let mut title = String::new();
-    
-    for event in parser {
-        match event {
-            Event::Start(Tag::Heading { .. }) => {
-                in_heading = true;
-            }
-            Event::Text(text) if in_heading => {
-                title.push_str(&text);
+/// A link or image reference as written in a document, with its location
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentLink {
+    pub url: String,
+    pub line: usize,
+    pub column: usize,
+    pub is_image: bool,
+}
+
+/// Extracts every link and image destination from Markdown content,
+/// including external and broken ones
+pub fn extract_document_links(content: &str) -> Vec<DocumentLink> {
+    let line_index = LineIndex::new(content);
+    let mut links = Vec::new();
+
+    for (event, range) in Parser::new_ext(content, markdown::parser_options()).into_offset_iter() {
+        let (dest_url, is_image) = match event {
+            Event::Start(Tag::Link { dest_url, .. }) => (dest_url, false),
+            Event::Start(Tag::Image { dest_url, .. }) => (dest_url, true),
+            _ => continue,
+        };
+
+        let (line, column) = line_index.position(range.start);
+        links.push(DocumentLink {
+            url: dest_url.to_string(),
+            line,
+            column,
+            is_image,
+        });
+    }
+
+    links
+}
+
+/// Where a link destination is written in the source, so it can be replaced in place
+#[derive(Debug, Clone)]
+pub struct LinkDestination {
+    /// The destination with backslash escapes and character references decoded
+    pub url: String,
+    /// Byte range of the destination as written, without any `<>`
+    pub span: Range<usize>,
+    /// Written as `<...>`, which allows spaces
+    pub angle_brackets: bool,
+}
+
+/// A link the parser reported whose destination couldn't be found in the
+/// source, so it can't be rewritten in place
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnmatchedLink {
+    /// The destination as the parser decoded it
+    pub url: String,
+    /// Byte offset of the link or reference definition
+    pub offset: usize,
+}
+
+/// Finds the destinations of inline links and images and of reference
+/// definitions. Autolinks are skipped since they always carry a URL scheme.
+/// Destinations written in a way the source scan doesn't follow are returned
+/// separately.
+pub fn extract_link_destinations(content: &str) -> (Vec<LinkDestination>, Vec<UnmatchedLink>) {
+    let mut destinations = Vec::new();
+    let mut unmatched = Vec::new();
+    let mut parser = Parser::new_ext(content, markdown::parser_options()).into_offset_iter();
+
+    for (event, range) in parser.by_ref() {
+        let (link_type, dest_url) = match event {
+            Event::Start(Tag::Link { link_type, dest_url, .. })
+            | Event::Start(Tag::Image { link_type, dest_url, .. }) => (link_type, dest_url),
+            _ => continue,
+        };
+        if link_type != LinkType::Inline || dest_url.is_empty() {
+            continue;
+        }
+
+        // The link text may contain `](` as well, e.g. from a nested image,
+        // so take the last one that is followed by this destination
+        let found = content[range.clone()]
+            .match_indices("](")
+            .filter_map(|(offset, _)| parse_destination(content, range.start + offset + 2))
+            .filter(|destination| destination.url == *dest_url)
+            .last();
+        match found {
+            Some(destination) => destinations.push(destination),
+            None => unmatched.push(UnmatchedLink {
+                url: dest_url.to_string(),
+                offset: range.start,
+            }),
+        }
+    }
+
+    for (_, definition) in parser.reference_definitions().iter() {
+        let source = &content[definition.span.clone()];
+        let found = find_unescaped(source, "]:")
+            .and_then(|offset| parse_destination(content, definition.span.start + offset + 2))
+            .filter(|destination| destination.url == *definition.dest);
+        match found {
+            Some(destination) => destinations.push(destination),
+            None if !definition.dest.is_empty() => unmatched.push(UnmatchedLink {
+                url: definition.dest.to_string(),
+                offset: definition.span.start,
+            }),
+            None => {}
+        }
+    }
+
+    destinations.sort_by_key(|destination| destination.span.start);
+    destinations.dedup_by_key(|destination| destination.span.start);
+    unmatched.sort_by_key(|link| link.offset);
+    (destinations, unmatched)
+}
+
+/// Reads a link destination starting at `start`, after optional whitespace
+fn parse_destination(content: &str, start: usize) -> Option<LinkDestination> {
+    let rest = &content[start..];
+    let trimmed = rest.trim_start_matches([' ', '\t', '\n', '\r']);
+    let offset = start + rest.len() - trimmed.len();
+
+    if let Some(inner) = trimmed.strip_prefix('<') {
+        let end = find_unescaped(inner, ">")?;
+        if inner[..end].contains('\n') {
+            return None;
+        }
+        return Some(LinkDestination {
+            url: unescape(&inner[..end]),
+            span: offset + 1..offset + 1 + end,
+            angle_brackets: true,
+        });
+    }
+
+    let mut depth = 0;
+    let mut escaped = false;
+    let mut end = trimmed.len();
+    for (index, c) in trimmed.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '(' => depth += 1,
+            ')' if depth == 0 => {
+                end = index;
+                break;
             }
-            Event::End(TagEnd::Heading(_)) => {
-                if in_heading && !title.is_empty() {
-                    return Some(title.trim().to_string());
-                }
-                in_heading = false;
+            ')' => depth -= 1,
+            c if c.is_whitespace() || c.is_control() => {
+                end = index;
+                break;
             }
             _ => {}
         }
     }
-    
-    None
+
+    if end == 0 {
+        return None;
+    }
+    Some(LinkDestination {
+        url: unescape(&trimmed[..end]),
+        span: offset..offset + end,
+        angle_brackets: false,
+    })
 }
 
+fn find_unescaped(text: &str, pattern: &str) -> Option<usize> {
+    text.match_indices(pattern)
+        .map(|(index, _)| index)
+        .find(|&index| text[..index].chars().rev().take_while(|&c| c == '\\').count() % 2 == 0)
+}
+
+/// Drops the backslash from escaped ASCII punctuation and decodes character
+/// references, as CommonMark does
+fn unescape(raw: &str) -> String {
+    let mut unescaped = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(c) = rest.chars().next() {
+        if c == '\\' {
+            if let Some(next) = rest[1..]
+                .chars()
+                .next()
+                .filter(|c| c.is_ascii_punctuation())
+            {
+                unescaped.push(next);
+                rest = &rest[1 + next.len_utf8()..];
+                continue;
+            }
+        }
+        if c == '&' {
+            if let Some((decoded, length)) = decode_entity(rest) {
+                unescaped.push(decoded);
+                rest = &rest[length..];
+                continue;
+            }
+        }
+        unescaped.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    unescaped
+}
+
+/// Decodes a numeric or common named character reference at the start of
+/// `text`, returning the character and the length of the reference
+fn decode_entity(text: &str) -> Option<(char, usize)> {
+    let end = text.find(';')?;
+    let name = &text[1..end];
+    let decoded = if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) if (1..=6).contains(&hex.len()) => u32::from_str_radix(hex, 16).ok()?,
+            None if (1..=7).contains(&number.len()) => number.parse().ok()?,
+            _ => return None,
+        };
+        // Invalid code points become the replacement character, as in CommonMark
+        match code {
+            0 => '\u{FFFD}',
+            code => char::from_u32(code).unwrap_or('\u{FFFD}'),
+        }
+    } else {
+        match name {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            "nbsp" => '\u{A0}',
+            _ => return None,
+        }
+    };
+    Some((decoded, end + 1))
+}
+
+/// Returns true for links with a URL scheme (http:, mailto:, ...) rather than a path
+pub fn is_external_link(link: &str) -> bool {
+    match link.find(':') {
+        // A single letter before the colon is a Windows drive, not a scheme
+        Some(idx) if idx > 1 => link[..idx]
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.'),
+        _ => false,
+    }
+}
+
+/// Splits a local link into its decoded path and optional `#fragment`
+pub fn split_link_target(link: &str) -> (String, Option<String>) {
+    let (path, fragment) = match link.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment.to_string())),
+        None => (link, None),
+    };
+    let path = path.split('?').next().unwrap_or(path);
+
+    (percent_decode(path), fragment)
+}
+
+/// Decodes `%XX` escapes so links like `my%20notes.md` match files on disk
+pub fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Ok(byte) = u8::from_str_radix(input.get(i + 1..i + 3).unwrap_or(""), 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8(decoded).unwrap_or_else(|_| input.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `files` to a fresh folder and discovers from its `index.md`
+    fn discover(name: &str, files: &[(&str, &str)], max_depth: usize) -> Vec<(String, bool)> {
+        let dir =
+            std::env::temp_dir().join(format!("mdox-discovery-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (path, content) in files {
+            fs::write(dir.join(path), content).unwrap();
+        }
+
+        let root = dir.join("index.md");
+        let found = discover_linked_documents(
+            &DiscoveryCache::default(),
+            &root.to_string_lossy(),
+            max_depth,
+        )
+        .unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        found
+            .into_iter()
+            .map(|document| {
+                let name = Path::new(&document.path).file_name().unwrap();
+                (name.to_string_lossy().to_string(), document.cyclic)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn loops_are_marked() {
+        let found = discover(
+            "loops",
+            &[
+                ("index.md", "[a](a.md)"),
+                ("a.md", "[b](b.md)"),
+                ("b.md", "[a](a.md) [c](c.md)"),
+                ("c.md", ""),
+            ],
+            5,
+        );
+        assert_eq!(
+            found,
+            [
+                ("a.md".to_string(), true),
+                ("b.md".to_string(), true),
+                ("c.md".to_string(), false)
+            ]
+        );
+    }
+
+    #[test]
+    fn loops_that_close_at_the_last_level_are_marked() {
+        let files = [
+            ("index.md", "[a](a.md)"),
+            ("a.md", "[b](b.md) [home](index.md)"),
+            ("b.md", "[a](a.md) [c](c.md)"),
+            ("c.md", ""),
+        ];
+        assert_eq!(
+            discover("last-level", &files, 2),
+            [("a.md".to_string(), true), ("b.md".to_string(), true)]
+        );
+        assert_eq!(
+            discover("back-to-root", &files, 1),
+            [("a.md".to_string(), true)]
+        );
+    }
+}
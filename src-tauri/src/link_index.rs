@@ -0,0 +1,590 @@
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::files;
+use crate::link_discovery;
+use crate::markdown::{self, LineIndex};
+use crate::search;
+
+/// Characters of context kept on each side of a link
+const CONTEXT_LENGTH: usize = 60;
+
+/// Longest an index goes without scanning its files, for changes the file
+/// watcher doesn't see
+const RESCAN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A link to a document from another document in the workspace
+#[derive(Debug, Clone, Serialize)]
+pub struct Backlink {
+    /// The document the link is in
+    pub path: String,
+    pub title: String,
+    /// Starting at 1
+    pub line: usize,
+    pub column: usize,
+    /// The link text, without formatting
+    pub text: String,
+    /// The `#fragment` the link points at, if any
+    pub anchor: Option<String>,
+    /// The rest of the line before and after the link, as written
+    pub before: String,
+    pub link: String,
+    pub after: String,
+}
+
+/// The documents in a workspace and the links between them
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphNode {
+    pub path: String,
+    pub title: String,
+    pub tags: Vec<String>,
+}
+
+/// A link from the document at `source` to the one at `target`
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    pub anchor: Option<String>,
+    pub line: usize,
+}
+
+/// Documents that readers can't get to by following links, and documents
+/// that lead nowhere
+#[derive(Debug, Clone, Serialize)]
+pub struct OrphanReport {
+    pub files_checked: usize,
+    /// No other document links to them
+    pub orphans: Vec<ReportedDocument>,
+    /// Other documents link to them, but none that an entry point leads to
+    pub unreachable: Vec<ReportedDocument>,
+    /// They link to no other document
+    pub dead_ends: Vec<ReportedDocument>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportedDocument {
+    pub path: String,
+    pub title: String,
+}
+
+/// The link index of each workspace root
+#[derive(Default)]
+pub struct LinkIndexes(Mutex<HashMap<PathBuf, Arc<WorkspaceIndex>>>);
+
+/// One root's latest index and whether the files under it need scanning again
+struct WorkspaceIndex {
+    index: Mutex<Arc<LinkIndex>>,
+    /// When the files were last scanned, unless the watcher saw a change since
+    scanned: Mutex<Option<Instant>>,
+}
+
+impl LinkIndexes {
+    /// Brings the index of `root` up to date and returns the links to `path`
+    pub fn backlinks(&self, root: &str, path: &str) -> Result<Vec<Backlink>, String> {
+        let target = Path::new(path)
+            .canonicalize()
+            .map_err(|_| format!("File not found: {}", path))?;
+        Ok(self.index(root)?.backlinks(&target))
+    }
+
+    /// Brings the index of `root` up to date and returns its link graph
+    pub fn graph(&self, root: &str) -> Result<LinkGraph, String> {
+        Ok(self.index(root)?.graph())
+    }
+
+    /// Brings the index of `root` up to date and reports the documents no
+    /// link leads to, other than the `entry_points`
+    pub fn orphans(&self, root: &str, entry_points: &[String]) -> Result<OrphanReport, String> {
+        Ok(self.index(root)?.orphans(entry_points))
+    }
+
+    /// Marks the index of every root `path` is under as needing a scan, after
+    /// the file watcher saw it change
+    pub fn invalidate(&self, path: &Path) {
+        let path = search::resolve_changed_path(path);
+        let indexes = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        for (root, workspace) in indexes.iter() {
+            if path.starts_with(root) {
+                *workspace.scanned.lock().unwrap_or_else(|e| e.into_inner()) = None;
+            }
+        }
+    }
+
+    /// The index of `root`, rescanned first if files under it changed. The
+    /// scan works on a copy outside every lock, so lookups on other roots, and
+    /// on this one while it is up to date, aren't kept waiting.
+    fn index(&self, root: &str) -> Result<Arc<LinkIndex>, String> {
+        let root = Path::new(root)
+            .canonicalize()
+            .map_err(|_| format!("Directory not found: {}", root))?;
+
+        let workspace = {
+            let mut indexes = self.0.lock().unwrap_or_else(|e| e.into_inner());
+            indexes
+                .entry(root.clone())
+                .or_insert_with(|| {
+                    Arc::new(WorkspaceIndex {
+                        index: Mutex::new(Arc::new(LinkIndex::new(root))),
+                        scanned: Mutex::new(None),
+                    })
+                })
+                .clone()
+        };
+
+        let needs_scan = {
+            let mut scanned = workspace.scanned.lock().unwrap_or_else(|e| e.into_inner());
+            let stale = scanned.is_none_or(|at| at.elapsed() > RESCAN_INTERVAL);
+            if stale {
+                *scanned = Some(Instant::now());
+            }
+            stale
+        };
+        let snapshot = workspace
+            .index
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        if !needs_scan {
+            return Ok(snapshot);
+        }
+
+        let mut index = LinkIndex::clone(&snapshot);
+        if let Err(e) = index.refresh() {
+            *workspace.scanned.lock().unwrap_or_else(|e| e.into_inner()) = None;
+            return Err(e);
+        }
+        let index = Arc::new(index);
+        *workspace.index.lock().unwrap_or_else(|e| e.into_inner()) = index.clone();
+        Ok(index)
+    }
+}
+
+/// The links between the Markdown files under a folder
+#[derive(Clone)]
+pub struct LinkIndex {
+    root: PathBuf,
+    /// Shared between copies, so copying an index to refresh it is cheap
+    documents: HashMap<PathBuf, Arc<IndexedDocument>>,
+}
+
+struct IndexedDocument {
+    modified: Option<SystemTime>,
+    title: String,
+    tags: Vec<String>,
+    links: Vec<IndexedLink>,
+}
+
+/// A link to another Markdown file that exists
+struct IndexedLink {
+    target: PathBuf,
+    anchor: Option<String>,
+    text: String,
+    line: usize,
+    column: usize,
+    before: String,
+    link: String,
+    after: String,
+}
+
+impl LinkIndex {
+    pub fn new(root: PathBuf) -> LinkIndex {
+        LinkIndex {
+            root,
+            documents: HashMap::new(),
+        }
+    }
+
+    /// Indexes the Markdown files under `root`, or the single file it names
+    pub fn build(root: &str) -> Result<LinkIndex, String> {
+        let root = Path::new(root)
+            .canonicalize()
+            .map_err(|_| format!("Path not found: {}", root))?;
+        let mut index = LinkIndex::new(root);
+        index.refresh()?;
+        Ok(index)
+    }
+
+    /// The folder links starting with `/` are relative to: the root, or the
+    /// folder of a single-file root
+    pub fn root_dir(&self) -> &Path {
+        if self.root.is_dir() {
+            &self.root
+        } else {
+            self.root.parent().unwrap_or(&self.root)
+        }
+    }
+
+    /// Re-reads the files whose modification time changed since they were
+    /// indexed, adds new ones and drops those that are gone
+    pub fn refresh(&mut self) -> Result<(), String> {
+        let found: HashSet<PathBuf> = files::walk_markdown_files(&self.root.to_string_lossy())?
+            .into_iter()
+            .collect();
+        self.documents.retain(|path, _| found.contains(path));
+
+        for path in found {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+            let unchanged = self
+                .documents
+                .get(&path)
+                .is_some_and(|document| modified.is_some() && document.modified == modified);
+            if unchanged {
+                continue;
+            }
+
+            match files::read_file_contents(&path.to_string_lossy()) {
+                Ok(content) => {
+                    let document = self.index_document(&path, modified, &content);
+                    self.documents.insert(path, Arc::new(document));
+                }
+                Err(e) => {
+                    eprintln!("Skipping {} in link index: {}", path.display(), e);
+                    self.documents.remove(&path);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Every link to `target` from another document, by document and line
+    pub fn backlinks(&self, target: &Path) -> Vec<Backlink> {
+        let mut backlinks: Vec<Backlink> = self
+            .documents
+            .iter()
+            .filter(|(path, _)| path.as_path() != target)
+            .flat_map(|(path, document)| {
+                document
+                    .links
+                    .iter()
+                    .filter(|link| link.target == target)
+                    .map(move |link| Backlink {
+                        path: path.to_string_lossy().to_string(),
+                        title: document.title.clone(),
+                        line: link.line,
+                        column: link.column,
+                        text: link.text.clone(),
+                        anchor: link.anchor.clone(),
+                        before: link.before.clone(),
+                        link: link.link.clone(),
+                        after: link.after.clone(),
+                    })
+            })
+            .collect();
+
+        backlinks.sort_by(|a, b| a.path.cmp(&b.path).then(a.line.cmp(&b.line)));
+        backlinks
+    }
+
+    /// Every document with the links between them, ordered by path and line.
+    /// Links to files outside the index are left out.
+    pub fn graph(&self) -> LinkGraph {
+        let mut paths: Vec<&PathBuf> = self.documents.keys().collect();
+        paths.sort();
+
+        let mut graph = LinkGraph {
+            nodes: Vec::with_capacity(paths.len()),
+            edges: Vec::new(),
+        };
+        for path in paths {
+            let document = &self.documents[path];
+            let source = path.to_string_lossy().to_string();
+
+            graph.edges.extend(
+                document
+                    .links
+                    .iter()
+                    .filter(|link| self.documents.contains_key(&link.target))
+                    .map(|link| GraphEdge {
+                        source: source.clone(),
+                        target: link.target.to_string_lossy().to_string(),
+                        anchor: link.anchor.clone(),
+                        line: link.line,
+                    }),
+            );
+            graph.nodes.push(GraphNode {
+                path: source,
+                title: document.title.clone(),
+                tags: document.tags.clone(),
+            });
+        }
+
+        graph
+    }
+
+    /// Finds the documents nothing links to, those only reachable from other
+    /// such documents, and those that link nowhere. Documents whose file name
+    /// or stem matches one of `entry_points` (ignoring case), such as README
+    /// or index, are where readers start, so they are never orphans.
+    pub fn orphans(&self, entry_points: &[String]) -> OrphanReport {
+        let is_entry_point = |path: &Path| {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase());
+            let stem = path.file_stem().map(|s| s.to_string_lossy().to_lowercase());
+            entry_points.iter().any(|entry| {
+                let entry = entry.to_lowercase();
+                name.as_deref() == Some(entry.as_str()) || stem.as_deref() == Some(entry.as_str())
+            })
+        };
+
+        let mut linked = HashSet::new();
+        for (path, document) in &self.documents {
+            for link in &document.links {
+                if link.target != *path && self.documents.contains_key(&link.target) {
+                    linked.insert(link.target.as_path());
+                }
+            }
+        }
+
+        // Everything reachable by following links from the entry points
+        let mut reachable: HashSet<&Path> = HashSet::new();
+        let mut pending: Vec<&Path> = self
+            .documents
+            .keys()
+            .map(PathBuf::as_path)
+            .filter(|path| is_entry_point(path))
+            .collect();
+        while let Some(path) = pending.pop() {
+            if !reachable.insert(path) {
+                continue;
+            }
+            if let Some(document) = self.documents.get(path) {
+                pending.extend(
+                    document
+                        .links
+                        .iter()
+                        .map(|link| link.target.as_path())
+                        .filter(|target| self.documents.contains_key(*target)),
+                );
+            }
+        }
+
+        let mut paths: Vec<&PathBuf> = self.documents.keys().collect();
+        paths.sort();
+
+        let mut report = OrphanReport {
+            files_checked: paths.len(),
+            orphans: Vec::new(),
+            unreachable: Vec::new(),
+            dead_ends: Vec::new(),
+        };
+        for path in paths {
+            let document = &self.documents[path];
+            let reported = || ReportedDocument {
+                path: path.to_string_lossy().to_string(),
+                title: document.title.clone(),
+            };
+
+            if !is_entry_point(path) {
+                if !linked.contains(path.as_path()) {
+                    report.orphans.push(reported());
+                } else if !reachable.is_empty() && !reachable.contains(path.as_path()) {
+                    report.unreachable.push(reported());
+                }
+            }
+            let links_elsewhere = document
+                .links
+                .iter()
+                .any(|link| link.target != *path && self.documents.contains_key(&link.target));
+            if !links_elsewhere {
+                report.dead_ends.push(reported());
+            }
+        }
+
+        report
+    }
+
+    fn index_document(
+        &self,
+        path: &Path,
+        modified: Option<SystemTime>,
+        content: &str,
+    ) -> IndexedDocument {
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+        let line_index = LineIndex::new(content);
+
+        let links = parse_links(content)
+            .into_iter()
+            .filter_map(|(url, text, range)| {
+                if url.is_empty() || link_discovery::is_external_link(&url) {
+                    return None;
+                }
+                let (link_path, anchor) = link_discovery::split_link_target(&url);
+                if link_path.is_empty() {
+                    return None;
+                }
+
+                let target = match link_path.strip_prefix('/') {
+                    Some(absolute) => self.root_dir().join(absolute),
+                    None => base_dir.join(&link_path),
+                };
+                let target = target.canonicalize().ok()?;
+                if !files::is_markdown_file(&target) {
+                    return None;
+                }
+
+                let (line, column) = line_index.position(range.start);
+                let (before, link, after) = line_context(content, range);
+                Some(IndexedLink {
+                    target,
+                    anchor: anchor.filter(|anchor| !anchor.is_empty()),
+                    text,
+                    line,
+                    column,
+                    before,
+                    link,
+                    after,
+                })
+            })
+            .collect();
+
+        let title = markdown::document_title(content).unwrap_or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default()
+        });
+
+        IndexedDocument {
+            modified,
+            title,
+            tags: markdown::document_tags(content),
+            links,
+        }
+    }
+}
+
+/// The destination, plain text and source range of each link in a document
+fn parse_links(content: &str) -> Vec<(String, String, Range<usize>)> {
+    let mut links = Vec::new();
+    let mut current: Option<(String, String, Range<usize>)> = None;
+
+    for (event, range) in Parser::new_ext(content, markdown::parser_options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                current = Some((dest_url.to_string(), String::new(), range));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, link_text, _)) = current.as_mut() {
+                    link_text.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Link) => {
+                links.extend(current.take());
+            }
+            _ => {}
+        }
+    }
+
+    links
+}
+
+/// The link as written on its first line, with the text around it on that line
+fn line_context(content: &str, link: Range<usize>) -> (String, String, String) {
+    let line_start = content[..link.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = content[link.start..]
+        .find('\n')
+        .map_or(content.len(), |i| link.start + i);
+    let link_end = link.end.min(line_end);
+
+    let before = &content[line_start..link.start];
+    let skip = before.chars().count().saturating_sub(CONTEXT_LENGTH);
+    let before: String = before.chars().skip(skip).collect();
+    let after: String = content[link_end..line_end]
+        .trim_end_matches('\r')
+        .chars()
+        .take(CONTEXT_LENGTH)
+        .collect();
+
+    (
+        before.trim_start().to_string(),
+        content[link.start..link_end]
+            .trim_end_matches('\r')
+            .to_string(),
+        after,
+    )
+}
+
+/// Writes a graph in Graphviz DOT format, naming documents by their path
+/// below `root` and labelling them with their titles
+pub fn format_dot(graph: &LinkGraph, root: &Path) -> String {
+    let name = |path: &str| {
+        let path = Path::new(path);
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        quote_dot(&relative.to_string_lossy().replace('\\', "/"))
+    };
+
+    let mut output = String::from("digraph links {\n    node [shape=box];\n");
+    for node in &graph.nodes {
+        output.push_str(&format!(
+            "    {} [label={}];\n",
+            name(&node.path),
+            quote_dot(&node.title)
+        ));
+    }
+    for edge in &graph.edges {
+        output.push_str(&format!(
+            "    {} -> {}",
+            name(&edge.source),
+            name(&edge.target)
+        ));
+        if let Some(anchor) = &edge.anchor {
+            output.push_str(&format!(" [label={}]", quote_dot(&format!("#{}", anchor))));
+        }
+        output.push_str(";\n");
+    }
+    output.push_str("}\n");
+    output
+}
+
+fn quote_dot(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Formats an orphan report as a list per problem, with paths below `root`,
+/// followed by a summary
+pub fn format_orphan_report(report: &OrphanReport, root: &Path) -> String {
+    let mut output = String::new();
+    let sections = [
+        ("Orphaned, nothing links here", &report.orphans),
+        ("Unreachable from the entry points", &report.unreachable),
+        ("Dead ends, linking to no other document", &report.dead_ends),
+    ];
+
+    for (heading, documents) in sections {
+        if documents.is_empty() {
+            continue;
+        }
+        output.push_str(&format!("{}:\n", heading));
+        for document in documents {
+            let path = Path::new(&document.path);
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            output.push_str(&format!(
+                "  {} ({})\n",
+                relative.to_string_lossy(),
+                document.title
+            ));
+        }
+        output.push('\n');
+    }
+
+    output.push_str(&format!(
+        "Checked {} files: {} orphaned, {} unreachable, {} dead ends\n",
+        report.files_checked,
+        report.orphans.len(),
+        report.unreachable.len(),
+        report.dead_ends.len()
+    ));
+    output
+}
@@ -0,0 +1,644 @@
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use crate::markdown::{self, LineIndex};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A problem found by a rule. Lines and columns start at 1 and columns count
+/// characters; the end position is exclusive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub rule: String,
+    pub severity: Severity,
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiagnostics {
+    pub file: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// The `[lint]` section of `.mdox.toml`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct LintConfig {
+    /// Rule IDs that should not run
+    pub disable: Vec<String>,
+    /// Severity overrides by rule ID; everything else is a warning
+    pub severity: HashMap<String, Severity>,
+    pub duplicate_heading: DuplicateHeadingConfig,
+    pub line_length: LineLengthConfig,
+    pub list_marker: ListMarkerConfig,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct DuplicateHeadingConfig {
+    /// Only compare headings under the same parent, so changelog-style
+    /// "Added" / "Fixed" sections are allowed
+    pub siblings_only: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct LineLengthConfig {
+    pub max: usize,
+    pub code_blocks: bool,
+    pub tables: bool,
+}
+
+impl Default for LineLengthConfig {
+    fn default() -> Self {
+        LineLengthConfig {
+            max: 80,
+            code_blocks: false,
+            tables: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ListMarkerConfig {
+    pub style: MarkerStyle,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum MarkerStyle {
+    /// Whatever the first bullet in the document uses
+    #[default]
+    #[serde(rename = "consistent")]
+    Consistent,
+    #[serde(rename = "-")]
+    Dash,
+    #[serde(rename = "*")]
+    Asterisk,
+    #[serde(rename = "+")]
+    Plus,
+}
+
+impl LintConfig {
+    /// Rejects rule IDs that don't exist, so typos don't silently do nothing
+    pub fn validate(&self) -> Result<(), String> {
+        let configured = self.disable.iter().chain(self.severity.keys());
+        for rule in configured {
+            if !RULES.iter().any(|(id, _)| id == rule) {
+                return Err(format!("Unknown lint rule: {}", rule));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A rule's raw result, as a byte range into the document
+struct Finding {
+    range: Range<usize>,
+    message: String,
+}
+
+/// A document parsed once and shared by every rule
+struct Document<'a> {
+    text: &'a str,
+    /// Where the Markdown starts, after any front matter
+    body_start: usize,
+    events: Vec<(Event<'a>, Range<usize>)>,
+    code_blocks: Vec<Range<usize>>,
+    tables: Vec<Range<usize>>,
+    hard_breaks: Vec<Range<usize>>,
+}
+
+impl<'a> Document<'a> {
+    fn parse(text: &'a str) -> Self {
+        let (front_matter, body) = markdown::split_front_matter(text);
+        let body_start = front_matter.map_or(0, str::len);
+
+        let mut document = Document {
+            text,
+            body_start,
+            events: Vec::new(),
+            code_blocks: Vec::new(),
+            tables: Vec::new(),
+            hard_breaks: Vec::new(),
+        };
+
+        for (event, range) in Parser::new_ext(body, markdown::parser_options()).into_offset_iter() {
+            let range = range.start + body_start..range.end + body_start;
+            match &event {
+                Event::Start(Tag::CodeBlock(_)) => document.code_blocks.push(range.clone()),
+                Event::Start(Tag::Table(_)) => document.tables.push(range.clone()),
+                Event::HardBreak => document.hard_breaks.push(range.clone()),
+                _ => {}
+            }
+            document.events.push((event, range));
+        }
+
+        document
+    }
+
+    /// Byte ranges of the body's lines, without their line endings
+    fn lines(&self) -> Vec<Range<usize>> {
+        let mut lines = Vec::new();
+        let mut start = self.body_start;
+        for line in self.text[self.body_start..].split_inclusive('\n') {
+            let content = line.trim_end_matches(['\n', '\r']);
+            lines.push(start..start + content.len());
+            start += line.len();
+        }
+        lines
+    }
+
+    /// The first line of a block, for rules that report on a whole block
+    fn first_line(&self, range: &Range<usize>) -> Range<usize> {
+        let end = self.text[range.clone()]
+            .find('\n')
+            .map_or(range.end, |i| range.start + i);
+        range.start..range.start + self.text[range.start..end].trim_end().len()
+    }
+}
+
+fn overlaps(blocks: &[Range<usize>], line: &Range<usize>) -> bool {
+    blocks
+        .iter()
+        .any(|block| block.start < line.end.max(line.start + 1) && line.start < block.end)
+}
+
+type Rule = fn(&Document, &LintConfig) -> Vec<Finding>;
+
+/// Every rule by ID, in the order they run
+const RULES: &[(&str, Rule)] = &[
+    ("heading-increment", heading_increment),
+    ("duplicate-heading", duplicate_heading),
+    ("trailing-spaces", trailing_spaces),
+    ("bare-url", bare_url),
+    ("no-alt-text", no_alt_text),
+    ("list-marker", list_marker),
+    ("line-length", line_length),
+];
+
+/// Runs every enabled rule over a document
+pub fn lint_markdown(content: &str, config: &LintConfig) -> Vec<Diagnostic> {
+    let document = Document::parse(content);
+    let index = LineIndex::new(content);
+
+    let mut diagnostics = Vec::new();
+    for (id, rule) in RULES {
+        if config.disable.iter().any(|disabled| disabled == id) {
+            continue;
+        }
+        let severity = config
+            .severity
+            .get(*id)
+            .copied()
+            .unwrap_or(Severity::Warning);
+
+        for finding in rule(&document, config) {
+            let (line, column) = char_position(content, &index, finding.range.start);
+            let (end_line, end_column) = char_position(content, &index, finding.range.end);
+            diagnostics.push(Diagnostic {
+                rule: id.to_string(),
+                severity,
+                message: finding.message,
+                line,
+                column,
+                end_line,
+                end_column,
+            });
+        }
+    }
+
+    diagnostics.sort_by_key(|d| (d.line, d.column));
+    diagnostics
+}
+
+/// Formats diagnostics as `file:line:column: severity: message [rule]` lines
+/// followed by a summary
+pub fn format_report(results: &[FileDiagnostics], files_checked: usize) -> String {
+    let mut output = String::new();
+    let mut problems = 0;
+
+    for result in results {
+        for d in &result.diagnostics {
+            let severity = match d.severity {
+                Severity::Warning => "warning",
+                Severity::Error => "error",
+            };
+            output.push_str(&format!(
+                "{}:{}:{}: {}: {} [{}]\n",
+                result.file, d.line, d.column, severity, d.message, d.rule
+            ));
+            problems += 1;
+        }
+    }
+
+    output.push_str(&format!(
+        "Linted {} files, {} problems\n",
+        files_checked, problems
+    ));
+
+    output
+}
+
+fn char_position(text: &str, index: &LineIndex, offset: usize) -> (usize, usize) {
+    let (line, byte_column) = index.position(offset);
+    let line_start = offset - (byte_column - 1);
+    (line, text[line_start..offset].chars().count() + 1)
+}
+
+fn heading_increment(document: &Document, _config: &LintConfig) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut previous = None;
+
+    for (event, range) in &document.events {
+        if let Event::Start(Tag::Heading { level, .. }) = event {
+            let level = *level as usize;
+            if let Some(previous) = previous.filter(|&previous| level > previous + 1) {
+                findings.push(Finding {
+                    range: document.first_line(range),
+                    message: format!(
+                        "Heading level jumps from h{} to h{}; expected h{}",
+                        previous,
+                        level,
+                        previous + 1
+                    ),
+                });
+            }
+            previous = Some(level);
+        }
+    }
+
+    findings
+}
+
+fn duplicate_heading(document: &Document, config: &LintConfig) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let index = LineIndex::new(document.text);
+    // First line of each heading text, keyed by the start of its parent heading
+    let mut seen: HashMap<(Option<usize>, String), usize> = HashMap::new();
+    // Open sections as (level, start of heading)
+    let mut sections: Vec<(usize, usize)> = Vec::new();
+    let mut current: Option<(usize, Range<usize>, String)> = None;
+
+    for (event, range) in &document.events {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                current = Some((*level as usize, range.clone(), String::new()));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, _, heading)) = current.as_mut() {
+                    heading.push_str(text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                let Some((level, range, heading)) = current.take() else {
+                    continue;
+                };
+
+                while sections.last().is_some_and(|&(open, _)| open >= level) {
+                    sections.pop();
+                }
+                let parent = if config.duplicate_heading.siblings_only {
+                    sections.last().map(|&(_, start)| start)
+                } else {
+                    None
+                };
+                sections.push((level, range.start));
+
+                let heading = heading.trim().to_string();
+                if heading.is_empty() {
+                    continue;
+                }
+
+                match seen.get(&(parent, heading.to_lowercase())) {
+                    Some(first_line) => findings.push(Finding {
+                        range: document.first_line(&range),
+                        message: format!(
+                            "Duplicate heading \"{}\" (first used on line {})",
+                            heading, first_line
+                        ),
+                    }),
+                    None => {
+                        seen.insert(
+                            (parent, heading.to_lowercase()),
+                            index.position(range.start).0,
+                        );
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    findings
+}
+
+fn trailing_spaces(document: &Document, _config: &LintConfig) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for line in document.lines() {
+        let text = &document.text[line.clone()];
+        let trimmed = text.trim_end_matches([' ', '\t']);
+        if trimmed.len() == text.len() {
+            continue;
+        }
+
+        let whitespace = line.start + trimmed.len()..line.end;
+        // Two or more trailing spaces before a continuation line are a hard break
+        if overlaps(&document.hard_breaks, &whitespace) {
+            continue;
+        }
+
+        findings.push(Finding {
+            range: whitespace,
+            message: "Trailing whitespace".to_string(),
+        });
+    }
+
+    findings
+}
+
+/// A URL written as plain text, without trailing punctuation
+static BARE_URL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?:https?|ftp)://[^\s<>]*[^\s<>.,;:!?'")\]]"#).unwrap());
+
+fn bare_url(document: &Document, _config: &LintConfig) -> Vec<Finding> {
+    let mut link_depth = 0;
+    let mut in_code_block = false;
+
+    // The parser splits text at characters like `_`, so scan contiguous runs
+    let mut runs: Vec<Range<usize>> = Vec::new();
+    for (event, range) in &document.events {
+        match event {
+            Event::Start(Tag::Link { .. }) | Event::Start(Tag::Image { .. }) => link_depth += 1,
+            Event::End(TagEnd::Link) | Event::End(TagEnd::Image) => link_depth -= 1,
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Text(_) if link_depth == 0 && !in_code_block => match runs.last_mut() {
+                Some(run) if run.end == range.start => run.end = range.end,
+                _ => runs.push(range.clone()),
+            },
+            _ => {}
+        }
+    }
+
+    let mut findings = Vec::new();
+    for run in runs {
+        for found in BARE_URL.find_iter(&document.text[run.clone()]) {
+            findings.push(Finding {
+                range: run.start + found.start()..run.start + found.end(),
+                message: "Bare URL; wrap it in <> or use [text](url) to make it a link".to_string(),
+            });
+        }
+    }
+
+    findings
+}
+
+fn no_alt_text(document: &Document, _config: &LintConfig) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut current: Option<(Range<usize>, String)> = None;
+
+    for (event, range) in &document.events {
+        match event {
+            Event::Start(Tag::Image { .. }) if current.is_none() => {
+                current = Some((range.clone(), String::new()));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, alt)) = current.as_mut() {
+                    alt.push_str(text);
+                }
+            }
+            Event::End(TagEnd::Image) if current.as_ref().is_some_and(|(r, _)| r == range) => {
+                if let Some((range, alt)) = current.take() {
+                    if alt.trim().is_empty() {
+                        findings.push(Finding {
+                            range,
+                            message: "Image has no alt text".to_string(),
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    findings
+}
+
+fn list_marker(document: &Document, config: &LintConfig) -> Vec<Finding> {
+    let mut expected = match config.list_marker.style {
+        MarkerStyle::Consistent => None,
+        MarkerStyle::Dash => Some('-'),
+        MarkerStyle::Asterisk => Some('*'),
+        MarkerStyle::Plus => Some('+'),
+    };
+
+    let mut findings = Vec::new();
+    // Whether the items of each open list should be checked
+    let mut lists: Vec<bool> = Vec::new();
+
+    for (i, (event, range)) in document.events.iter().enumerate() {
+        match event {
+            Event::Start(Tag::List(first_number)) => {
+                // A list directly after another one has to switch markers,
+                // otherwise the two would merge into one
+                let follows_list =
+                    i > 0 && matches!(document.events[i - 1].0, Event::End(TagEnd::List(false)));
+                lists.push(first_number.is_none() && !follows_list);
+            }
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+            }
+            Event::Start(Tag::Item) if lists.last() == Some(&true) => {
+                let item = &document.text[range.clone()];
+                let indent = item.len() - item.trim_start().len();
+                let Some(marker) = item[indent..].chars().next() else {
+                    continue;
+                };
+
+                match expected {
+                    None => expected = Some(marker),
+                    Some(expected) if expected != marker => {
+                        let start = range.start + indent;
+                        findings.push(Finding {
+                            range: start..start + 1,
+                            message: format!(
+                                "Inconsistent list marker: expected '{}' but found '{}'",
+                                expected, marker
+                            ),
+                        });
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    findings
+}
+
+fn line_length(document: &Document, config: &LintConfig) -> Vec<Finding> {
+    let settings = &config.line_length;
+    let mut findings = Vec::new();
+
+    for line in document.lines() {
+        let text = &document.text[line.clone()];
+        let length = text.chars().count();
+        if length <= settings.max {
+            continue;
+        }
+        if (!settings.code_blocks && overlaps(&document.code_blocks, &line))
+            || (!settings.tables && overlaps(&document.tables, &line))
+        {
+            continue;
+        }
+
+        // Only flag lines that could be wrapped; a long URL or word can't be
+        let overflow = text
+            .char_indices()
+            .nth(settings.max)
+            .map_or(text.len(), |(i, _)| i);
+        if !text[overflow..].contains(char::is_whitespace) {
+            continue;
+        }
+
+        findings.push(Finding {
+            range: line.start + overflow..line.end,
+            message: format!("Line is {} characters long (max {})", length, settings.max),
+        });
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The rule and line of each diagnostic
+    fn lint(content: &str, config: &LintConfig) -> Vec<(String, usize)> {
+        lint_markdown(content, config)
+            .into_iter()
+            .map(|d| (d.rule, d.line))
+            .collect()
+    }
+
+    fn found(rule: &str, lines: &[usize]) -> Vec<(String, usize)> {
+        lines.iter().map(|&line| (rule.to_string(), line)).collect()
+    }
+
+    #[test]
+    fn heading_increment_flags_skipped_levels() {
+        let config = LintConfig::default();
+        assert_eq!(
+            lint("# A\n\n### B\n\n## C\n\n# D\n\n## E\n", &config),
+            found("heading-increment", &[3])
+        );
+    }
+
+    #[test]
+    fn duplicate_heading_compares_text_or_siblings() {
+        let content = "# Release\n\n## Added\n\n# Older\n\n## added\n";
+        assert_eq!(
+            lint(content, &LintConfig::default()),
+            found("duplicate-heading", &[7])
+        );
+
+        let mut config = LintConfig::default();
+        config.duplicate_heading.siblings_only = true;
+        assert_eq!(lint(content, &config), found("duplicate-heading", &[]));
+        assert_eq!(
+            lint("# A\n\n## B\n\n## B\n", &config),
+            found("duplicate-heading", &[5])
+        );
+    }
+
+    #[test]
+    fn trailing_spaces_allow_hard_breaks() {
+        let config = LintConfig::default();
+        assert_eq!(
+            lint("one  \ntwo \nthree\n\nfour\t\n", &config),
+            found("trailing-spaces", &[2, 5])
+        );
+    }
+
+    #[test]
+    fn bare_url_skips_links_and_code() {
+        let config = LintConfig::default();
+        let content = "See https://example.com/a_b.\n\n<https://example.com>\n[x](https://example.com)\n`https://example.com`\n\n```\nhttps://example.com\n```\n";
+        let diagnostics = lint_markdown(content, &config);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].column, diagnostics[0].end_column), (5, 28));
+    }
+
+    #[test]
+    fn no_alt_text_flags_empty_descriptions() {
+        let config = LintConfig::default();
+        assert_eq!(
+            lint(
+                "![](a.png)\n\n![ ](b.png)\n\n![Chart](c.png)\n\n![`code`](d.png)\n",
+                &config
+            ),
+            found("no-alt-text", &[1, 3])
+        );
+    }
+
+    #[test]
+    fn list_marker_follows_the_first_or_configured_marker() {
+        let content = "- a\n  * b\n- c\n\n* separate list\n";
+        assert_eq!(
+            lint(content, &LintConfig::default()),
+            found("list-marker", &[2])
+        );
+
+        let mut config = LintConfig::default();
+        config.list_marker.style = MarkerStyle::Asterisk;
+        assert_eq!(lint(content, &config), found("list-marker", &[1, 3]));
+        assert_eq!(lint("1. a\n2. b\n", &config), found("list-marker", &[]));
+    }
+
+    #[test]
+    fn line_length_skips_what_cannot_wrap() {
+        let mut config = LintConfig::default();
+        config.line_length.max = 10;
+        let content = "short\n\nthis line is too long\n\n<https://example.com/a/very/long/path>\n\n```\ncode that is too long\n```\n\n| a table cell that is long |\n| --- |\n";
+        assert_eq!(lint(content, &config), found("line-length", &[3]));
+
+        config.line_length.code_blocks = true;
+        config.line_length.tables = true;
+        assert_eq!(lint(content, &config), found("line-length", &[3, 8, 11]));
+    }
+
+    #[test]
+    fn config_disables_rules_and_sets_severity() {
+        let content = "# A\n\n### B \n";
+        let mut config = LintConfig::default();
+        config.disable.push("trailing-spaces".to_string());
+        config
+            .severity
+            .insert("heading-increment".to_string(), Severity::Error);
+
+        let diagnostics = lint_markdown(content, &config);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].rule, "heading-increment");
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+
+        config.disable.push("no-such-rule".to_string());
+        assert!(config.validate().is_err());
+    }
+}
@@ -3,117 +3,277 @@
 
 mod cli;
 mod commands;
+mod config;
 mod files;
+mod format;
+mod git_status;
+mod gitignore;
+mod history;
+mod instance;
+mod link_check;
 mod link_discovery;
+mod link_index;
+mod lint;
 mod markdown;
+mod quick_open;
 mod remote;
+mod rename;
+mod replace;
+mod search;
+mod serve;
+mod trash;
+mod watcher;
+mod windows;
 
-use tauri::{
-    menu::{MenuBuilder, MenuItemBuilder, SubmenuBuilder},
-    Emitter, Manager,
-};
+use tauri::menu::{MenuBuilder, MenuItemBuilder, SubmenuBuilder};
+use tauri::Manager;
+
+use git_status::GitStatuses;
+use instance::OpenRequest;
+use link_discovery::DiscoveryCache;
+use link_index::LinkIndexes;
+use quick_open::{FileLists, TitleCache};
+use search::SearchIndexes;
+use trash::LastDelete;
+use watcher::Watchers;
+use windows::PendingDocuments;
 
 fn main() {
     let cli_args = cli::parse_args();
 
-    tauri::Builder::default()
+    // Subcommands run headless and exit without starting the GUI
+    if let Some(command) = cli_args.command.clone() {
+        std::process::exit(cli::run(command));
+    }
+
+    // Read piped input up front so it can be forwarded like a file
+    let stdin = if cli_args.reads_stdin() {
+        match files::read_stdin() {
+            Ok(content) => Some(content),
+            Err(e) => {
+                eprintln!("mdox: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    let request = OpenRequest {
+        targets: cli_args
+            .open_targets()
+            .into_iter()
+            .map(|target| target.into_absolute())
+            .collect(),
+        stdin,
+    };
+
+    // Hand the document to a running instance instead of starting another one
+    if !cli_args.new_instance && instance::forward(&request) {
+        return;
+    }
+    let listener = if cli_args.new_instance {
+        None
+    } else {
+        instance::listen()
+    };
+    // Another launch may have claimed the socket in the meantime
+    if !cli_args.new_instance && listener.is_none() && instance::forward(&request) {
+        return;
+    }
+
+    let app = tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
+        .manage(PendingDocuments::default())
+        .manage(Watchers::default())
+        .manage(GitStatuses::default())
+        .manage(LastDelete::default())
+        .manage(SearchIndexes::default())
+        .manage(FileLists::default())
+        .manage(TitleCache::default())
+        .manage(LinkIndexes::default())
+        .manage(DiscoveryCache::default())
+        .on_window_event(|window, event| {
+            // Stop watching files for windows that are gone
+            if let tauri::WindowEvent::Destroyed = event {
+                window.state::<Watchers>().remove(window.label());
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             commands::read_file,
+            commands::take_pending_document,
             commands::parse_markdown,
             commands::resolve_file_path,
             commands::save_file,
+            commands::list_history,
+            commands::diff_history_version,
+            commands::restore_history_version,
+            commands::format_document,
+            commands::lint_document,
             commands::file_exists,
             commands::read_directory,
             commands::create_directory,
             commands::delete_file_or_directory,
+            commands::undo_delete,
             commands::rename_file_or_directory,
+            commands::preview_rename,
+            commands::rename_with_links,
+            commands::watch_paths,
             commands::discover_linked_documents,
+            commands::check_links,
+            commands::search_workspace,
+            commands::find_backlinks,
+            commands::link_graph,
+            commands::orphan_report,
+            commands::quick_open,
+            commands::find_in_workspace,
+            commands::replace_in_workspace,
             commands::fetch_remote_file,
             commands::discover_remote_linked_documents,
         ])
-        .setup({
-            let cli_args = cli_args.clone();
-            move |app| {
-                // Build native menu
-                let new_file_item = MenuItemBuilder::new("New File")
-                    .id("new_file")
-                    .accelerator("CmdOrCtrl+N")
-                    .build(app)?;
-
-                let open_item = MenuItemBuilder::new("Open...")
-                    .id("open_file")
-                    .accelerator("CmdOrCtrl+O")
-                    .build(app)?;
-
-                let open_url_item = MenuItemBuilder::new("Open from URL...")
-                    .id("open_url")
-                    .accelerator("CmdOrCtrl+Shift+O")
-                    .build(app)?;
-
-                let save_as_item = MenuItemBuilder::new("Save As...")
-                    .id("save_as")
-                    .accelerator("CmdOrCtrl+Shift+S")
-                    .build(app)?;
-
-                let file_submenu = SubmenuBuilder::new(app, "File")
-                    .item(&new_file_item)
-                    .separator()
-                    .item(&open_item)
-                    .item(&open_url_item)
-                    .separator()
-                    .item(&save_as_item)
-                    .separator()
-                    .close_window()
-                    .build()?;
-
-                let menu = MenuBuilder::new(app).item(&file_submenu).build()?;
-
-                app.set_menu(menu)?;
-
-                // Handle menu events
-                app.on_menu_event(move |app, event| {
-                    if event.id() == "new_file" {
-                        if let Some(window) = app.get_webview_window("main") {
-                            if let Err(e) = window.emit("menu-new-file", ()) {
-                                eprintln!("Failed to emit 'menu-new-file': {}", e);
-                            }
-                        }
-                    } else if event.id() == "open_file" {
-                        if let Some(window) = app.get_webview_window("main") {
-                            if let Err(e) = window.emit("menu-open-file", ()) {
-                                eprintln!("Failed to emit 'menu-open-file': {}", e);
-                            }
-                        }
-                    } else if event.id() == "open_url" {
-                        if let Some(window) = app.get_webview_window("main") {
-                            if let Err(e) = window.emit("menu-open-url", ()) {
-                                eprintln!("Failed to emit 'menu-open-url': {}", e);
-                            }
-                        }
-                    } else if event.id() == "save_as" {
-                        if let Some(window) = app.get_webview_window("main") {
-                            if let Err(e) = window.emit("menu-save-as", ()) {
-                                eprintln!("Failed to emit 'menu-save-as': {}", e);
-                            }
-                        }
-                    }
-                });
+        .setup(move |app| {
+            // Build native menu
+            let new_file_item = MenuItemBuilder::new("New File")
+                .id("new_file")
+                .accelerator("CmdOrCtrl+N")
+                .build(app)?;
+
+            let open_item = MenuItemBuilder::new("Open...")
+                .id("open_file")
+                .accelerator("CmdOrCtrl+O")
+                .build(app)?;
+
+            let open_url_item = MenuItemBuilder::new("Open from URL...")
+                .id("open_url")
+                .accelerator("CmdOrCtrl+Shift+O")
+                .build(app)?;
+
+            let go_to_file_item = MenuItemBuilder::new("Go to File...")
+                .id("go_to_file")
+                .accelerator("CmdOrCtrl+Shift+P")
+                .build(app)?;
+
+            let save_as_item = MenuItemBuilder::new("Save As...")
+                .id("save_as")
+                .accelerator("CmdOrCtrl+Shift+S")
+                .build(app)?;
+
+            let file_submenu = SubmenuBuilder::new(app, "File")
+                .item(&new_file_item)
+                .separator()
+                .item(&open_item)
+                .item(&open_url_item)
+                .item(&go_to_file_item)
+                .separator()
+                .item(&save_as_item)
+                .separator()
+                .close_window()
+                .build()?;
+
+            let check_links_item = MenuItemBuilder::new("Check Links...")
+                .id("check_links")
+                .build(app)?;
 
-                // Handle CLI file argument
-                if let Some(file_path) = &cli_args.file {
-                    if let Some(window) = app.get_webview_window("main") {
-                        if let Err(e) = window.emit("file-to-open", file_path) {
-                            eprintln!("Failed to emit 'file-to-open': {}", e);
-                        }
-                    } else {
-                        eprintln!("Failed to get 'main' webview window");
-                    }
-                }
-                Ok(())
+            let format_document_item = MenuItemBuilder::new("Format Document")
+                .id("format_document")
+                .accelerator("CmdOrCtrl+Shift+F")
+                .build(app)?;
+
+            let local_history_item = MenuItemBuilder::new("Local History...")
+                .id("local_history")
+                .build(app)?;
+
+            let search_workspace_item = MenuItemBuilder::new("Search in Workspace...")
+                .id("search_workspace")
+                .accelerator("CmdOrCtrl+Alt+F")
+                .build(app)?;
+
+            let replace_workspace_item = MenuItemBuilder::new("Replace in Workspace...")
+                .id("replace_workspace")
+                .accelerator("CmdOrCtrl+Alt+H")
+                .build(app)?;
+
+            let link_graph_item = MenuItemBuilder::new("Link Graph...")
+                .id("link_graph")
+                .build(app)?;
+
+            let orphan_report_item = MenuItemBuilder::new("Orphaned Documents...")
+                .id("orphan_report")
+                .build(app)?;
+
+            let tools_submenu = SubmenuBuilder::new(app, "Tools")
+                .item(&search_workspace_item)
+                .item(&replace_workspace_item)
+                .separator()
+                .item(&check_links_item)
+                .item(&link_graph_item)
+                .item(&orphan_report_item)
+                .item(&format_document_item)
+                .item(&local_history_item)
+                .build()?;
+
+            let menu = MenuBuilder::new(app)
+                .item(&file_submenu)
+                .item(&tools_submenu)
+                .build()?;
+
+            app.set_menu(menu)?;
+
+            // Handle menu events
+            app.on_menu_event(move |app, event| {
+                let event_name = match event.id().as_ref() {
+                    "new_file" => "menu-new-file",
+                    "open_file" => "menu-open-file",
+                    "open_url" => "menu-open-url",
+                    "go_to_file" => "menu-go-to-file",
+                    "save_as" => "menu-save-as",
+                    "check_links" => "menu-check-links",
+                    "link_graph" => "menu-link-graph",
+                    "orphan_report" => "menu-orphan-report",
+                    "format_document" => "menu-format-document",
+                    "local_history" => "menu-local-history",
+                    "search_workspace" => "menu-search-workspace",
+                    "replace_workspace" => "menu-replace-workspace",
+                    _ => return,
+                };
+                windows::emit_to_focused(app, event_name);
+            });
+
+            // Windows collect their documents with `take_pending_document` once loaded
+            if !request.is_empty() {
+                windows::open_request(app.handle(), request);
             }
+
+            // Open files passed to later `mdox` invocations
+            if let Some(listener) = listener {
+                let handle = app.handle().clone();
+                instance::accept_requests(listener, move |request| {
+                    windows::open_request(&handle, request);
+                });
+            }
+
+            Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application");
+
+    app.run(|_app, _event| {
+        // macOS delivers double-clicked files to the running app instead of a new process
+        #[cfg(target_os = "macos")]
+        if let tauri::RunEvent::Opened { urls } = _event {
+            let targets = urls
+                .into_iter()
+                .filter_map(|url| url.to_file_path().ok())
+                .map(|path| cli::parse_open_target(&path.to_string_lossy()))
+                .collect();
+            windows::open_request(
+                _app,
+                OpenRequest {
+                    targets,
+                    stdin: None,
+                },
+            );
+        }
+    });
 }
@@ -1,6 +1,9 @@
 use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
 use std::path::Path;
 
+/// Stylesheet the viewer uses, embedded so standalone pages render the same way
+const MARKDOWN_CSS: &str = include_str!("../../src/styles/markdown.css");
+
 fn generate_id(text: &str) -> String {
     text.to_lowercase()
         .chars()
@@ -20,80 +23,31 @@ fn generate_id(text: &str) -> String {
         .join("-")
 }
 
-pub fn parse_to_html(markdown: &str) -> String {
+pub fn parser_options() -> Options {
     let mut options = Options::empty();
     options.insert(Options::ENABLE_STRIKETHROUGH);
     options.insert(Options::ENABLE_TABLES);
     options.insert(Options::ENABLE_TASKLISTS);
     options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
-
-    let parser = Parser::new_ext(markdown, options);
-
-    // Collect text content for heading IDs
-    let mut events = Vec::new();
-    let mut in_heading = false;
-
-    let mut heading_text = String::new();
-    let mut heading_start_index = 0;
-
-    for event in parser {
-        match &event {
-            Event::Start(Tag::Heading {
-                level: _,
-                id: _,
-                classes: _,
-                attrs: _,
-            }) => {
-                in_heading = true;
-                heading_text.clear();
-                heading_start_index = events.len();
-                events.push(event);
-            }
-            Event::End(pulldown_cmark::TagEnd::Heading(_)) => {
-                in_heading = false;
-                if !heading_text.is_empty() {
-                    let id = generate_id(&heading_text);
-                    // Update the heading start event with the ID
-                    if let Some(Event::Start(Tag::Heading { level, classes, .. })) =
-                        events.get_mut(heading_start_index)
-                    {
-                        *events.get_mut(heading_start_index).unwrap() =
-                            Event::Start(Tag::Heading {
-                                level: *level,
-                                id: Some(CowStr::from(id)),
-                                classes: classes.clone(),
-                                attrs: vec![],
-                            });
-                    }
-                }
-                events.push(event);
-            }
-            Event::Text(text) if in_heading => {
-                heading_text.push_str(text);
-                events.push(event);
-            }
-            _ => {
-                events.push(event);
-            }
-        }
-    }
-
-    let mut html_output = String::new();
-    html::push_html(&mut html_output, events.into_iter());
-
-    html_output
+    options
 }
 
-pub fn parse_to_html_with_base_path(markdown: &str, base_path: &str) -> String {
-    let mut options = Options::empty();
-    options.insert(Options::ENABLE_STRIKETHROUGH);
-    options.insert(Options::ENABLE_TABLES);
-    options.insert(Options::ENABLE_TASKLISTS);
-    options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
+/// Returns false for link targets that would run script when clicked
+fn is_safe_url(url: &str) -> bool {
+    let scheme = url.trim_start().to_lowercase();
+    !(scheme.starts_with("javascript:")
+        || scheme.starts_with("vbscript:")
+        || (scheme.starts_with("data:") && !scheme.starts_with("data:image/")))
+}
 
-    let parser = Parser::new_ext(markdown, options);
-    let base = Path::new(base_path);
-    let base_dir = base.parent();
+/// Parses markdown and applies heading IDs, image path rewriting and
+/// optional sanitization. Every HTML output path goes through here.
+fn transform_events<'a>(
+    markdown: &'a str,
+    base_dir: Option<&Path>,
+    sanitize: bool,
+) -> Vec<Event<'a>> {
+    let parser = Parser::new_ext(markdown, parser_options());
 
     // Collect and transform events
     let mut events = Vec::new();
@@ -116,19 +70,25 @@ pub fn parse_to_html_with_base_path(markdown: &str, base_path: &str) -> String {
             }
             Event::End(pulldown_cmark::TagEnd::Heading(_)) => {
                 in_heading = false;
-                if !heading_text.is_empty() {
-                    let id = generate_id(&heading_text);
-                    // Update the heading start event with the ID
-                    if let Some(Event::Start(Tag::Heading { level, classes, .. })) =
-                        events.get_mut(heading_start_index)
+                // Sanitized headings never keep `{...}` attributes or classes,
+                // whatever the heading contains
+                if sanitize || !heading_text.is_empty() {
+                    if let Some(Event::Start(Tag::Heading {
+                        level, id, classes, ..
+                    })) = events.get_mut(heading_start_index)
                     {
-                        *events.get_mut(heading_start_index).unwrap() =
-                            Event::Start(Tag::Heading {
-                                level: *level,
-                                id: Some(CowStr::from(id)),
-                                classes: classes.clone(),
-                                attrs: vec![],
-                            });
+                        let id = if heading_text.is_empty() {
+                            id.clone()
+                        } else {
+                            Some(CowStr::from(generate_id(&heading_text)))
+                        };
+                        let classes = if sanitize { vec![] } else { classes.clone() };
+                        events[heading_start_index] = Event::Start(Tag::Heading {
+                            level: *level,
+                            id,
+                            classes,
+                            attrs: vec![],
+                        });
                     }
                 }
                 events.push(event);
@@ -137,13 +97,32 @@ pub fn parse_to_html_with_base_path(markdown: &str, base_path: &str) -> String {
                 heading_text.push_str(text);
                 events.push(event.clone());
             }
+            Event::Html(raw) | Event::InlineHtml(raw) if sanitize => {
+                // Show raw HTML as literal text instead of injecting it
+                events.push(Event::Text(raw.clone()));
+            }
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) if sanitize && !is_safe_url(dest_url) => {
+                events.push(Event::Start(Tag::Link {
+                    link_type: *link_type,
+                    dest_url: CowStr::from("#"),
+                    title: title.clone(),
+                    id: id.clone(),
+                }));
+            }
             Event::Start(Tag::Image {
                 link_type,
                 dest_url,
                 title,
                 id,
             }) => {
-                let new_url = if !dest_url.starts_with("http://")
+                let new_url = if sanitize && !is_safe_url(dest_url) {
+                    CowStr::from("")
+                } else if !dest_url.starts_with("http://")
                     && !dest_url.starts_with("https://")
                     && !dest_url.starts_with("data:")
                     && base_dir.is_some()
@@ -174,8 +153,267 @@ pub fn parse_to_html_with_base_path(markdown: &str, base_path: &str) -> String {
         }
     }
 
+    events
+}
+
+pub fn parse_to_html(markdown: &str) -> String {
+    render_html(markdown, None, false)
+}
+
+pub fn parse_to_html_with_base_path(markdown: &str, base_path: &str) -> String {
+    render_html(markdown, Path::new(base_path).parent(), false)
+}
+
+/// Renders markdown to an HTML fragment, resolving images against `base_dir`
+/// and optionally neutralizing raw HTML and script URLs
+pub fn render_html(markdown: &str, base_dir: Option<&Path>, sanitize: bool) -> String {
     let mut html_output = String::new();
-    html::push_html(&mut html_output, events.into_iter());
+    html::push_html(
+        &mut html_output,
+        transform_events(markdown, base_dir, sanitize).into_iter(),
+    );
 
     html_output
 }
+
+/// Wraps a rendered fragment in a standalone HTML page styled like the viewer
+pub fn render_page(title: &str, body_html: &str) -> String {
+    let mut escaped_title = String::new();
+    html::push_html(
+        &mut escaped_title,
+        std::iter::once(Event::Text(CowStr::from(title))),
+    );
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en" data-theme="light">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{}</title>
+<style>
+:root {{
+  --primary-color: #0066cc;
+  --text-primary: #1a1a1a;
+  --text-secondary: #666666;
+  --border-color: #141414;
+  --code-bg: #f5f5f5;
+  --table-stripe: rgba(0, 0, 0, 0.02);
+  --blockquote-border: #d0d7de;
+}}
+body {{
+  margin: 0;
+  font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, "Helvetica Neue", Arial, sans-serif;
+}}
+.markdown-content {{
+  max-width: 900px;
+  margin: 0 auto;
+  padding: 40px 24px;
+}}
+{}
+</style>
+</head>
+<body>
+<article class="markdown-content">
+{}</article>
+</body>
+</html>
+"#,
+        escaped_title.trim(),
+        MARKDOWN_CSS,
+        body_html
+    )
+}
+
+/// Returns the text of the first heading in a document
+pub fn first_heading(markdown: &str) -> Option<String> {
+    let mut in_heading = false;
+    let mut title = String::new();
+
+    for event in Parser::new_ext(markdown, parser_options()) {
+        match event {
+            Event::Start(Tag::Heading { .. }) => {
+                in_heading = true;
+            }
+            Event::Text(text) | Event::Code(text) if in_heading => {
+                title.push_str(&text);
+            }
+            Event::End(pulldown_cmark::TagEnd::Heading(_)) => {
+                if !title.trim().is_empty() {
+                    return Some(title.trim().to_string());
+                }
+                in_heading = false;
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Returns the IDs the renderer assigns to each heading, in document order
+pub fn heading_ids(markdown: &str) -> Vec<String> {
+    let mut ids = Vec::new();
+    let mut in_heading = false;
+    let mut heading_text = String::new();
+
+    for event in Parser::new_ext(markdown, parser_options()) {
+        match event {
+            Event::Start(Tag::Heading { .. }) => {
+                in_heading = true;
+                heading_text.clear();
+            }
+            // Only plain text feeds the ID, matching `transform_events`
+            Event::Text(text) if in_heading => {
+                heading_text.push_str(&text);
+            }
+            Event::End(pulldown_cmark::TagEnd::Heading(_)) => {
+                in_heading = false;
+                if !heading_text.is_empty() {
+                    ids.push(generate_id(&heading_text));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    ids
+}
+
+/// Maps byte offsets in a document to 1-based line and column numbers
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+        LineIndex { line_starts }
+    }
+
+    /// Returns the (line, column) of a byte offset, both starting at 1
+    pub fn position(&self, offset: usize) -> (usize, usize) {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        };
+        (line + 1, offset - self.line_starts[line] + 1)
+    }
+}
+
+/// Splits YAML (`---`) or TOML (`+++`) front matter off the start of a document.
+/// The front matter is returned with its delimiter lines.
+pub fn split_front_matter(content: &str) -> (Option<&str>, &str) {
+    let first_line_end = match content.find('\n') {
+        Some(end) => end,
+        None => return (None, content),
+    };
+
+    let delimiter = content[..first_line_end].trim_end();
+    if delimiter != "---" && delimiter != "+++" {
+        return (None, content);
+    }
+
+    let mut offset = first_line_end + 1;
+    for line in content[offset..].split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        offset += line.len();
+        if trimmed == delimiter || (delimiter == "---" && trimmed == "...") {
+            return (Some(&content[..offset]), &content[offset..]);
+        }
+    }
+
+    (None, content)
+}
+
+/// The document's title: a `title` in its front matter, otherwise its first heading
+pub fn document_title(content: &str) -> Option<String> {
+    let (front_matter, body) = split_front_matter(content);
+    front_matter
+        .and_then(front_matter_title)
+        .or_else(|| first_heading(body))
+}
+
+/// Reads a top-level `title: ...` (YAML) or `title = "..."` (TOML) line
+fn front_matter_title(front_matter: &str) -> Option<String> {
+    front_matter.lines().find_map(|line| {
+        let rest = line.strip_prefix("title")?.trim_start();
+        let value = rest
+            .strip_prefix(':')
+            .or_else(|| rest.strip_prefix('='))?
+            .trim();
+        let unquoted = ['"', '\'']
+            .iter()
+            .find_map(|&quote| value.strip_prefix(quote)?.strip_suffix(quote))
+            .unwrap_or(value);
+        (!unquoted.is_empty()).then(|| unquoted.to_string())
+    })
+}
+
+/// The `tags` (or `keywords`) listed in a document's front matter, written as
+/// `tags: [a, b]`, `tags: a, b`, a YAML block list or `tags = ["a", "b"]`
+pub fn document_tags(content: &str) -> Vec<String> {
+    let Some(front_matter) = split_front_matter(content).0 else {
+        return Vec::new();
+    };
+
+    let mut lines = front_matter.lines().skip(1);
+    while let Some(line) = lines.next() {
+        let Some(rest) = ["tags", "keywords"]
+            .iter()
+            .find_map(|key| line.strip_prefix(key))
+        else {
+            continue;
+        };
+        let Some(value) = rest
+            .trim_start()
+            .strip_prefix(':')
+            .or_else(|| rest.trim_start().strip_prefix('='))
+        else {
+            continue;
+        };
+
+        let value = value.trim();
+        let items: Vec<&str> = if value.is_empty() {
+            // A block list on the following indented `- item` lines
+            lines
+                .clone()
+                .map_while(|line| line.trim_start().strip_prefix("- "))
+                .collect()
+        } else {
+            let value = value
+                .strip_prefix('[')
+                .and_then(|value| value.strip_suffix(']'))
+                .unwrap_or(value);
+            value.split(',').collect()
+        };
+
+        return items
+            .into_iter()
+            .map(|item| item.trim().trim_matches(['"', '\'']).trim())
+            .filter(|item| !item.is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_drops_heading_attributes() {
+        for markdown in [
+            "# Title {onclick=alert(1)}",
+            "# `code` {onclick=alert(1)}",
+            "# ![x](y.png) {.evil onclick=alert(1)}",
+        ] {
+            let html = render_html(markdown, None, true);
+            assert!(!html.contains("onclick"), "{}", html);
+            assert!(!html.contains("class="), "{}", html);
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::files;
+
+/// Points for each matched character
+const SCORE_MATCH: i64 = 16;
+/// Extra points for a match at the start of a word or path segment
+const BONUS_BOUNDARY: i64 = 10;
+/// Extra points for a match at an uppercase letter after a lowercase one
+const BONUS_CAMEL: i64 = 8;
+/// Extra points for a match right after the previous one
+const BONUS_CONSECUTIVE: i64 = 8;
+/// Extra points for a match in the file name rather than its folders
+const BONUS_FILE_NAME: i64 = 6;
+/// Cost of skipping characters between two matches
+const PENALTY_GAP_START: i64 = 3;
+const PENALTY_GAP_EXTENSION: i64 = 1;
+
+/// Extra points for the most recently opened file; older ones get less
+const BONUS_RECENT: i64 = 30;
+const BONUS_RECENT_STEP: i64 = 3;
+
+/// Longest a file list is kept without walking the folder again, for changes
+/// the file watcher doesn't see
+const RESCAN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A file that matches a quick open query, best first
+#[derive(Debug, Clone, Serialize)]
+pub struct QuickOpenMatch {
+    pub path: String,
+    /// The path below the root, with `/` separators
+    pub relative_path: String,
+    pub title: Option<String>,
+    pub score: i64,
+    /// Matched character positions in `relative_path`
+    pub path_positions: Vec<usize>,
+    /// Matched character positions in `title`, when it matched better than the path
+    pub title_positions: Vec<usize>,
+}
+
+/// A file's modification time when its title was read, and the title
+type CachedTitle = (Option<SystemTime>, Option<String>);
+
+/// Document titles, kept until the file's modification time changes
+#[derive(Default)]
+pub struct TitleCache(Mutex<HashMap<PathBuf, CachedTitle>>);
+
+impl TitleCache {
+    fn title(&self, path: &Path) -> Option<String> {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        let mut titles = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((cached_modified, title)) = titles.get(path) {
+            if modified.is_some() && *cached_modified == modified {
+                return title.clone();
+            }
+        }
+
+        let title = files::read_title(path);
+        titles.insert(path.to_path_buf(), (modified, title.clone()));
+        title
+    }
+}
+
+/// The Markdown files under each root, kept between keystrokes until the file
+/// watcher sees one created, deleted or moved
+#[derive(Default)]
+pub struct FileLists(Mutex<HashMap<PathBuf, FileList>>);
+
+#[derive(Default)]
+struct FileList {
+    /// When the folder was last walked, unless the watcher saw a change since
+    scanned: Option<Instant>,
+    files: Arc<Vec<PathBuf>>,
+}
+
+impl FileLists {
+    fn files(&self, root: &str) -> Result<Arc<Vec<PathBuf>>, String> {
+        let root_path = PathBuf::from(root);
+        {
+            let mut lists = self.0.lock().unwrap_or_else(|e| e.into_inner());
+            let list = lists.entry(root_path.clone()).or_default();
+            if list
+                .scanned
+                .is_some_and(|at| at.elapsed() <= RESCAN_INTERVAL)
+            {
+                return Ok(list.files.clone());
+            }
+            list.scanned = Some(Instant::now());
+        }
+
+        // Walk without holding the lock; a change seen meanwhile clears
+        // `scanned` again so the next query walks once more
+        let files = match files::walk_markdown_files(root) {
+            Ok(files) => Arc::new(files),
+            Err(e) => {
+                self.invalidate(&root_path);
+                return Err(e);
+            }
+        };
+        let mut lists = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        lists.entry(root_path).or_default().files = files.clone();
+        Ok(files)
+    }
+
+    /// Marks the list of every root `path` is under as needing a walk
+    pub fn invalidate(&self, path: &Path) {
+        let mut lists = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        for (root, list) in lists.iter_mut() {
+            if path.starts_with(root) {
+                list.scanned = None;
+            }
+        }
+    }
+}
+
+/// Ranks the Markdown files under `root` by how well their path or title
+/// fuzzy-matches `query`. Files in `recent`, most recent first, rank higher;
+/// with an empty query they come first and the rest follow by path.
+pub fn quick_open(
+    file_lists: &FileLists,
+    titles: &TitleCache,
+    root: &str,
+    query: &str,
+    recent: &[String],
+    limit: usize,
+) -> Result<Vec<QuickOpenMatch>, String> {
+    let root_path = Path::new(root);
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let recent_rank: HashMap<&Path, usize> = recent
+        .iter()
+        .enumerate()
+        .map(|(rank, path)| (Path::new(path.as_str()), rank))
+        .collect();
+
+    let mut matches = Vec::new();
+    for path in file_lists.files(root)?.iter() {
+        let relative_path = path
+            .strip_prefix(root_path)
+            .unwrap_or(path)
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let title = titles.title(path);
+        let recent_bonus = recent_rank.get(path.as_path()).map_or(0, |&rank| {
+            (BONUS_RECENT - BONUS_RECENT_STEP * rank as i64).max(BONUS_RECENT_STEP)
+        });
+
+        let mut found = QuickOpenMatch {
+            path: path.to_string_lossy().to_string(),
+            relative_path,
+            title,
+            score: recent_bonus,
+            path_positions: Vec::new(),
+            title_positions: Vec::new(),
+        };
+
+        if !query.is_empty() {
+            let file_name_start = found
+                .relative_path
+                .rfind('/')
+                .map_or(0, |slash| found.relative_path[..slash + 1].chars().count());
+            let by_path = fuzzy_match(&query, &found.relative_path, file_name_start);
+            let by_title = found
+                .title
+                .as_deref()
+                .and_then(|title| fuzzy_match(&query, title, 0));
+
+            match (by_path, by_title) {
+                (None, None) => continue,
+                (Some(by_path), Some((score, positions))) if score > by_path.0 => {
+                    found.score += score;
+                    found.title_positions = positions;
+                }
+                (Some((score, positions)), _) => {
+                    found.score += score;
+                    found.path_positions = positions;
+                }
+                (None, Some((score, positions))) => {
+                    found.score += score;
+                    found.title_positions = positions;
+                }
+            }
+        }
+
+        matches.push(found);
+    }
+
+    matches.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(a.relative_path.len().cmp(&b.relative_path.len()))
+            .then_with(|| a.relative_path.cmp(&b.relative_path))
+    });
+    matches.truncate(limit);
+    Ok(matches)
+}
+
+/// Scores `text` against a lowercase `query` whose characters must all appear
+/// in order, returning the score and the positions of the best alignment.
+/// Characters from `file_name_start` on count extra.
+fn fuzzy_match(query: &[char], text: &str, file_name_start: usize) -> Option<(i64, Vec<usize>)> {
+    let original: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = original
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    let (n, m) = (query.len(), lower.len());
+    if n == 0 || n > m {
+        return None;
+    }
+
+    let bonus: Vec<i64> = (0..m)
+        .map(|j| {
+            let mut bonus = SCORE_MATCH;
+            let previous = j.checked_sub(1).map(|p| original[p]);
+            match previous {
+                None => bonus += BONUS_BOUNDARY,
+                Some('/' | '\\' | '_' | '-' | '.' | ' ') => bonus += BONUS_BOUNDARY,
+                Some(p) if p.is_lowercase() && original[j].is_uppercase() => bonus += BONUS_CAMEL,
+                _ => {}
+            }
+            if j >= file_name_start {
+                bonus += BONUS_FILE_NAME;
+            }
+            bonus
+        })
+        .collect();
+
+    // best[i][j]: the best score with query[i] matched at text[j];
+    // from[i][j]: where query[i - 1] was matched for that score
+    let mut best = vec![vec![None::<i64>; m]; n];
+    let mut from = vec![vec![0usize; m]; n];
+
+    for j in 0..m {
+        if lower[j] == query[0] {
+            best[0][j] = Some(bonus[j]);
+        }
+    }
+
+    for i in 1..n {
+        // The best earlier match to jump from, less the cost of the gap so far
+        let mut gap: Option<(i64, usize)> = None;
+        for j in i..m {
+            if j >= 2 {
+                let extended = gap.map(|(score, k)| (score - PENALTY_GAP_EXTENSION, k));
+                let opened = best[i - 1][j - 2].map(|score| (score - PENALTY_GAP_START, j - 2));
+                gap = match (extended, opened) {
+                    (Some(a), Some(b)) => Some(if b.0 >= a.0 { b } else { a }),
+                    (a, b) => a.or(b),
+                };
+            }
+            if lower[j] != query[i] {
+                continue;
+            }
+
+            let consecutive = best[i - 1][j - 1].map(|score| (score + BONUS_CONSECUTIVE, j - 1));
+            let previous = match (consecutive, gap) {
+                (Some(a), Some(b)) => Some(if a.0 >= b.0 { a } else { b }),
+                (a, b) => a.or(b),
+            };
+            if let Some((score, k)) = previous {
+                best[i][j] = Some(score + bonus[j]);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let (mut j, score) = best[n - 1]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, score)| score.map(|score| (j, score)))
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))?;
+
+    let mut positions = vec![0; n];
+    for i in (0..n).rev() {
+        positions[i] = j;
+        j = from[i][j];
+    }
+    Some((score, positions))
+}
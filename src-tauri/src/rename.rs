@@ -0,0 +1,514 @@
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::files::{self, WriteFailure};
+use crate::link_discovery;
+use crate::markdown::LineIndex;
+
+/// A link whose destination changes because of a move
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkEdit {
+    pub line: usize,
+    pub old_url: String,
+    pub new_url: String,
+}
+
+/// A document whose links change
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentEdits {
+    /// Where the document is now
+    pub path: String,
+    /// Where it ends up, which differs when it is the moved file or inside the moved folder
+    pub new_path: String,
+    pub edits: Vec<LinkEdit>,
+    #[serde(skip)]
+    content: String,
+}
+
+/// A link that needs a new destination but is written in a way that can't be
+/// rewritten in place, such as one using character references
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedLink {
+    pub path: String,
+    pub line: usize,
+    pub url: String,
+}
+
+/// Everything a rename would change, shown to the user before applying it
+#[derive(Debug, Clone, Serialize)]
+pub struct RenamePlan {
+    pub old_path: String,
+    pub new_path: String,
+    pub documents: Vec<DocumentEdits>,
+    /// Links left for the user to fix by hand
+    pub skipped: Vec<SkippedLink>,
+    /// Documents whose links could not be rewritten, once the plan is applied
+    pub failed: Vec<WriteFailure>,
+}
+
+/// Maps paths at or below the moved file or folder to their new location
+struct Move {
+    from: PathBuf,
+    to: PathBuf,
+}
+
+impl Move {
+    fn apply(&self, path: &Path) -> PathBuf {
+        match path.strip_prefix(&self.from) {
+            Ok(rest) if rest.as_os_str().is_empty() => self.to.clone(),
+            Ok(rest) => self.to.join(rest),
+            Err(_) => path.to_path_buf(),
+        }
+    }
+}
+
+/// Works out which links in the Markdown files under `root` would break when
+/// `old_path` moves to `new_path`, and how to rewrite them. Links inside the
+/// moved file or folder are adjusted too. Nothing is changed on disk.
+pub fn plan_rename(
+    root: Option<&str>,
+    old_path: &str,
+    new_path: &str,
+) -> Result<RenamePlan, String> {
+    let from = Path::new(old_path)
+        .canonicalize()
+        .map_err(|_| format!("Path not found: {}", old_path))?;
+    let to = resolve_new_path(new_path)?;
+
+    if to.starts_with(&from) && to != from {
+        return Err("Can't move a folder into itself".to_string());
+    }
+    // Renaming only the case of a name finds the same file on some file systems
+    let same_file = to
+        .canonicalize()
+        .map(|existing| existing == from)
+        .unwrap_or(false);
+    if to.exists() && !same_file {
+        return Err(format!("{} already exists", to.display()));
+    }
+
+    let root = match root {
+        Some(root) => Some(
+            Path::new(root)
+                .canonicalize()
+                .map_err(|_| format!("Path not found: {}", root))?,
+        ),
+        None => None,
+    };
+    let moved = Move { from, to };
+
+    let mut documents = BTreeSet::new();
+    if let Some(root) = &root {
+        documents.extend(files::walk_markdown_files(&root.to_string_lossy())?);
+    }
+    // The moved file or folder may be outside the workspace
+    if moved.from.is_dir() || files::is_markdown_file(&moved.from) {
+        documents.extend(files::walk_markdown_files(&moved.from.to_string_lossy())?);
+    }
+
+    let mut plan = RenamePlan {
+        old_path: moved.from.to_string_lossy().to_string(),
+        new_path: moved.to.to_string_lossy().to_string(),
+        documents: Vec::new(),
+        skipped: Vec::new(),
+        failed: Vec::new(),
+    };
+
+    for document in documents {
+        let document = document.canonicalize().unwrap_or(document);
+        let content = match files::read_file_contents(&document.to_string_lossy()) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Skipping {}: {}", document.display(), e);
+                continue;
+            }
+        };
+
+        let (edits, skipped) = rewrite_document(&document, &content, root.as_deref(), &moved);
+        plan.documents.extend(edits);
+        plan.skipped.extend(skipped);
+    }
+
+    Ok(plan)
+}
+
+/// Moves `old_path` to `new_path` and rewrites the links that pointed at it
+/// with `write`. Every document is attempted; those that fail are listed in
+/// the returned plan's `failed`.
+pub fn apply_rename(
+    root: Option<&str>,
+    old_path: &str,
+    new_path: &str,
+    mut write: impl FnMut(&str, &str) -> Result<(), String>,
+) -> Result<RenamePlan, String> {
+    let mut plan = plan_rename(root, old_path, new_path)?;
+    files::rename_file_or_directory(&plan.old_path, &plan.new_path)?;
+
+    for document in &plan.documents {
+        if let Err(message) = write(&document.new_path, &document.content) {
+            plan.failed.push(WriteFailure {
+                path: document.new_path.clone(),
+                message,
+            });
+        }
+    }
+
+    Ok(plan)
+}
+
+/// The new path with its folder resolved like the old one, so the two compare
+fn resolve_new_path(new_path: &str) -> Result<PathBuf, String> {
+    let path = Path::new(new_path);
+    let name = path
+        .file_name()
+        .ok_or_else(|| format!("Invalid name: {}", new_path))?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let parent = parent
+        .canonicalize()
+        .map_err(|_| format!("Folder not found: {}", parent.display()))?;
+    Ok(parent.join(name))
+}
+
+/// The document with its links rewritten, if any change, and the links that
+/// would have to change but can't be found in the source
+fn rewrite_document(
+    document: &Path,
+    content: &str,
+    root: Option<&Path>,
+    moved: &Move,
+) -> (Option<DocumentEdits>, Vec<SkippedLink>) {
+    let new_document = moved.apply(document);
+    let line_index = LineIndex::new(content);
+
+    let mut rewritten = String::with_capacity(content.len());
+    let mut copied = 0;
+    let mut edits = Vec::new();
+
+    let (destinations, unmatched) = link_discovery::extract_link_destinations(content);
+    for destination in destinations {
+        let Some(written) = rewrite_url(
+            &destination.url,
+            destination.angle_brackets,
+            document,
+            &new_document,
+            root,
+            moved,
+        ) else {
+            continue;
+        };
+
+        rewritten.push_str(&content[copied..destination.span.start]);
+        rewritten.push_str(&written);
+        copied = destination.span.end;
+
+        edits.push(LinkEdit {
+            line: line_index.position(destination.span.start).0,
+            old_url: destination.url,
+            new_url: written,
+        });
+    }
+
+    let skipped = unmatched
+        .into_iter()
+        .filter(|link| {
+            rewrite_url(&link.url, false, document, &new_document, root, moved).is_some()
+        })
+        .map(|link| SkippedLink {
+            path: new_document.to_string_lossy().to_string(),
+            line: line_index.position(link.offset).0,
+            url: link.url,
+        })
+        .collect();
+
+    if edits.is_empty() {
+        return (None, skipped);
+    }
+    rewritten.push_str(&content[copied..]);
+
+    let edits = DocumentEdits {
+        path: document.to_string_lossy().to_string(),
+        new_path: new_document.to_string_lossy().to_string(),
+        edits,
+        content: rewritten,
+    };
+    (Some(edits), skipped)
+}
+
+/// The destination a link needs after the move, or `None` if it still works.
+/// The `?query#fragment` part is kept as written.
+fn rewrite_url(
+    url: &str,
+    angle_brackets: bool,
+    document: &Path,
+    new_document: &Path,
+    root: Option<&Path>,
+    moved: &Move,
+) -> Option<String> {
+    if url.is_empty() || link_discovery::is_external_link(url) {
+        return None;
+    }
+
+    let (path_part, suffix) = url.split_at(url.find(['?', '#']).unwrap_or(url.len()));
+    let link_path = link_discovery::percent_decode(path_part);
+    if link_path.is_empty() {
+        return None;
+    }
+
+    let base_dir = document.parent()?;
+    let (target, root_relative) = match link_path.strip_prefix('/') {
+        Some(absolute) => (root?.join(absolute), true),
+        None => (base_dir.join(&link_path), false),
+    };
+    // Links that were already broken are left alone
+    let target = target.canonicalize().ok()?;
+    let new_target = moved.apply(&target);
+
+    let document_moved = new_document != document;
+    if new_target == target && (root_relative || !document_moved) {
+        return None;
+    }
+
+    // A root-relative link to something moved out of the root can only be
+    // written relative to the document
+    let mut new_path = match root.filter(|root| root_relative && new_target.starts_with(root)) {
+        Some(root) => format!("/{}", relative_path(root, &new_target)),
+        None => relative_path(new_document.parent()?, &new_target),
+    };
+    if link_path.starts_with("./") && !new_path.starts_with("../") {
+        new_path = format!("./{}", new_path);
+    }
+    if new_path == link_path {
+        return None;
+    }
+
+    Some(format!(
+        "{}{}",
+        encode_link_path(&new_path, angle_brackets),
+        suffix
+    ))
+}
+
+/// Percent-encodes the characters that would end the destination or start
+/// its query or fragment. Inside `<...>` spaces and parentheses are allowed.
+fn encode_link_path(path: &str, angle_brackets: bool) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for c in path.chars() {
+        let escape = match c {
+            '%' | '#' | '?' | '<' | '>' | '\\' => true,
+            ' ' | '(' | ')' => !angle_brackets,
+            c => c.is_control(),
+        };
+        if escape {
+            let mut buffer = [0; 4];
+            for byte in c.encode_utf8(&mut buffer).bytes() {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        } else {
+            encoded.push(c);
+        }
+    }
+    encoded
+}
+
+/// A `/`-separated path from the folder `from` to `to`
+fn relative_path(from: &Path, to: &Path) -> String {
+    let from: Vec<_> = from.components().collect();
+    let to: Vec<_> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let parts: Vec<String> = std::iter::repeat_n("..".to_string(), from.len() - common)
+        .chain(
+            to[common..]
+                .iter()
+                .map(|component| component.as_os_str().to_string_lossy().to_string()),
+        )
+        .collect();
+
+    if parts.is_empty() {
+        ".".to_string()
+    } else {
+        parts.join("/")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A fresh folder holding `files`, removed again when dropped
+    struct Workspace(PathBuf);
+
+    impl Workspace {
+        fn new(name: &str, files: &[(&str, &str)]) -> Workspace {
+            let dir =
+                std::env::temp_dir().join(format!("mdox-rename-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            for (path, content) in files {
+                let path = dir.join(path);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, content).unwrap();
+            }
+            Workspace(dir.canonicalize().unwrap())
+        }
+
+        fn path(&self, path: &str) -> PathBuf {
+            self.0.join(path)
+        }
+
+        fn plan(&self, from: &str, to: &str) -> RenamePlan {
+            plan_rename(
+                Some(&self.0.to_string_lossy()),
+                &self.path(from).to_string_lossy(),
+                &self.path(to).to_string_lossy(),
+            )
+            .unwrap()
+        }
+    }
+
+    impl Drop for Workspace {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// The new destinations `document` gets when `from` moves to `to`
+    fn rewritten(workspace: &Workspace, document: &str, from: &str, to: &str) -> Vec<String> {
+        let moved = Move {
+            from: workspace.path(from),
+            to: workspace.path(to),
+        };
+        let document = workspace.path(document);
+        let content = fs::read_to_string(&document).unwrap();
+        let (edits, _) = rewrite_document(&document, &content, Some(&workspace.0), &moved);
+        edits
+            .map(|edits| edits.edits.into_iter().map(|edit| edit.new_url).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn links_to_the_moved_file_follow_it() {
+        let workspace = Workspace::new(
+            "follow",
+            &[
+                (
+                    "index.md",
+                    "[a](guide.md#setup) [b](./guide.md) [c](other.md) [d](https://x.org/guide.md)",
+                ),
+                ("guide.md", ""),
+                ("other.md", ""),
+            ],
+        );
+        assert_eq!(
+            rewritten(&workspace, "index.md", "guide.md", "docs/guide.md"),
+            ["docs/guide.md#setup", "./docs/guide.md"]
+        );
+    }
+
+    #[test]
+    fn the_moved_document_keeps_its_own_links_working() {
+        let workspace = Workspace::new(
+            "own",
+            &[
+                ("notes/a.md", "[b](b.md) [root](/index.md) [c](../c.md)"),
+                ("notes/b.md", ""),
+                ("index.md", ""),
+                ("c.md", ""),
+            ],
+        );
+        assert_eq!(
+            rewritten(&workspace, "notes/a.md", "notes/a.md", "a.md"),
+            ["notes/b.md", "c.md"]
+        );
+    }
+
+    #[test]
+    fn root_relative_links_stay_root_relative() {
+        let workspace = Workspace::new(
+            "root",
+            &[
+                ("docs/a.md", "[b](/b.md)"),
+                ("b.md", ""),
+                ("inner/x.md", ""),
+            ],
+        );
+        assert_eq!(
+            rewritten(&workspace, "docs/a.md", "b.md", "inner/b.md"),
+            ["/inner/b.md"]
+        );
+    }
+
+    #[test]
+    fn root_relative_links_out_of_the_root_become_relative() {
+        let workspace =
+            Workspace::new("outside", &[("site/a.md", "[b](/b.md)"), ("site/b.md", "")]);
+        let moved = Move {
+            from: workspace.path("site/b.md"),
+            to: workspace.path("b.md"),
+        };
+        let document = workspace.path("site/a.md");
+        let url = rewrite_url(
+            "/b.md",
+            false,
+            &document,
+            &document,
+            Some(&workspace.path("site")),
+            &moved,
+        );
+        assert_eq!(url.as_deref(), Some("../b.md"));
+    }
+
+    #[test]
+    fn links_written_with_character_references_are_rewritten() {
+        let workspace = Workspace::new(
+            "entities",
+            &[("index.md", "[x](a&amp;b.md)"), ("a&b.md", "")],
+        );
+        let plan = workspace.plan("a&b.md", "c&d.md");
+        assert_eq!(plan.documents.len(), 1);
+        assert_eq!(plan.documents[0].edits[0].new_url, "c&d.md");
+        assert!(plan.skipped.is_empty());
+    }
+
+    #[test]
+    fn links_that_cant_be_located_are_reported() {
+        let workspace = Workspace::new(
+            "skipped",
+            &[("index.md", "intro\n\n[x](&copy;.md)"), ("©.md", "")],
+        );
+        let plan = workspace.plan("©.md", "c.md");
+        assert!(plan.documents.is_empty());
+        assert_eq!(plan.skipped.len(), 1);
+        assert_eq!(plan.skipped[0].line, 3);
+        assert_eq!(plan.skipped[0].url, "©.md");
+    }
+
+    #[test]
+    fn encode_link_path_escapes_what_ends_a_destination() {
+        assert_eq!(encode_link_path("a b(1).md", false), "a%20b%281%29.md");
+        assert_eq!(encode_link_path("a b(1).md", true), "a b(1).md");
+        assert_eq!(encode_link_path("100%#?.md", true), "100%25%23%3F.md");
+        assert_eq!(encode_link_path("é.md", false), "é.md");
+    }
+
+    #[test]
+    fn relative_path_walks_up_and_down() {
+        assert_eq!(
+            relative_path(Path::new("/a/b"), Path::new("/a/b/c.md")),
+            "c.md"
+        );
+        assert_eq!(
+            relative_path(Path::new("/a/b"), Path::new("/a/c/d.md")),
+            "../c/d.md"
+        );
+        assert_eq!(
+            relative_path(Path::new("/a/b"), Path::new("/x.md")),
+            "../../x.md"
+        );
+        assert_eq!(relative_path(Path::new("/a/b"), Path::new("/a/b")), ".");
+    }
+}
@@ -0,0 +1,311 @@
+use pulldown_cmark::{Event, Parser, Tag};
+use regex::{Captures, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use crate::files::{self, WriteFailure};
+use crate::markdown::{self, LineIndex};
+
+/// Most matches returned by one search, so a pattern like `e` can't flood the UI
+const MAX_MATCHES: usize = 5000;
+
+/// Longest stretch of the line shown on each side of a match, in characters
+const CONTEXT_LENGTH: usize = 60;
+
+/// What to look for across the workspace
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FindOptions {
+    pub pattern: String,
+    /// Treat `pattern` as a regular expression rather than literal text
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    /// Leave code blocks and inline code alone
+    pub skip_code: bool,
+    pub skip_front_matter: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FindResult {
+    pub files: Vec<FileMatches>,
+    pub match_count: usize,
+    /// Set when the search stopped at `MAX_MATCHES`
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileMatches {
+    pub path: String,
+    pub matches: Vec<TextMatch>,
+}
+
+/// One match, with the rest of its line around it
+#[derive(Debug, Clone, Serialize)]
+pub struct TextMatch {
+    /// Byte offset in the file, which identifies the match when replacing
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+    pub before: String,
+    pub matched: String,
+    pub after: String,
+    /// What the match becomes, when a replacement was given
+    pub replacement: Option<String>,
+}
+
+/// The matches to replace in one file, by offset
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReplaceSelection {
+    pub path: String,
+    pub offsets: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplaceSummary {
+    pub files_changed: usize,
+    pub replacements: usize,
+    /// The files rewritten
+    pub written: Vec<String>,
+    /// The files left alone, and why
+    pub failed: Vec<WriteFailure>,
+}
+
+/// The compiled search and how to expand its replacement
+struct Matcher {
+    regex: Regex,
+    expand: bool,
+    options: FindOptions,
+}
+
+impl Matcher {
+    fn new(options: &FindOptions) -> Result<Matcher, String> {
+        if options.pattern.is_empty() {
+            return Err("Enter something to search for".to_string());
+        }
+
+        let mut pattern = if options.regex {
+            options.pattern.clone()
+        } else {
+            regex::escape(&options.pattern)
+        };
+        if options.whole_word {
+            pattern = format!(r"\b(?:{})\b", pattern);
+        }
+
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!options.case_sensitive)
+            .multi_line(true)
+            .build()
+            .map_err(|e| format!("Invalid regular expression: {}", e))?;
+
+        Ok(Matcher {
+            regex,
+            expand: options.regex,
+            options: options.clone(),
+        })
+    }
+
+    /// The matches in `content` outside the parts the options skip
+    fn find<'c>(&self, content: &'c str) -> Vec<Captures<'c>> {
+        let skipped = self.skipped_ranges(content);
+        self.regex
+            .captures_iter(content)
+            .filter(|captures| {
+                let found = captures.get(0).map_or(0..0, |m| m.range());
+                !skipped.iter().any(|range| {
+                    found.start < range.end && range.start < found.end.max(found.start + 1)
+                })
+            })
+            .collect()
+    }
+
+    fn skipped_ranges(&self, content: &str) -> Vec<Range<usize>> {
+        let (front_matter, body) = markdown::split_front_matter(content);
+        let body_start = front_matter.map_or(0, str::len);
+
+        let mut ranges = Vec::new();
+        if self.options.skip_front_matter && body_start > 0 {
+            ranges.push(0..body_start);
+        }
+        if self.options.skip_code {
+            let parser = Parser::new_ext(body, markdown::parser_options()).into_offset_iter();
+            for (event, range) in parser {
+                if matches!(event, Event::Start(Tag::CodeBlock(_)) | Event::Code(_)) {
+                    ranges.push(body_start + range.start..body_start + range.end);
+                }
+            }
+        }
+        ranges
+    }
+
+    fn replacement(&self, captures: &Captures, replacement: &str) -> String {
+        if self.expand {
+            let mut expanded = String::new();
+            captures.expand(replacement, &mut expanded);
+            expanded
+        } else {
+            replacement.to_string()
+        }
+    }
+}
+
+/// Finds `options.pattern` in the Markdown files under `root`. With a
+/// `replacement`, each match also shows what it would become.
+pub fn find_in_files(
+    root: &str,
+    options: &FindOptions,
+    replacement: Option<&str>,
+) -> Result<FindResult, String> {
+    let matcher = Matcher::new(options)?;
+    let mut result = FindResult {
+        files: Vec::new(),
+        match_count: 0,
+        truncated: false,
+    };
+
+    for path in files::walk_markdown_files(root)? {
+        let path = path.to_string_lossy().to_string();
+        let content = match files::read_file_contents(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Skipping {}: {}", path, e);
+                continue;
+            }
+        };
+
+        let line_index = LineIndex::new(&content);
+        let mut matches = Vec::new();
+        for captures in matcher.find(&content) {
+            if result.match_count == MAX_MATCHES {
+                result.truncated = true;
+                break;
+            }
+            let Some(found) = captures.get(0) else {
+                continue;
+            };
+            let (line, column) = line_index.position(found.start());
+            let (before, after) = line_context(&content, found.range());
+            matches.push(TextMatch {
+                offset: found.start(),
+                line,
+                column,
+                before,
+                matched: found.as_str().to_string(),
+                after,
+                replacement: replacement.map(|text| matcher.replacement(&captures, text)),
+            });
+            result.match_count += 1;
+        }
+
+        if !matches.is_empty() {
+            result.files.push(FileMatches { path, matches });
+        }
+        if result.truncated {
+            break;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Replaces the selected matches in the Markdown files under `root` that
+/// aren't ignored. A file that changed since the search or can't be written
+/// is reported and skipped; the others are still replaced.
+pub fn replace_in_files(
+    root: &str,
+    options: &FindOptions,
+    replacement: &str,
+    selections: &[ReplaceSelection],
+    mut write: impl FnMut(&str, &str) -> Result<(), String>,
+) -> Result<ReplaceSummary, String> {
+    let matcher = Matcher::new(options)?;
+    let workspace: HashSet<PathBuf> = files::walk_markdown_files(root)?.into_iter().collect();
+    let mut summary = ReplaceSummary {
+        files_changed: 0,
+        replacements: 0,
+        written: Vec::new(),
+        failed: Vec::new(),
+    };
+
+    for selection in selections {
+        let replaced = if workspace.contains(&PathBuf::from(&selection.path)) {
+            replace_selection(&matcher, replacement, selection, &mut write)
+        } else {
+            Err("Not a searchable file in this folder".to_string())
+        };
+
+        match replaced {
+            Ok(0) => {}
+            Ok(count) => {
+                summary.replacements += count;
+                summary.written.push(selection.path.clone());
+            }
+            Err(message) => summary.failed.push(WriteFailure {
+                path: selection.path.clone(),
+                message,
+            }),
+        }
+    }
+
+    summary.files_changed = summary.written.len();
+    Ok(summary)
+}
+
+/// Replaces the selected matches in one file, returning how many were replaced
+fn replace_selection(
+    matcher: &Matcher,
+    replacement: &str,
+    selection: &ReplaceSelection,
+    write: &mut impl FnMut(&str, &str) -> Result<(), String>,
+) -> Result<usize, String> {
+    let content = files::read_file_contents(&selection.path)?;
+    let selected: HashSet<usize> = selection.offsets.iter().copied().collect();
+
+    let mut new_content = String::with_capacity(content.len());
+    let mut copied = 0;
+    let mut replaced = HashSet::new();
+    for captures in matcher.find(&content) {
+        let Some(found) = captures.get(0) else {
+            continue;
+        };
+        if !selected.contains(&found.start()) {
+            continue;
+        }
+        new_content.push_str(&content[copied..found.start()]);
+        new_content.push_str(&matcher.replacement(&captures, replacement));
+        copied = found.end();
+        replaced.insert(found.start());
+    }
+    new_content.push_str(&content[copied..]);
+
+    if replaced.len() != selected.len() {
+        return Err("Changed since the search; search again before replacing".to_string());
+    }
+    if new_content == content {
+        return Ok(0);
+    }
+    write(&selection.path, &new_content)?;
+    Ok(replaced.len())
+}
+
+/// The text on the match's first line before it, and on its last line after it
+fn line_context(content: &str, found: Range<usize>) -> (String, String) {
+    let line_start = content[..found.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = content[found.end..]
+        .find('\n')
+        .map_or(content.len(), |i| found.end + i);
+
+    let before = &content[line_start..found.start];
+    let skip = before.chars().count().saturating_sub(CONTEXT_LENGTH);
+    let before: String = before.chars().skip(skip).collect();
+    let after: String = content[found.end..line_end]
+        .trim_end_matches('\r')
+        .chars()
+        .take(CONTEXT_LENGTH)
+        .collect();
+
+    (before.trim_start().to_string(), after)
+}
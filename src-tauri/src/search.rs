@@ -0,0 +1,651 @@
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use rust_stemmers::{Algorithm, Stemmer};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::files;
+use crate::markdown::{self, LineIndex};
+
+/// How much a word counts for, by where it appears
+const HEADING_WEIGHT: f64 = 3.0;
+const TEXT_WEIGHT: f64 = 1.0;
+const CODE_WEIGHT: f64 = 0.75;
+
+/// How much a word that only starts with the last query word counts, compared
+/// with the whole word
+const PREFIX_WEIGHT: f64 = 0.5;
+
+/// BM25 parameters: how quickly repeated words stop adding to the score, and
+/// how much long documents are held back
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+/// Longest an index goes without scanning its files, for changes the file
+/// watcher doesn't see
+const RESCAN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Matching lines shown for each document
+const MATCHES_PER_DOCUMENT: usize = 3;
+
+/// Longest snippet shown for a matching line, in characters
+const SNIPPET_LENGTH: usize = 160;
+
+/// A document that matches a search, best first
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResult {
+    pub path: String,
+    pub title: String,
+    pub score: f64,
+    /// How many lines match, of which `matches` shows the best few
+    pub match_count: usize,
+    pub matches: Vec<LineMatch>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LineMatch {
+    /// Starting at 1
+    pub line: usize,
+    pub snippet: Vec<SnippetPart>,
+}
+
+/// A piece of a snippet, highlighted when it is a matched word
+#[derive(Debug, Clone, Serialize)]
+pub struct SnippetPart {
+    pub text: String,
+    pub highlight: bool,
+}
+
+/// The search index of each workspace root
+#[derive(Default)]
+pub struct SearchIndexes(Mutex<HashMap<PathBuf, Arc<WorkspaceIndex>>>);
+
+/// One root's index and whether the files under it need scanning again
+struct WorkspaceIndex {
+    index: Mutex<SearchIndex>,
+    /// When the files were last scanned, unless the watcher saw a change since
+    scanned: Mutex<Option<Instant>>,
+}
+
+impl SearchIndexes {
+    /// Searches the index of `root`, first bringing it up to date if files
+    /// under it changed. An empty query only updates the index, which is a
+    /// way to build it ahead of time.
+    pub fn search(
+        &self,
+        root: &str,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>, String> {
+        let root = Path::new(root)
+            .canonicalize()
+            .map_err(|_| format!("Directory not found: {}", root))?;
+
+        let workspace = {
+            let mut indexes = self.0.lock().unwrap_or_else(|e| e.into_inner());
+            indexes
+                .entry(root.clone())
+                .or_insert_with(|| {
+                    Arc::new(WorkspaceIndex {
+                        index: Mutex::new(SearchIndex::default()),
+                        scanned: Mutex::new(None),
+                    })
+                })
+                .clone()
+        };
+
+        // Scan without holding any index lock, so searches of an up to date
+        // index aren't kept waiting by the walk
+        let needs_scan = {
+            let mut scanned = workspace.scanned.lock().unwrap_or_else(|e| e.into_inner());
+            let stale = scanned.is_none_or(|at| at.elapsed() > RESCAN_INTERVAL);
+            if stale {
+                *scanned = Some(Instant::now());
+            }
+            stale
+        };
+        if needs_scan {
+            match scan(&root) {
+                Ok(found) => workspace
+                    .index
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .update(found),
+                Err(e) => {
+                    *workspace.scanned.lock().unwrap_or_else(|e| e.into_inner()) = None;
+                    return Err(e);
+                }
+            }
+        }
+
+        let index = workspace.index.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(index.search(query, limit))
+    }
+
+    /// Marks the index of every root `path` is under as needing a scan, after
+    /// the file watcher saw it change
+    pub fn invalidate(&self, path: &Path) {
+        let path = resolve_changed_path(path);
+        let indexes = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        for (root, workspace) in indexes.iter() {
+            if path.starts_with(root) {
+                *workspace.scanned.lock().unwrap_or_else(|e| e.into_inner()) = None;
+            }
+        }
+    }
+}
+
+/// The canonical form of a path that may no longer exist, so it compares with
+/// the canonical roots indexes are kept under
+pub fn resolve_changed_path(path: &Path) -> PathBuf {
+    if let Ok(path) = path.canonicalize() {
+        return path;
+    }
+    match (path.parent().map(Path::canonicalize), path.file_name()) {
+        (Some(Ok(parent)), Some(name)) => parent.join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// The Markdown files under `root` and when each was last modified
+fn scan(root: &Path) -> Result<Vec<(PathBuf, Option<SystemTime>)>, String> {
+    Ok(files::walk_markdown_files(&root.to_string_lossy())?
+        .into_iter()
+        .map(|path| {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+            (path, modified)
+        })
+        .collect())
+}
+
+/// An inverted index of the Markdown files under a folder
+#[derive(Default)]
+pub struct SearchIndex {
+    documents: HashMap<PathBuf, IndexedDocument>,
+    /// Each stemmed word and the documents it appears in
+    postings: HashMap<String, HashMap<PathBuf, Posting>>,
+    /// Sum of the document lengths, for the average BM25 needs
+    total_length: f64,
+}
+
+struct IndexedDocument {
+    modified: Option<SystemTime>,
+    title: String,
+    lines: Vec<String>,
+    /// Weighted number of words
+    length: f64,
+    terms: Vec<String>,
+}
+
+#[derive(Default)]
+struct Posting {
+    /// Occurrences, weighted by where they appear
+    weight: f64,
+    /// Lines the word appears on, starting at 1
+    lines: BTreeSet<usize>,
+}
+
+impl SearchIndex {
+    /// Re-reads the scanned files whose modification time changed since they
+    /// were indexed, adds new ones and drops those that are gone
+    fn update(&mut self, scanned: Vec<(PathBuf, Option<SystemTime>)>) {
+        let found: HashSet<&PathBuf> = scanned.iter().map(|(path, _)| path).collect();
+        let removed: Vec<PathBuf> = self
+            .documents
+            .keys()
+            .filter(|path| !found.contains(path))
+            .cloned()
+            .collect();
+        for path in removed {
+            self.remove(&path);
+        }
+
+        for (path, modified) in scanned {
+            let unchanged = self
+                .documents
+                .get(&path)
+                .is_some_and(|document| modified.is_some() && document.modified == modified);
+            if unchanged {
+                continue;
+            }
+
+            self.remove(&path);
+            match files::read_file_contents(&path.to_string_lossy()) {
+                Ok(content) => self.add(path, modified, &content),
+                Err(e) => eprintln!("Skipping {} in search index: {}", path.display(), e),
+            }
+        }
+    }
+
+    fn add(&mut self, path: PathBuf, modified: Option<SystemTime>, content: &str) {
+        let mut length = 0.0;
+        let mut terms: HashMap<String, Posting> = HashMap::new();
+        for (word, line, weight) in index_words(content) {
+            let posting = terms.entry(stem(&word)).or_default();
+            posting.weight += weight;
+            posting.lines.insert(line);
+            length += weight;
+        }
+
+        let title = markdown::document_title(content).unwrap_or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default()
+        });
+        let document = IndexedDocument {
+            modified,
+            title,
+            lines: content.lines().map(str::to_string).collect(),
+            length,
+            terms: terms.keys().cloned().collect(),
+        };
+
+        for (term, posting) in terms {
+            self.postings
+                .entry(term)
+                .or_default()
+                .insert(path.clone(), posting);
+        }
+        self.total_length += length;
+        self.documents.insert(path, document);
+    }
+
+    fn remove(&mut self, path: &Path) {
+        let Some(document) = self.documents.remove(path) else {
+            return;
+        };
+        for term in &document.terms {
+            if let Some(postings) = self.postings.get_mut(term) {
+                postings.remove(path);
+                if postings.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+        self.total_length -= document.length;
+    }
+
+    /// Documents containing every word of `query`, ranked with BM25. The last
+    /// word also matches longer words it is the start of, so results show up
+    /// while typing.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        let words: Vec<String> = tokenize(query).map(|(_, _, word)| word).collect();
+        let Some((last, rest)) = words.split_last() else {
+            return Vec::new();
+        };
+
+        // Each query word and the index terms it matches, with how much they count
+        let mut query_terms: Vec<Vec<(&str, f64)>> = rest
+            .iter()
+            .map(|word| self.exact_terms(&stem(word)))
+            .collect();
+        query_terms.push(self.prefix_terms(last));
+
+        let Some((first, others)) = query_terms.split_first() else {
+            return Vec::new();
+        };
+        let candidates: HashSet<&PathBuf> = first
+            .iter()
+            .flat_map(|(term, _)| self.postings[*term].keys())
+            .filter(|path| {
+                others.iter().all(|terms| {
+                    terms
+                        .iter()
+                        .any(|(term, _)| self.postings[*term].contains_key(*path))
+                })
+            })
+            .collect();
+
+        let mut results: Vec<SearchResult> = candidates
+            .into_iter()
+            .filter_map(|path| self.result(path, &query_terms))
+            .collect();
+        results.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        results.truncate(limit);
+        results
+    }
+
+    fn exact_terms(&self, term: &str) -> Vec<(&str, f64)> {
+        self.postings
+            .get_key_value(term)
+            .map(|(term, _)| vec![(term.as_str(), 1.0)])
+            .unwrap_or_default()
+    }
+
+    /// Terms that start with `word` or its stem
+    fn prefix_terms(&self, word: &str) -> Vec<(&str, f64)> {
+        let stemmed = stem(word);
+        self.postings
+            .keys()
+            .filter(|term| term.starts_with(word) || term.starts_with(&stemmed))
+            .map(|term| {
+                let weight = if *term == stemmed { 1.0 } else { PREFIX_WEIGHT };
+                (term.as_str(), weight)
+            })
+            .collect()
+    }
+
+    fn result(&self, path: &Path, query_terms: &[Vec<(&str, f64)>]) -> Option<SearchResult> {
+        let document = self.documents.get(path)?;
+        let count = self.documents.len() as f64;
+        let average_length = (self.total_length / count).max(1.0);
+
+        let mut score = 0.0;
+        let mut matched_terms = HashSet::new();
+        let mut lines: HashMap<usize, usize> = HashMap::new();
+
+        for terms in query_terms {
+            // A prefix can match several terms; the best one counts
+            let mut best: f64 = 0.0;
+            let mut word_lines = BTreeSet::new();
+            for &(term, weight) in terms {
+                let postings = &self.postings[term];
+                let Some(posting) = postings.get(path) else {
+                    continue;
+                };
+                let frequency = postings.len() as f64;
+                let idf = (1.0 + (count - frequency + 0.5) / (frequency + 0.5)).ln();
+                let normalized = 1.0 - BM25_B + BM25_B * document.length / average_length;
+                let tf = posting.weight * (BM25_K1 + 1.0) / (posting.weight + BM25_K1 * normalized);
+                best = best.max(weight * idf * tf);
+                matched_terms.insert(term);
+                word_lines.extend(&posting.lines);
+            }
+            score += best;
+            for line in word_lines {
+                *lines.entry(line).or_default() += 1;
+            }
+        }
+
+        // Lines with more of the query words first, then in document order
+        let mut ranked: Vec<(usize, usize)> = lines.into_iter().collect();
+        let match_count = ranked.len();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked.truncate(MATCHES_PER_DOCUMENT);
+        ranked.sort();
+
+        let matches = ranked
+            .into_iter()
+            .filter_map(|(line, _)| {
+                let text = document.lines.get(line - 1)?;
+                Some(LineMatch {
+                    line,
+                    snippet: snippet(text, &matched_terms),
+                })
+            })
+            .collect();
+
+        Some(SearchResult {
+            path: path.to_string_lossy().to_string(),
+            title: document.title.clone(),
+            score,
+            match_count,
+            matches,
+        })
+    }
+}
+
+/// Reduces a word to its English stem, so "linking", "linked" and "links"
+/// all become "link"
+fn stem(word: &str) -> String {
+    Stemmer::create(Algorithm::English).stem(word).into_owned()
+}
+
+/// Splits text into lowercase words, with the byte range each came from
+fn tokenize(text: &str) -> impl Iterator<Item = (usize, usize, String)> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(move |word| {
+            let start = word.as_ptr() as usize - text.as_ptr() as usize;
+            (start, start + word.len(), word.to_lowercase())
+        })
+}
+
+/// The words of a document with the line each is on and its weight: headings
+/// and the front matter title count more, code less. Link targets and HTML
+/// are left out.
+fn index_words(content: &str) -> Vec<(String, usize, f64)> {
+    let line_index = LineIndex::new(content);
+    let (front_matter, body) = markdown::split_front_matter(content);
+    let body_start = front_matter.map_or(0, str::len);
+
+    let mut words = Vec::new();
+    if let Some(front_matter) = front_matter {
+        let title = markdown::document_title(front_matter).unwrap_or_default();
+        let line = front_matter
+            .lines()
+            .position(|line| line.starts_with("title"))
+            .map_or(1, |index| index + 1);
+        words.extend(tokenize(&title).map(|(_, _, word)| (word, line, HEADING_WEIGHT)));
+    }
+
+    let mut heading_depth = 0;
+    let mut in_code_block = false;
+    for (event, range) in Parser::new_ext(body, markdown::parser_options()).into_offset_iter() {
+        let (text, weight) = match event {
+            Event::Start(Tag::Heading { .. }) => {
+                heading_depth += 1;
+                continue;
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                heading_depth -= 1;
+                continue;
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                in_code_block = true;
+                continue;
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                in_code_block = false;
+                continue;
+            }
+            Event::Text(text) if in_code_block => (text, CODE_WEIGHT),
+            Event::Text(text) if heading_depth > 0 => (text, HEADING_WEIGHT),
+            Event::Text(text) => (text, TEXT_WEIGHT),
+            Event::Code(code) if heading_depth > 0 => (code, HEADING_WEIGHT),
+            Event::Code(code) => (code, CODE_WEIGHT),
+            _ => continue,
+        };
+
+        // A text event can span lines, as in code blocks
+        let (first_line, _) = line_index.position(body_start + range.start);
+        for (start, _, word) in tokenize(&text) {
+            let line = first_line + text[..start].matches('\n').count();
+            words.push((word, line, weight));
+        }
+    }
+
+    words
+}
+
+/// A line cut down to about `SNIPPET_LENGTH` characters around its first
+/// match, with the matching words highlighted
+fn snippet(line: &str, terms: &HashSet<&str>) -> Vec<SnippetPart> {
+    let line = line.trim();
+    let matches: Vec<(usize, usize)> = tokenize(line)
+        .filter(|(_, _, word)| terms.contains(stem(word).as_str()))
+        .map(|(start, end, _)| (start, end))
+        .collect();
+
+    let first_match = matches.first().map_or(0, |&(start, _)| start);
+    let start = if line[..first_match].chars().count() > SNIPPET_LENGTH / 3 {
+        floor_to_char(line, first_match.saturating_sub(SNIPPET_LENGTH / 3))
+    } else {
+        0
+    };
+    let end = line[start..]
+        .char_indices()
+        .nth(SNIPPET_LENGTH)
+        .map_or(line.len(), |(index, _)| start + index);
+
+    let mut parts = Vec::new();
+    let mut push = |text: &str, highlight: bool| {
+        if !text.is_empty() {
+            parts.push(SnippetPart {
+                text: text.to_string(),
+                highlight,
+            });
+        }
+    };
+
+    if start > 0 {
+        push("…", false);
+    }
+    let mut copied = start;
+    for (match_start, match_end) in matches {
+        if match_start < copied || match_end > end {
+            continue;
+        }
+        push(&line[copied..match_start], false);
+        push(&line[match_start..match_end], true);
+        copied = match_end;
+    }
+    push(&line[copied..end], false);
+    if end < line.len() {
+        push("…", false);
+    }
+
+    parts
+}
+
+fn floor_to_char(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(documents: &[(&str, &str)]) -> SearchIndex {
+        let mut index = SearchIndex::default();
+        for (path, content) in documents {
+            index.add(PathBuf::from(path), None, content);
+        }
+        index
+    }
+
+    fn paths(results: &[SearchResult]) -> Vec<&str> {
+        results.iter().map(|result| result.path.as_str()).collect()
+    }
+
+    fn highlighted(parts: &[SnippetPart]) -> Vec<&str> {
+        parts
+            .iter()
+            .filter(|part| part.highlight)
+            .map(|part| part.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn words_match_other_forms_of_the_same_word() {
+        let index = index(&[("a.md", "The links are fine."), ("b.md", "Nothing here.")]);
+        assert_eq!(paths(&index.search("linking", 10)), ["a.md"]);
+        assert_eq!(paths(&index.search("linked", 10)), ["a.md"]);
+    }
+
+    #[test]
+    fn every_word_has_to_match() {
+        let index = index(&[("a.md", "red apple"), ("b.md", "red cherry")]);
+        assert_eq!(paths(&index.search("red apple", 10)), ["a.md"]);
+        assert!(index.search("red banana", 10).is_empty());
+    }
+
+    #[test]
+    fn repeated_words_rank_higher() {
+        let index = index(&[
+            ("once.md", "cache and other words to pad the text"),
+            ("twice.md", "cache the cache and other words to pad"),
+        ]);
+        assert_eq!(paths(&index.search("cache", 10)), ["twice.md", "once.md"]);
+    }
+
+    #[test]
+    fn shorter_documents_rank_higher() {
+        let index = index(&[
+            ("long.md", "cache with many other words around it in here"),
+            ("short.md", "cache here"),
+        ]);
+        assert_eq!(paths(&index.search("cache", 10)), ["short.md", "long.md"]);
+    }
+
+    #[test]
+    fn headings_count_more_than_text() {
+        let index = index(&[
+            ("text.md", "# Other\n\ncache notes"),
+            ("heading.md", "# Cache\n\nother notes"),
+        ]);
+        assert_eq!(paths(&index.search("cache", 10)), ["heading.md", "text.md"]);
+    }
+
+    #[test]
+    fn rare_words_count_more_than_common_ones() {
+        let index = index(&[
+            ("a.md", "common common rare"),
+            ("b.md", "common rare rare"),
+            ("c.md", "common"),
+            ("d.md", "common"),
+        ]);
+        assert_eq!(paths(&index.search("common rare", 10)), ["b.md", "a.md"]);
+    }
+
+    #[test]
+    fn only_the_last_word_matches_as_a_prefix() {
+        let index = index(&[("a.md", "installation guide")]);
+        assert_eq!(paths(&index.search("guide inst", 10)), ["a.md"]);
+        assert!(index.search("inst guide", 10).is_empty());
+    }
+
+    #[test]
+    fn whole_words_rank_above_prefixes() {
+        let index = index(&[("prefix.md", "testers notes"), ("whole.md", "test notes")]);
+        assert_eq!(paths(&index.search("test", 10)), ["whole.md", "prefix.md"]);
+    }
+
+    #[test]
+    fn results_show_the_matching_lines() {
+        let index = index(&[("a.md", "# Setup\n\nFirst line\nInstall the tools\n")]);
+        let results = index.search("install", 10);
+        assert_eq!(results[0].title, "Setup");
+        assert_eq!(results[0].match_count, 1);
+        assert_eq!(results[0].matches[0].line, 4);
+        assert_eq!(highlighted(&results[0].matches[0].snippet), ["Install"]);
+    }
+
+    #[test]
+    fn snippets_highlight_every_form_of_a_word() {
+        let stemmed = stem("link");
+        let terms = HashSet::from([stemmed.as_str()]);
+        let parts = snippet("  Linked pages, links and a linking page  ", &terms);
+        assert_eq!(highlighted(&parts), ["Linked", "links", "linking"]);
+        assert_eq!(parts[0].text, "Linked");
+        assert_eq!(parts.last().unwrap().text, " page");
+    }
+
+    #[test]
+    fn long_lines_are_cut_around_the_first_match() {
+        let line = format!("{} needle {}", "before ".repeat(40), "after ".repeat(40));
+        let stemmed = stem("needle");
+        let terms = HashSet::from([stemmed.as_str()]);
+        let parts = snippet(&line, &terms);
+        assert_eq!(parts.first().unwrap().text, "…");
+        assert_eq!(parts.last().unwrap().text, "…");
+        assert_eq!(highlighted(&parts), ["needle"]);
+
+        let shown: String = parts[1..parts.len() - 1]
+            .iter()
+            .map(|part| part.text.as_str())
+            .collect();
+        assert!(shown.chars().count() <= SNIPPET_LENGTH);
+        assert!(shown.find("needle").unwrap() <= SNIPPET_LENGTH / 3 + 1);
+    }
+}
@@ -0,0 +1,362 @@
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::files;
+use crate::gitignore::{self, IgnoreTree};
+use crate::link_discovery;
+use crate::markdown;
+
+/// Endpoint browsers subscribe to for reload notifications
+const EVENTS_PATH: &str = "/__mdox/events";
+
+/// Injected into every rendered page to reload it when the server says so
+const RELOAD_SCRIPT: &str = r#"<script>
+new EventSource("/__mdox/events").onmessage = () => location.reload();
+</script>
+"#;
+
+/// Open server-sent event connections waiting for a reload
+type Clients = Arc<Mutex<Vec<Sender<()>>>>;
+
+/// Serves rendered Markdown from `root` on localhost until the process exits
+pub fn serve(root: &str, port: u16) -> Result<(), String> {
+    let root = Path::new(root)
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", root, e))?;
+
+    if !root.is_dir() {
+        return Err(format!("Path is not a directory: {}", root.display()));
+    }
+
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .map_err(|e| format!("Failed to listen on port {}: {}", port, e))?;
+
+    let clients: Clients = Arc::new(Mutex::new(Vec::new()));
+
+    // Keep the watcher alive for as long as the server runs
+    let _watcher = watch_for_changes(&root, clients.clone())?;
+
+    println!("Serving {} at http://127.0.0.1:{}/", root.display(), port);
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Failed to accept connection: {}", e);
+                continue;
+            }
+        };
+
+        let root = root.clone();
+        let clients = clients.clone();
+        thread::spawn(move || {
+            if let Err(e) = handle_connection(stream, &root, port, &clients) {
+                eprintln!("Request failed: {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// Notifies every connected browser whenever a file the server would show
+/// changes under `root`
+fn watch_for_changes(root: &Path, clients: Clients) -> Result<notify::RecommendedWatcher, String> {
+    let (tx, rx) = mpsc::channel();
+
+    let mut watcher = notify::recommended_watcher(tx)
+        .map_err(|e| format!("Failed to start file watcher: {}", e))?;
+    watcher
+        .watch(root, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch {}: {}", root.display(), e))?;
+
+    let mut ignore = IgnoreTree::new(root, &[], true);
+    thread::spawn(move || {
+        while let Ok(event) = rx.recv() {
+            let Ok(event) = event else {
+                continue;
+            };
+            if event
+                .paths
+                .iter()
+                .any(|path| gitignore::is_ignore_file(path))
+            {
+                ignore.reload();
+            }
+            if matches!(event.kind, EventKind::Access(_))
+                || event
+                    .paths
+                    .iter()
+                    .all(|path| ignore.is_ignored(path) || is_editor_temp_file(path))
+            {
+                continue;
+            }
+
+            // Editors often write a file in several steps; wait for them to settle
+            thread::sleep(Duration::from_millis(100));
+            while rx.try_recv().is_ok() {}
+
+            let mut clients = clients.lock().unwrap_or_else(|e| e.into_inner());
+            clients.retain(|client| client.send(()).is_ok());
+        }
+    });
+
+    Ok(watcher)
+}
+
+/// Backup and lock files editors write next to a document; their swap files
+/// are hidden and ignored anyway
+fn is_editor_temp_file(path: &Path) -> bool {
+    let name = file_name(path);
+    name.ends_with('~') || (name.starts_with('#') && name.ends_with('#')) || name == "4913"
+}
+
+/// Whether the request was addressed to this server by name, so a page on
+/// another site can't reach it by pointing its own domain at 127.0.0.1
+fn is_local_host(host: &str, port: u16) -> bool {
+    let (name, host_port) = match host.rsplit_once(':') {
+        Some((name, host_port)) => (name, host_port.parse().ok()),
+        None => (host, Some(80)),
+    };
+    matches!(name, "localhost" | "127.0.0.1") && host_port == Some(port)
+}
+
+fn handle_connection(
+    mut stream: TcpStream,
+    root: &Path,
+    port: u16,
+    clients: &Clients,
+) -> Result<(), String> {
+    let mut reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+
+    let mut request_line = String::new();
+    reader
+        .read_line(&mut request_line)
+        .map_err(|e| format!("Failed to read request: {}", e))?;
+
+    // Drain the headers; only Host affects the response
+    let mut host = None;
+    let mut header = String::new();
+    while reader.read_line(&mut header).map_err(|e| e.to_string())? > 0 && !header.trim().is_empty()
+    {
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("host") {
+                host = Some(value.trim().to_string());
+            }
+        }
+        header.clear();
+    }
+
+    if !host.is_some_and(|host| is_local_host(&host, port)) {
+        return respond(&mut stream, "403 Forbidden", "text/plain", b"Forbidden");
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let target = parts.next().unwrap_or("/");
+
+    if method != "GET" {
+        return respond(
+            &mut stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            b"Method not allowed",
+        );
+    }
+
+    let request_path = target.split(['?', '#']).next().unwrap_or("/");
+
+    if request_path == EVENTS_PATH {
+        return stream_events(stream, clients);
+    }
+
+    let decoded = link_discovery::percent_decode(request_path);
+    let requested = root.join(decoded.trim_start_matches('/'));
+
+    // Refuse anything that escapes the served directory, and hidden or
+    // ignored files such as .env and .git/config
+    let mut ignore = IgnoreTree::new(root, &[], true);
+    let resolved = match requested.canonicalize() {
+        Ok(path)
+            if path.starts_with(root)
+                && !ignore.is_ignored(&requested)
+                && !ignore.is_ignored(&path) =>
+        {
+            path
+        }
+        _ => {
+            return respond(
+                &mut stream,
+                "404 Not Found",
+                "text/html; charset=utf-8",
+                not_found_page(request_path).as_bytes(),
+            )
+        }
+    };
+
+    if resolved.is_dir() {
+        if !request_path.ends_with('/') {
+            let location = format!("{}/", request_path);
+            return redirect(&mut stream, &location);
+        }
+
+        for index in ["index.md", "README.md", "readme.md"] {
+            let index_path = resolved.join(index);
+            if index_path.is_file() {
+                return respond_markdown(&mut stream, &index_path);
+            }
+        }
+
+        let listing = directory_page(&resolved, request_path)?;
+        return respond(
+            &mut stream,
+            "200 OK",
+            "text/html; charset=utf-8",
+            listing.as_bytes(),
+        );
+    }
+
+    if files::is_markdown_file(&resolved) {
+        return respond_markdown(&mut stream, &resolved);
+    }
+
+    let bytes = std::fs::read(&resolved).map_err(|e| format!("Failed to read file: {}", e))?;
+    respond(&mut stream, "200 OK", content_type(&resolved), &bytes)
+}
+
+fn respond_markdown(stream: &mut TcpStream, path: &Path) -> Result<(), String> {
+    let content = files::read_file_contents(&path.to_string_lossy())?;
+
+    // Relative image URLs stay relative so the browser fetches them from this server
+    let body = markdown::render_html(&content, None, false);
+    let title = markdown::first_heading(&content).unwrap_or_else(|| file_name(path));
+    let page = markdown::render_page(&title, &format!("{}{}", body, RELOAD_SCRIPT));
+
+    respond(
+        stream,
+        "200 OK",
+        "text/html; charset=utf-8",
+        page.as_bytes(),
+    )
+}
+
+fn stream_events(mut stream: TcpStream, clients: &Clients) -> Result<(), String> {
+    let (tx, rx) = mpsc::channel();
+    clients.lock().unwrap_or_else(|e| e.into_inner()).push(tx);
+
+    stream
+        .write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n")
+        .map_err(|e| e.to_string())?;
+
+    loop {
+        // Wake up periodically so closed connections are noticed and dropped
+        let message: &[u8] = match rx.recv_timeout(Duration::from_secs(15)) {
+            Ok(()) => b"data: reload\n\n",
+            Err(mpsc::RecvTimeoutError::Timeout) => b": keep-alive\n\n",
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+        };
+
+        if stream
+            .write_all(message)
+            .and_then(|_| stream.flush())
+            .is_err()
+        {
+            return Ok(());
+        }
+    }
+}
+
+fn directory_page(dir: &Path, request_path: &str) -> Result<String, String> {
+    let options = files::ListOptions {
+        markdown_only: true,
+        ..Default::default()
+    };
+    let entries = files::read_directory(&dir.to_string_lossy(), &options, None)?;
+
+    let mut listing = format!("# Index of {}\n\n", request_path);
+    for entry in entries {
+        let suffix = if entry.is_directory { "/" } else { "" };
+        listing.push_str(&format!(
+            "- [{}{}](<{}{}>)\n",
+            entry.name, suffix, entry.name, suffix
+        ));
+    }
+
+    let body = markdown::render_html(&listing, None, true);
+    Ok(markdown::render_page(
+        request_path,
+        &format!("{}{}", body, RELOAD_SCRIPT),
+    ))
+}
+
+fn not_found_page(request_path: &str) -> String {
+    let body = markdown::render_html(
+        &format!("# Not found\n\n`{}` does not exist.", request_path),
+        None,
+        true,
+    );
+    markdown::render_page("Not found", &body)
+}
+
+fn redirect(stream: &mut TcpStream, location: &str) -> Result<(), String> {
+    let response = format!(
+        "HTTP/1.1 301 Moved Permanently\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        location
+    );
+    stream
+        .write_all(response.as_bytes())
+        .map_err(|e| e.to_string())
+}
+
+fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> Result<(), String> {
+    let header = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    stream
+        .write_all(header.as_bytes())
+        .and_then(|_| stream.write_all(body))
+        .map_err(|e| e.to_string())
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .as_deref()
+    {
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("html") | Some("htm") => "text/html; charset=utf-8",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("json") => "application/json",
+        Some("pdf") => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Untitled".to_string())
+}
@@ -0,0 +1,110 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Whether `restore` can put items back here. The Finder trash has no API
+/// for it, so on macOS items can only be restored with "Put Back".
+pub const CAN_RESTORE: bool = cfg!(not(target_os = "macos"));
+
+/// A file or folder moved to the trash, remembered so it can be put back
+#[derive(Debug, Clone)]
+pub struct TrashedItem {
+    /// Where the item was before it was trashed
+    pub original: PathBuf,
+    /// The trash's own name for the item, which tells it apart from earlier
+    /// items trashed from the same path
+    #[cfg_attr(target_os = "macos", allow(dead_code))]
+    id: Option<OsString>,
+}
+
+/// The most recent delete, for "Undo Delete"
+#[derive(Default)]
+pub struct LastDelete(Mutex<Option<TrashedItem>>);
+
+impl LastDelete {
+    pub fn set(&self, item: Option<TrashedItem>) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = item;
+    }
+
+    pub fn take(&self) -> Option<TrashedItem> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+/// Moves `path` to the trash: the freedesktop.org trash on Linux and the BSDs,
+/// the Recycle Bin on Windows and the Finder trash on macOS
+pub fn move_to_trash(path: &Path) -> Result<TrashedItem, String> {
+    if std::fs::symlink_metadata(path).is_err() {
+        return Err(format!("Path not found: {}", path.display()));
+    }
+
+    let original = absolute(path)?;
+    let earlier = trash_ids(&original);
+    trash::delete(&original).map_err(|e| format!("Failed to move to trash: {}", e))?;
+    let id = trash_ids(&original)
+        .into_iter()
+        .find(|id| !earlier.contains(id));
+
+    Ok(TrashedItem { original, id })
+}
+
+/// The trash's names for the items trashed from `original`
+#[cfg(not(target_os = "macos"))]
+fn trash_ids(original: &Path) -> Vec<OsString> {
+    trash::os_limited::list()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|trashed| trashed.original_path() == original)
+        .map(|trashed| trashed.id)
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn trash_ids(_original: &Path) -> Vec<OsString> {
+    Vec::new()
+}
+
+/// Makes `path` absolute without resolving the item itself, so a symlink
+/// is trashed rather than what it points to, and matches the path the
+/// trash records
+fn absolute(path: &Path) -> Result<PathBuf, String> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| format!("Can't move {} to the trash", path.display()))?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let parent = parent
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", parent.display(), e))?;
+    Ok(parent.join(name))
+}
+
+/// Puts a trashed item back where it was
+#[cfg(not(target_os = "macos"))]
+pub fn restore(item: &TrashedItem) -> Result<(), String> {
+    if std::fs::symlink_metadata(&item.original).is_ok() {
+        return Err(format!("{} already exists", item.original.display()));
+    }
+
+    // The same path may have been trashed before; without an id the newest is ours
+    let trashed = trash::os_limited::list()
+        .map_err(|e| format!("Failed to read the trash: {}", e))?
+        .into_iter()
+        .filter(|trashed| trashed.original_path() == item.original)
+        .filter(|trashed| item.id.as_ref().is_none_or(|id| *id == trashed.id))
+        .max_by_key(|trashed| trashed.time_deleted)
+        .ok_or_else(|| format!("{} is no longer in the trash", item.original.display()))?;
+
+    trash::os_limited::restore_all([trashed])
+        .map_err(|e| format!("Failed to restore from trash: {}", e))
+}
+
+#[cfg(target_os = "macos")]
+pub fn restore(item: &TrashedItem) -> Result<(), String> {
+    Err(format!(
+        "Use \"Put Back\" in the Finder trash to restore {}",
+        item.original.display()
+    ))
+}
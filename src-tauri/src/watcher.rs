@@ -0,0 +1,371 @@
+use notify::event::{EventKind, ModifyKind, RenameMode};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::files::ListOptions;
+use crate::gitignore::{self, IgnoreTree};
+
+/// How long the disk has to stay quiet before a batch of events is reported
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Longest a batch is held back while events keep arriving, e.g. during `git checkout`
+const MAX_DELAY: Duration = Duration::from_secs(1);
+
+/// A change on disk, after coalescing the raw events of one batch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum Change {
+    Changed { path: String },
+    Created { path: String },
+    Deleted { path: String },
+    Renamed { from: String, to: String },
+}
+
+impl Change {
+    /// The Tauri event the change is sent as
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            Change::Changed { .. } => "file-changed",
+            Change::Created { .. } => "file-created",
+            Change::Deleted { .. } => "file-deleted",
+            Change::Renamed { .. } => "file-renamed",
+        }
+    }
+
+    /// The paths the change touched
+    pub fn paths(&self) -> Vec<&Path> {
+        match self {
+            Change::Changed { path } | Change::Created { path } | Change::Deleted { path } => {
+                vec![Path::new(path)]
+            }
+            Change::Renamed { from, to } => vec![Path::new(from), Path::new(to)],
+        }
+    }
+}
+
+/// Keeps watching until dropped
+pub struct FileWatcher {
+    _watcher: Arc<Mutex<RecommendedWatcher>>,
+}
+
+/// The watcher of each window, by window label
+#[derive(Default)]
+pub struct Watchers(Mutex<HashMap<String, FileWatcher>>);
+
+impl Watchers {
+    /// Replaces what a window watches, stopping its previous watcher
+    pub fn insert(&self, label: &str, watcher: FileWatcher) {
+        let mut watchers = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        watchers.insert(label.to_string(), watcher);
+    }
+
+    pub fn remove(&self, label: &str) {
+        let mut watchers = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        watchers.remove(label);
+    }
+}
+
+/// The paths a window cares about
+struct Scope {
+    document: Option<PathBuf>,
+    root: Option<PathBuf>,
+    /// What the file tree leaves out below `root`
+    ignore: Option<IgnoreTree>,
+}
+
+impl Scope {
+    fn includes(&mut self, path: &Path) -> bool {
+        if self.document.as_deref() == Some(path) {
+            return true;
+        }
+
+        // Hidden, skipped and ignored entries are not shown in the file tree
+        match (&self.root, &mut self.ignore) {
+            (Some(root), Some(ignore)) => path.starts_with(root) && !ignore.is_ignored(path),
+            _ => false,
+        }
+    }
+}
+
+/// Watches an open document and a workspace root, calling `on_change` for each
+/// change once the disk has settled. Changes below the root that `options`
+/// leave out of the file tree are not reported.
+pub fn watch<F>(
+    document: Option<&str>,
+    root: Option<&str>,
+    options: &ListOptions,
+    on_change: F,
+) -> Result<FileWatcher, String>
+where
+    F: Fn(Change) + Send + 'static,
+{
+    let root = root.map(PathBuf::from);
+    let mut scope = Scope {
+        document: document.map(PathBuf::from),
+        ignore: root
+            .as_deref()
+            .map(|root| IgnoreTree::new(root, &options.exclude, options.respect_ignore_files)),
+        root,
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let watcher = notify::recommended_watcher(tx)
+        .map_err(|e| format!("Failed to start file watcher: {}", e))?;
+    let watcher = Arc::new(Mutex::new(watcher));
+
+    if let Some(root) = scope.root.clone() {
+        watch_tree(&watcher, &root, &mut scope)?;
+    }
+
+    // Watch the document's folder rather than the file, so saves that replace
+    // the file through a rename are still seen
+    if let Some(document) = &scope.document {
+        let covered = scope
+            .root
+            .as_deref()
+            .is_some_and(|root| document.starts_with(root));
+        if let (false, Some(parent)) = (covered, document.parent()) {
+            lock(&watcher)
+                .watch(parent, RecursiveMode::NonRecursive)
+                .map_err(|e| format!("Failed to watch {}: {}", document.display(), e))?;
+        }
+    }
+
+    // The thread only holds on weakly, so dropping the FileWatcher stops it
+    let weak = Arc::downgrade(&watcher);
+    thread::spawn(move || {
+        while let Some(batch) = next_batch(&rx, &mut scope) {
+            let ignore_changed = batch.ignore_changed;
+            let changes = batch.into_changes();
+            follow_new_directories(&weak, &changes, ignore_changed, &mut scope);
+            for change in changes {
+                on_change(change);
+            }
+        }
+    });
+
+    Ok(FileWatcher { _watcher: watcher })
+}
+
+fn lock(watcher: &Mutex<RecommendedWatcher>) -> std::sync::MutexGuard<'_, RecommendedWatcher> {
+    watcher.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Watches `dir` and everything below it that isn't ignored. inotify needs a
+/// watch per folder and a recursive watch would add one for every folder,
+/// `node_modules` and build output included, so on Linux each folder the file
+/// tree shows is watched on its own. Elsewhere one recursive watch is cheap.
+fn watch_tree(
+    watcher: &Mutex<RecommendedWatcher>,
+    dir: &Path,
+    scope: &mut Scope,
+) -> Result<(), String> {
+    if !cfg!(any(target_os = "linux", target_os = "android")) {
+        return lock(watcher)
+            .watch(dir, RecursiveMode::Recursive)
+            .map_err(|e| format!("Failed to watch {}: {}", dir.display(), e));
+    }
+
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        lock(watcher)
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch {}: {}", dir.display(), e))?;
+
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            // Symlinked folders aren't followed, as with a recursive watch
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            if is_dir && scope.includes(&path) {
+                pending.push(path);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Starts watching folders created or moved into the root, and folders an
+/// edited ignore file no longer leaves out
+fn follow_new_directories(
+    watcher: &Weak<Mutex<RecommendedWatcher>>,
+    changes: &[Change],
+    ignore_changed: bool,
+    scope: &mut Scope,
+) {
+    if !cfg!(any(target_os = "linux", target_os = "android")) {
+        return;
+    }
+    let Some(watcher) = watcher.upgrade() else {
+        return;
+    };
+
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for change in changes {
+        match change {
+            Change::Created { path } | Change::Renamed { to: path, .. } => {
+                dirs.push(PathBuf::from(path))
+            }
+            _ => {}
+        }
+    }
+    if ignore_changed {
+        dirs.extend(scope.root.clone());
+    }
+
+    for dir in dirs {
+        if dir.is_dir() {
+            if let Err(e) = watch_tree(&watcher, &dir, scope) {
+                eprintln!("{}", e);
+            }
+        }
+    }
+}
+
+/// Collects events until the disk goes quiet. Returns None once the watcher is dropped.
+fn next_batch(rx: &Receiver<notify::Result<notify::Event>>, scope: &mut Scope) -> Option<Batch> {
+    let mut batch = Batch::default();
+    let first = rx.recv().ok()?;
+    batch.record(first, scope);
+
+    let started = Instant::now();
+    while started.elapsed() < MAX_DELAY {
+        match rx.recv_timeout(DEBOUNCE) {
+            Ok(event) => batch.record(event, scope),
+            Err(RecvTimeoutError::Timeout) => break,
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    Some(batch)
+}
+
+/// Paths touched by a burst of events, and whether each existed before it
+#[derive(Default)]
+struct Batch {
+    existed: Vec<(PathBuf, bool)>,
+    renames: Vec<(PathBuf, PathBuf)>,
+    /// A `.gitignore` or `.ignore` file changed, so other paths may now be shown
+    ignore_changed: bool,
+}
+
+impl Batch {
+    fn record(&mut self, event: notify::Result<notify::Event>, scope: &mut Scope) {
+        let Ok(event) = event else {
+            return;
+        };
+
+        if event
+            .paths
+            .iter()
+            .any(|path| gitignore::is_ignore_file(path))
+        {
+            if let Some(ignore) = &mut scope.ignore {
+                ignore.reload();
+            }
+            self.ignore_changed = true;
+        }
+
+        match event.kind {
+            EventKind::Create(_) => self.touch(&event.paths, false, scope),
+            EventKind::Remove(_) => self.touch(&event.paths, true, scope),
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                let (from, to) = (&event.paths[0], &event.paths[1]);
+                self.touch(std::slice::from_ref(from), true, scope);
+                self.touch(std::slice::from_ref(to), false, scope);
+                if scope.includes(from) && scope.includes(to) {
+                    self.renames.push((from.clone(), to.clone()));
+                }
+            }
+            // One side of a rename: whatever is there now was moved in
+            EventKind::Modify(ModifyKind::Name(_)) => {
+                for path in &event.paths {
+                    self.touch(std::slice::from_ref(path), !path.exists(), scope);
+                }
+            }
+            // Permission and timestamp changes don't affect what's shown
+            EventKind::Modify(ModifyKind::Metadata(_)) | EventKind::Access(_) => {}
+            EventKind::Modify(_) | EventKind::Any | EventKind::Other => {
+                self.touch(&event.paths, true, scope)
+            }
+        }
+    }
+
+    /// Remembers whether a path existed the first time the batch sees it. Events
+    /// can't tell whether a rename replaced a file, but the open document did exist.
+    fn touch(&mut self, paths: &[PathBuf], existed: bool, scope: &mut Scope) {
+        for path in paths {
+            if scope.includes(path) && !self.existed.iter().any(|(seen, _)| seen == path) {
+                let existed = existed || scope.document.as_deref() == Some(path.as_path());
+                self.existed.push((path.clone(), existed));
+            }
+        }
+    }
+
+    fn existed_before(&self, path: &Path) -> bool {
+        self.existed
+            .iter()
+            .any(|(seen, existed)| seen == path && *existed)
+    }
+
+    /// Compares each path with what is on disk now, so a temp file written and
+    /// renamed over a document reports as a single change to that document
+    fn into_changes(self) -> Vec<Change> {
+        let mut changes = Vec::new();
+        let mut reported: Vec<&Path> = Vec::new();
+
+        for (from, to) in &self.renames {
+            let clean_move = self.existed_before(from)
+                && !from.exists()
+                && !self.existed_before(to)
+                && to.exists();
+            if clean_move
+                && !reported.contains(&from.as_path())
+                && !reported.contains(&to.as_path())
+            {
+                changes.push(Change::Renamed {
+                    from: path_string(from),
+                    to: path_string(to),
+                });
+                reported.push(from);
+                reported.push(to);
+            }
+        }
+
+        for (path, existed) in &self.existed {
+            if reported.contains(&path.as_path()) {
+                continue;
+            }
+
+            let change = match (*existed, path.exists()) {
+                // Folders report a change whenever something inside them does
+                (true, true) if path.is_dir() => continue,
+                (true, true) => Change::Changed {
+                    path: path_string(path),
+                },
+                (false, true) => Change::Created {
+                    path: path_string(path),
+                },
+                (true, false) => Change::Deleted {
+                    path: path_string(path),
+                },
+                (false, false) => continue,
+            };
+            changes.push(change);
+        }
+
+        changes
+    }
+}
+
+fn path_string(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
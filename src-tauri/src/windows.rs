@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, WebviewWindowBuilder};
+
+use crate::cli::OpenTarget;
+use crate::instance::OpenRequest;
+
+/// Label of the window created from `tauri.conf.json`
+pub const MAIN_WINDOW: &str = "main";
+
+/// What a window should show once its frontend has loaded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PendingDocument {
+    /// A file or URL, optionally at a line or heading
+    File(OpenTarget),
+    /// A folder to use as the workspace root in the file tree
+    Folder { path: String },
+    /// Markdown without a file behind it, opened as an unsaved document
+    Buffer { content: String },
+}
+
+impl PendingDocument {
+    fn from_target(target: OpenTarget) -> Self {
+        if Path::new(&target.path).is_dir() {
+            PendingDocument::Folder { path: target.path }
+        } else {
+            PendingDocument::File(target)
+        }
+    }
+}
+
+#[derive(Default)]
+struct PendingState {
+    documents: HashMap<String, PendingDocument>,
+    /// Windows whose frontend has already asked for its document
+    started: HashSet<String>,
+}
+
+/// Documents waiting for their window's frontend to ask for them
+#[derive(Default)]
+pub struct PendingDocuments(Mutex<PendingState>);
+
+impl PendingDocuments {
+    pub fn insert(&self, label: &str, document: PendingDocument) {
+        let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        state.documents.insert(label.to_string(), document);
+    }
+
+    /// Hands a window its document. Afterwards the window counts as in use.
+    pub fn take(&self, label: &str) -> Option<PendingDocument> {
+        let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        state.started.insert(label.to_string());
+        state.documents.remove(label)
+    }
+
+    /// Gives a document to a window that hasn't loaded yet and has nothing to
+    /// show, so files arriving during startup don't each open a new window
+    fn claim_unstarted(&self, label: &str, document: PendingDocument) -> Option<PendingDocument> {
+        let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if state.started.contains(label) || state.documents.contains_key(label) {
+            return Some(document);
+        }
+        state.documents.insert(label.to_string(), document);
+        None
+    }
+}
+
+static NEXT_WINDOW_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// Opens a document in a new window configured like the main one
+pub fn open_in_new_window(app: &AppHandle, document: PendingDocument) -> Result<(), String> {
+    let mut config = app
+        .config()
+        .app
+        .windows
+        .first()
+        .cloned()
+        .ok_or("No window configuration found")?;
+    config.label = format!("doc-{}", NEXT_WINDOW_ID.fetch_add(1, Ordering::Relaxed));
+
+    app.state::<PendingDocuments>().insert(&config.label, document);
+
+    WebviewWindowBuilder::from_config(app, &config)
+        .and_then(|builder| builder.build())
+        .map_err(|e| format!("Failed to open window: {}", e))?;
+
+    Ok(())
+}
+
+/// Opens everything the command line, another instance or the OS asked for.
+/// The main window takes the first document if it has nothing to show yet.
+pub fn open_request(app: &AppHandle, request: OpenRequest) {
+    if request.is_empty() {
+        focus_main_window(app);
+        return;
+    }
+
+    let mut documents: Vec<PendingDocument> = request
+        .targets
+        .into_iter()
+        .map(PendingDocument::from_target)
+        .collect();
+    if let Some(content) = request.stdin {
+        documents.insert(0, PendingDocument::Buffer { content });
+    }
+
+    let pending = app.state::<PendingDocuments>();
+    for document in documents {
+        let document = match app.get_webview_window(MAIN_WINDOW) {
+            Some(_) => pending.claim_unstarted(MAIN_WINDOW, document),
+            None => Some(document),
+        };
+
+        if let Some(document) = document {
+            if let Err(e) = open_in_new_window(app, document) {
+                eprintln!("{}", e);
+            }
+        }
+    }
+}
+
+/// Brings the main window to the front, or any window if it was closed
+pub fn focus_main_window(app: &AppHandle) {
+    let window = app
+        .get_webview_window(MAIN_WINDOW)
+        .or_else(|| app.webview_windows().into_values().next());
+
+    if let Some(window) = window {
+        let _ = window.unminimize();
+        let _ = window.show();
+        if let Err(e) = window.set_focus() {
+            eprintln!("Failed to focus window: {}", e);
+        }
+    }
+}
+
+/// Sends a menu event to the window the user is working in
+pub fn emit_to_focused(app: &AppHandle, event: &str) {
+    let label = app
+        .get_focused_window()
+        .map(|window| window.label().to_string())
+        .unwrap_or_else(|| MAIN_WINDOW.to_string());
+
+    if let Err(e) = app.emit_to(label.as_str(), event, ()) {
+        eprintln!("Failed to emit '{}': {}", event, e);
+    }
+}
@@ -1,11 +1,15 @@
 import { useEffect, useState, useCallback, useRef, memo } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 import { useTheme } from "./hooks/useTheme";
 import { useNavigation } from "./hooks/useNavigation";
 import { useRecentFiles } from "./hooks/useRecentFiles";
 import { useLinkedDocs } from "./hooks/useLinkedDocs";
+import { useBacklinks } from "./hooks/useBacklinks";
 import { useRootDirectory } from "./hooks/useRootDirectory";
+import { usePermanentDelete } from "./hooks/usePermanentDelete";
+import { useListOptions } from "./hooks/useListOptions";
 import Viewer from "./components/Viewer";
 import Editor from "./components/Editor";
 import Toolbar from "./components/Toolbar";
@@ -14,13 +18,25 @@ import Sidebar from "./components/Sidebar";
 import SearchBar from "./components/SearchBar";
 import Settings from "./components/Settings";
 import UrlDialog from "./components/UrlDialog";
+import LinkReport from "./components/LinkReport";
+import SaveConflict from "./components/SaveConflict";
+import LocalHistory from "./components/LocalHistory";
+import WorkspaceSearch from "./components/WorkspaceSearch";
+import ReplacePanel from "./components/ReplacePanel";
+import QuickOpen from "./components/QuickOpen";
+import LinkGraph from "./components/LinkGraph";
+import OrphanReport from "./components/OrphanReport";
 import "./App.css";
 
+const appWindow = getCurrentWebviewWindow();
+
 function App() {
   const { theme, toggleTheme } = useTheme();
   const navigation = useNavigation();
   const { recentFiles, addRecentFile, removeRecentFile } = useRecentFiles();
-  const { rootDirectory, selectRootDirectory, clearRootDirectory } = useRootDirectory();
+  const { rootDirectory, setRootDirectory, selectRootDirectory, clearRootDirectory } = useRootDirectory();
+  const { permanentDelete, togglePermanentDelete } = usePermanentDelete();
+  const { listOptions, setListOption } = useListOptions();
   
   const [currentFile, setCurrentFile] = useState(null);
   const [rootFile, setRootFile] = useState(null); // The main entry point file for link discovery
@@ -40,11 +56,24 @@ function App() {
   const [searchOpen, setSearchOpen] = useState(false);
   const [settingsOpen, setSettingsOpen] = useState(false);
   const [urlDialogOpen, setUrlDialogOpen] = useState(false);
+  const [linkReportOpen, setLinkReportOpen] = useState(false);
+  const [linkGraphOpen, setLinkGraphOpen] = useState(false);
+  const [orphanReportOpen, setOrphanReportOpen] = useState(false);
+  const [saveConflict, setSaveConflict] = useState(null); // Disk copy and merge preview when a save was refused
+  const [historyOpen, setHistoryOpen] = useState(false);
+  const [workspaceSearchOpen, setWorkspaceSearchOpen] = useState(false);
+  const [replaceOpen, setReplaceOpen] = useState(false);
+  const [quickOpenOpen, setQuickOpenOpen] = useState(false);
+  const [pendingLocation, setPendingLocation] = useState(null); // Line or heading to jump to once a file opens
   const [isRemoteFile, setIsRemoteFile] = useState(false);
 
   // Link discovery only runs for the root file
   const { linkedDocs, isLoading: isLoadingLinked } = useLinkedDocs(rootFile, isRemoteFile);
 
+  // Link checks and workspace search cover the root directory, or the open file's folder
+  const workspaceRoot = rootDirectory || (currentFile && !isRemoteFile ? currentFile.replace(/[/\\][^/\\]*$/, "") : null);
+  const { backlinks, isLoading: isLoadingBacklinks, refresh: refreshBacklinks } = useBacklinks(currentFile, workspaceRoot, isRemoteFile);
+
   const viewerRef = useRef(null);
   const editorRef = useRef(null);
   const editorScrollingRef = useRef(false);
@@ -206,15 +235,25 @@ function App() {
       return;
     }
 
+    await saveCurrentFile(fileContent);
+  };
+
+  // Saves the edited content, refusing if the file on disk no longer matches baseContent
+  const saveCurrentFile = async (baseContent, content = editedContent) => {
     try {
       await invoke("save_file", {
         path: currentFile,
-        content: editedContent
+        content,
+        baseContent
       });
-      setFileContent(editedContent);
+      setFileContent(content);
       setIsDirty(false);
       setError(null);
     } catch (err) {
+      if (err.kind === "conflict") {
+        setSaveConflict(err);
+        return;
+      }
       const errorMessage = typeof err === 'string' ? err : err.message || 'Unknown error occurred';
       setError(`Failed to save file: ${errorMessage}`);
       console.error("Error saving file:", err);
@@ -222,6 +261,121 @@ function App() {
     }
   };
 
+  const handleConflictOverwrite = async () => {
+    const { disk_content } = saveConflict;
+    setSaveConflict(null);
+    // Still refuses if the file changes yet again while the dialog was open
+    await saveCurrentFile(disk_content);
+  };
+
+  const handleConflictUseMerged = async () => {
+    const { disk_content, merged } = saveConflict;
+    setSaveConflict(null);
+    // The disk copy becomes the new base, so saving the merge succeeds.
+    // Open the editor so any conflict markers can be resolved first.
+    setFileContent(disk_content);
+    setEditedContent(merged);
+    setIsDirty(merged !== disk_content);
+    setIsEditMode(true);
+
+    try {
+      const html = await invoke("parse_markdown", { content: merged, basePath: currentFile });
+      setHtmlContent(html);
+    } catch (err) {
+      console.error("Error parsing markdown:", err);
+    }
+  };
+
+  const handleConflictReload = async () => {
+    const { disk_content } = saveConflict;
+    setSaveConflict(null);
+    setFileContent(disk_content);
+    setEditedContent(disk_content);
+    setIsDirty(false);
+
+    try {
+      const html = await invoke("parse_markdown", { content: disk_content, basePath: currentFile });
+      setHtmlContent(html);
+    } catch (err) {
+      console.error("Error parsing markdown:", err);
+    }
+  };
+
+  const handleHistoryRestore = async (content) => {
+    // The restored version is already written to disk
+    setHistoryOpen(false);
+    setFileContent(content);
+    setEditedContent(content);
+    setIsDirty(false);
+
+    try {
+      const html = await invoke("parse_markdown", { content, basePath: currentFile });
+      setHtmlContent(html);
+    } catch (err) {
+      console.error("Error parsing markdown:", err);
+    }
+  };
+
+  const handleLint = useCallback((content) => {
+    // Untitled and remote documents have no .mdox.toml, so they get the defaults
+    return invoke("lint_document", {
+      path: currentFile && !isRemoteFile ? currentFile : null,
+      content
+    });
+  }, [currentFile, isRemoteFile]);
+
+  const handleFormatDocument = useCallback(async () => {
+    if (!isEditMode) return;
+
+    try {
+      const formatted = await invoke("format_document", {
+        path: currentFile && !isRemoteFile ? currentFile : null,
+        content: editedContent
+      });
+      setEditedContent(formatted);
+      setIsDirty(formatted !== fileContent);
+
+      const html = await invoke("parse_markdown", {
+        content: formatted,
+        basePath: currentFile
+      });
+      setHtmlContent(html);
+      setError(null);
+
+      // Saved files go through the normal save, with its conflict check and
+      // history; untitled and remote documents are only formatted in place
+      if (currentFile && !isRemoteFile && formatted !== fileContent) {
+        await saveCurrentFile(fileContent, formatted);
+      }
+    } catch (err) {
+      const errorMessage = typeof err === 'string' ? err : err.message || 'Unknown error occurred';
+      setError(`Failed to format document: ${errorMessage}`);
+      console.error("Error formatting document:", err);
+    }
+  }, [isEditMode, currentFile, isRemoteFile, editedContent, fileContent]);
+
+  // Opens Markdown that has no file behind it (e.g. piped to stdin) as an unsaved document
+  const openBuffer = useCallback(async (content) => {
+    setCurrentFile(null);
+    setRootFile(null);
+    setDisplayUrl(null);
+    setFileContent("");
+    setEditedContent(content);
+    setIsRemoteFile(false);
+    setIsDirty(true);
+    setIsEditMode(false);
+    setError(null);
+
+    try {
+      const html = await invoke("parse_markdown", { content, basePath: null });
+      setHtmlContent(html);
+    } catch (err) {
+      const errorMessage = typeof err === 'string' ? err : err.message || 'Unknown error occurred';
+      setError(`Failed to render document: ${errorMessage}`);
+      console.error("Error rendering document:", err);
+    }
+  }, []);
+
   const handleNewFile = useCallback(() => {
     // Check if there are unsaved changes
     if (isDirty && currentFile) {
@@ -290,15 +444,15 @@ function App() {
   }, [currentFile, fileContent, editedContent, isEditMode, isRemoteFile, addRecentFile, navigation]);
 
   const toggleEditMode = useCallback(() => {
-    // Allow toggle for existing files or when already in edit mode (untitled docs)
-    if (!currentFile && !isEditMode) return;
+    // Allow toggle for existing files and unsaved documents
+    if (!currentFile && !isEditMode && !isDirty) return;
     const newEditMode = !isEditMode;
     setIsEditMode(newEditMode);
     // Enable split view by default when entering edit mode
     if (newEditMode) {
       setIsSplitView(true);
     }
-  }, [currentFile, isEditMode]);
+  }, [currentFile, isEditMode, isDirty]);
 
   const toggleSplitView = useCallback(() => {
     setIsSplitView(!isSplitView);
@@ -399,8 +553,6 @@ function App() {
   };
 
   const handleLinkClick = async (href) => {
-    if (!currentFile) return;
-
     // Handle anchor links (same page)
     if (href.startsWith("#")) {
       const normalizedId = normalizeId(href);
@@ -419,6 +571,9 @@ function App() {
       return;
     }
 
+    // Relative links need a document location to resolve against
+    if (!currentFile) return;
+
     // Handle relative links
     if (isRemoteFile) {
       // For remote files, resolve URL relative to current URL
@@ -446,13 +601,31 @@ function App() {
     }
   };
 
+  // The file or piped Markdown this window was opened for is waiting in the backend
   useEffect(() => {
-    const unlistenFileToOpen = listen("file-to-open", (event) => {
-      // Opening from CLI = root file
-      openFile(event.payload, { isRootFile: true });
-    });
+    invoke("take_pending_document")
+      .then((document) => {
+        if (!document) return;
+
+        if (document.kind === "buffer") {
+          openBuffer(document.content);
+        } else if (document.kind === "folder") {
+          // A folder on the command line becomes the workspace root
+          setRootDirectory(document.path);
+          setSidebarOpen(true);
+        } else {
+          // Opening from CLI = root file, possibly at a line or heading
+          const { path, line, column, heading } = document;
+          setPendingLocation(line || heading ? { line, column, heading } : null);
+          openFile(path, { isRootFile: true });
+        }
+      })
+      .catch((err) => console.error("Error reading pending document:", err));
+  }, [openFile, openBuffer, setRootDirectory]);
 
-    const unlistenMenuOpen = listen("menu-open-file", async () => {
+  useEffect(() => {
+    // Menu events are sent to the focused window only
+    const unlistenMenuOpen = appWindow.listen("menu-open-file", async () => {
       // Menu triggered open - show file picker
       const { open } = await import("@tauri-apps/plugin-dialog");
       const selected = await open({
@@ -470,27 +643,61 @@ function App() {
       }
     });
 
-    const unlistenMenuOpenUrl = listen("menu-open-url", () => {
+    const unlistenMenuOpenUrl = appWindow.listen("menu-open-url", () => {
       // Menu triggered open URL - show URL dialog
       setUrlDialogOpen(true);
     });
 
-    const unlistenMenuNewFile = listen("menu-new-file", () => {
+    const unlistenMenuNewFile = appWindow.listen("menu-new-file", () => {
       // Menu triggered new file - create untitled document
       handleNewFile();
     });
 
-    const unlistenMenuSaveAs = listen("menu-save-as", () => {
+    const unlistenMenuSaveAs = appWindow.listen("menu-save-as", () => {
       // Menu triggered save as
       handleSaveAs();
     });
 
+    const unlistenMenuCheckLinks = appWindow.listen("menu-check-links", () => {
+      setLinkReportOpen(true);
+    });
+
+    const unlistenMenuLinkGraph = appWindow.listen("menu-link-graph", () => {
+      setLinkGraphOpen(true);
+    });
+
+    const unlistenMenuOrphanReport = appWindow.listen("menu-orphan-report", () => {
+      setOrphanReportOpen(true);
+    });
+
+    const unlistenMenuLocalHistory = appWindow.listen("menu-local-history", () => {
+      setHistoryOpen(true);
+    });
+
+    const unlistenMenuSearchWorkspace = appWindow.listen("menu-search-workspace", () => {
+      setWorkspaceSearchOpen(true);
+    });
+
+    const unlistenMenuReplaceWorkspace = appWindow.listen("menu-replace-workspace", () => {
+      setReplaceOpen(true);
+    });
+
+    const unlistenMenuGoToFile = appWindow.listen("menu-go-to-file", () => {
+      setQuickOpenOpen(true);
+    });
+
     return () => {
-      unlistenFileToOpen.then((fn) => fn());
       unlistenMenuOpen.then((fn) => fn());
       unlistenMenuOpenUrl.then((fn) => fn());
       unlistenMenuNewFile.then((fn) => fn());
       unlistenMenuSaveAs.then((fn) => fn());
+      unlistenMenuCheckLinks.then((fn) => fn());
+      unlistenMenuLinkGraph.then((fn) => fn());
+      unlistenMenuOrphanReport.then((fn) => fn());
+      unlistenMenuLocalHistory.then((fn) => fn());
+      unlistenMenuSearchWorkspace.then((fn) => fn());
+      unlistenMenuReplaceWorkspace.then((fn) => fn());
+      unlistenMenuGoToFile.then((fn) => fn());
     };
   }, [openFile, handleNewFile, handleSaveAs]);
 
@@ -582,6 +789,87 @@ function App() {
     };
   }, [openFile]);
 
+  // Jump to the location requested from the command line once the file is shown
+  useEffect(() => {
+    if (!pendingLocation || isLoading || !currentFile) return;
+
+    if (pendingLocation.line) {
+      // Lines only exist in the source, so show it in the editor
+      if (!isEditMode) {
+        setIsEditMode(true);
+        setIsSplitView(true);
+        return;
+      }
+      if (editorRef.current) {
+        editorRef.current.goToLine(pendingLocation.line, pendingLocation.column);
+      }
+    } else if (pendingLocation.heading) {
+      const element = document.getElementById(normalizeId(pendingLocation.heading));
+      if (element) {
+        element.scrollIntoView({ block: "start" });
+      } else {
+        console.warn(`Heading "${pendingLocation.heading}" not found`);
+      }
+    }
+    setPendingLocation(null);
+  }, [pendingLocation, isLoading, currentFile, isEditMode, htmlContent]);
+
+  // Watch the open document and workspace root for changes made outside mdox
+  useEffect(() => {
+    const documentPath = currentFile && !isRemoteFile ? currentFile : null;
+    const options = {
+      exclude: listOptions.exclude,
+      respect_ignore_files: listOptions.respect_ignore_files,
+    };
+    invoke("watch_paths", { document: documentPath, root: rootDirectory || null, options })
+      .catch((err) => console.error("Failed to watch files:", err));
+  }, [currentFile, isRemoteFile, rootDirectory, listOptions.exclude, listOptions.respect_ignore_files]);
+
+  // Refresh the preview when the open file changes on disk, unless there are unsaved edits
+  useEffect(() => {
+    if (!currentFile || isRemoteFile) return;
+
+    const reload = async (event) => {
+      if (event.payload.path !== currentFile || isDirty) return;
+
+      try {
+        const content = await invoke("read_file", { path: currentFile });
+        const html = await invoke("parse_markdown", { content, basePath: currentFile });
+        setFileContent(content);
+        setEditedContent(content);
+        setHtmlContent(html);
+      } catch (err) {
+        console.error("Failed to reload file:", err);
+      }
+    };
+
+    const unlistenChanged = appWindow.listen("file-changed", reload);
+    const unlistenCreated = appWindow.listen("file-created", reload);
+    const unlistenRenamed = appWindow.listen("file-renamed", (event) => {
+      // Follow the open file to its new name
+      if (event.payload.from === currentFile) {
+        setCurrentFile(event.payload.to);
+      }
+    });
+
+    return () => {
+      unlistenChanged.then((fn) => fn());
+      unlistenCreated.then((fn) => fn());
+      unlistenRenamed.then((fn) => fn());
+    };
+  }, [currentFile, isRemoteFile, isDirty]);
+
+  // Format Document reads the current buffer, so it re-subscribes when that changes
+  useEffect(() => {
+    const unlistenMenuFormatDocument = appWindow.listen("menu-format-document", () => {
+      handleFormatDocument();
+    });
+
+    return () => {
+      unlistenMenuFormatDocument.then((fn) => fn());
+    };
+  }, [handleFormatDocument]);
+
   // Keyboard shortcuts
   useEffect(() => {
     const handleKeyDown = (event) => {
@@ -620,16 +908,16 @@ function App() {
       // Cmd/Ctrl + E: Toggle edit mode
       if ((event.metaKey || event.ctrlKey) && event.key === 'e') {
         event.preventDefault();
-        if (currentFile) {
-          toggleEditMode();
-        }
+        toggleEditMode();
       }
       
-      // Cmd/Ctrl + P: Toggle split view
+      // Cmd/Ctrl + P: Toggle split view while editing, otherwise go to file
       if ((event.metaKey || event.ctrlKey) && event.key === 'p') {
         event.preventDefault();
         if (isEditMode) {
           toggleSplitView();
+        } else {
+          setQuickOpenOpen(true);
         }
       }
       
@@ -704,6 +992,27 @@ function App() {
         rootDirectory={rootDirectory}
         onSelectRootDirectory={selectRootDirectory}
         onClearRootDirectory={clearRootDirectory}
+        permanentDelete={permanentDelete}
+        onTogglePermanentDelete={togglePermanentDelete}
+        excludePatterns={listOptions.exclude}
+        onChangeExcludePatterns={(patterns) => setListOption("exclude", patterns)}
+      />
+
+      <SaveConflict
+        conflict={saveConflict}
+        fileName={currentFile ? currentFile.split(/[/\\]/).pop() : ""}
+        onOverwrite={handleConflictOverwrite}
+        onUseMerged={handleConflictUseMerged}
+        onReload={handleConflictReload}
+        onClose={() => setSaveConflict(null)}
+      />
+
+      <LocalHistory
+        isOpen={historyOpen}
+        onClose={() => setHistoryOpen(false)}
+        filePath={currentFile && !isRemoteFile ? currentFile : null}
+        isDirty={isDirty}
+        onRestore={handleHistoryRestore}
       />
 
       <UrlDialog
@@ -712,18 +1021,92 @@ function App() {
         onSubmit={openRemoteFile}
       />
 
+      <LinkReport
+        isOpen={linkReportOpen}
+        onClose={() => setLinkReportOpen(false)}
+        rootPath={workspaceRoot}
+        onFileSelect={(path, options) => {
+          setLinkReportOpen(false);
+          openFile(path, options);
+        }}
+      />
+
+      <LinkGraph
+        isOpen={linkGraphOpen}
+        onClose={() => setLinkGraphOpen(false)}
+        rootPath={workspaceRoot}
+        currentFile={currentFile}
+        onFileSelect={(path, options) => {
+          setLinkGraphOpen(false);
+          openFile(path, options);
+        }}
+      />
+
+      <OrphanReport
+        isOpen={orphanReportOpen}
+        onClose={() => setOrphanReportOpen(false)}
+        rootPath={workspaceRoot}
+        onFileSelect={(path, options) => {
+          setOrphanReportOpen(false);
+          openFile(path, options);
+        }}
+      />
+
+      <WorkspaceSearch
+        isOpen={workspaceSearchOpen}
+        onClose={() => setWorkspaceSearchOpen(false)}
+        rootPath={workspaceRoot}
+        onResultSelect={async (path, line) => {
+          setWorkspaceSearchOpen(false);
+          await openFile(path);
+          if (line) setPendingLocation({ line });
+        }}
+      />
+
+      <ReplacePanel
+        isOpen={replaceOpen}
+        onClose={() => setReplaceOpen(false)}
+        rootPath={workspaceRoot}
+        onFileSelect={async (path, line) => {
+          setReplaceOpen(false);
+          await openFile(path);
+          if (line) setPendingLocation({ line });
+        }}
+      />
+
+      <QuickOpen
+        isOpen={quickOpenOpen}
+        onClose={() => setQuickOpenOpen(false)}
+        rootPath={workspaceRoot}
+        recentFiles={recentFiles}
+        onFileSelect={(path) => {
+          setQuickOpenOpen(false);
+          openFile(path);
+        }}
+      />
+
       <div className="app-body">
         <Sidebar
           isOpen={sidebarOpen}
           recentFiles={recentFiles}
           linkedDocs={linkedDocs}
           isLoadingLinked={isLoadingLinked}
+          backlinks={backlinks}
+          isLoadingBacklinks={isLoadingBacklinks}
+          onRefreshBacklinks={refreshBacklinks}
+          onBacklinkSelect={async (path, line) => {
+            await openFile(path, { isRootFile: false, addToRecent: false });
+            if (line) setPendingLocation({ line });
+          }}
           currentFile={currentFile}
           displayUrl={displayUrl}
           onFileSelect={openFileOrUrl}
           onRemoveRecent={removeRecentFile}
           onClose={() => setSidebarOpen(false)}
           rootDirectory={rootDirectory}
+          permanentDelete={permanentDelete}
+          listOptions={listOptions}
+          onChangeListOption={setListOption}
         />
 
         <div className="app-main">
@@ -768,7 +1151,7 @@ function App() {
                 Dismiss
               </button>
             </div>
-            ) : currentFile ? (
+            ) : currentFile || isDirty ? (
               <>
                 {isEditMode && (
                   <div
@@ -780,6 +1163,8 @@ function App() {
                       content={editedContent}
                       onChange={handleEditorChange}
                       onSave={handleSave}
+                      onFormat={handleFormatDocument}
+                      onLint={handleLint}
                       theme={theme}
                       onScroll={isSplitView ? handleEditorScroll : null}
                     />
@@ -5,22 +5,33 @@ import { defaultKeymap, indentWithTab, insertTab } from "@codemirror/commands";
 import { markdown } from "@codemirror/lang-markdown";
 import { oneDark } from "@codemirror/theme-one-dark";
 import { autocompletion, closeBrackets, closeBracketsKeymap } from "@codemirror/autocomplete";
+import { linter, lintGutter } from "@codemirror/lint";
 import "./Editor.css";
 
-const Editor = forwardRef(({ content, onChange, onSave, theme, onScroll }, ref) => {
+// Converts a 1-based line and column, as used by the backend, to a document offset
+const toOffset = (doc, line, column = 1) => {
+  const target = doc.line(Math.min(Math.max(line, 1), doc.lines));
+  return Math.min(target.from + Math.max(column, 1) - 1, target.to);
+};
+
+const Editor = forwardRef(({ content, onChange, onSave, onFormat, onLint, theme, onScroll }, ref) => {
   const editorRef = useRef(null);
   const viewRef = useRef(null);
   const scrollTimeoutRef = useRef(null);
   const onChangeRef = useRef(onChange);
   const onSaveRef = useRef(onSave);
+  const onFormatRef = useRef(onFormat);
+  const onLintRef = useRef(onLint);
   const onScrollRef = useRef(onScroll);
 
   // Keep refs updated
   useEffect(() => {
     onChangeRef.current = onChange;
     onSaveRef.current = onSave;
+    onFormatRef.current = onFormat;
+    onLintRef.current = onLint;
     onScrollRef.current = onScroll;
-  }, [onChange, onSave, onScroll]);
+  }, [onChange, onSave, onFormat, onLint, onScroll]);
 
   useImperativeHandle(ref, () => ({
     scrollToPercentage: (percentage) => {
@@ -30,6 +41,17 @@ const Editor = forwardRef(({ content, onChange, onSave, theme, onScroll }, ref)
         scroller.scrollTop = maxScroll * percentage;
       }
     },
+    goToLine: (line, column) => {
+      const view = viewRef.current;
+      if (!view) return;
+
+      const pos = toOffset(view.state.doc, line, column);
+      view.dispatch({
+        selection: { anchor: pos },
+        effects: EditorView.scrollIntoView(pos, { y: "center" }),
+      });
+      view.focus();
+    },
   }));
 
   useEffect(() => {
@@ -60,10 +82,31 @@ const Editor = forwardRef(({ content, onChange, onSave, theme, onScroll }, ref)
       return true;
     };
 
+    const lintSource = async (view) => {
+      if (!onLintRef.current) return [];
+
+      try {
+        const doc = view.state.doc;
+        const diagnostics = await onLintRef.current(doc.toString());
+        return diagnostics.map((d) => ({
+          from: toOffset(doc, d.line, d.column),
+          to: toOffset(doc, d.end_line, d.end_column),
+          severity: d.severity,
+          source: d.rule,
+          message: d.message,
+        }));
+      } catch (err) {
+        console.error("Error linting document:", err);
+        return [];
+      }
+    };
+
     const extensions = [
       markdown(),
       autocompletion(),
       closeBrackets(),
+      linter(lintSource, { delay: 500 }),
+      lintGutter(),
       keymap.of([
         ...closeBracketsKeymap,
         ...defaultKeymap,
@@ -75,6 +118,13 @@ const Editor = forwardRef(({ content, onChange, onSave, theme, onScroll }, ref)
             return true;
           },
         },
+        {
+          key: "Mod-Shift-f",
+          run: () => {
+            if (onFormatRef.current) onFormatRef.current();
+            return true;
+          },
+        },
         // Markdown shortcuts
         { key: "Mod-b", run: (view) => wrapSelection(view, "**") }, // Bold
         { key: "Mod-i", run: (view) => wrapSelection(view, "*") },  // Italic
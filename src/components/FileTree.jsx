@@ -1,9 +1,47 @@
-import { memo, useState, useEffect, useCallback } from 'react';
+import { memo, useState, useEffect, useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow';
 import { ask } from '@tauri-apps/plugin-dialog';
 import InputDialog from './InputDialog';
+import RenamePreview from './RenamePreview';
 import './FileTree.css';
 
+const FILTER_OPTIONS = [
+  { name: 'markdown_only', label: 'Markdown Files Only' },
+  { name: 'markdown_folders_only', label: 'Only Folders with Markdown' },
+  { name: 'show_hidden', label: 'Show Hidden Files' },
+  { name: 'respect_ignore_files', label: 'Respect .gitignore' },
+];
+
+const GIT_STATUS_LABELS = {
+  modified: { letter: 'M', label: 'Modified' },
+  added: { letter: 'A', label: 'Added' },
+  deleted: { letter: 'D', label: 'Deleted' },
+  renamed: { letter: 'R', label: 'Renamed' },
+  untracked: { letter: 'U', label: 'Untracked' },
+  conflicted: { letter: '!', label: 'Conflicted' },
+};
+
+const formatSize = (bytes) => {
+  if (bytes < 1024) return `${bytes} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+  return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+};
+
+// Hover text with whatever metadata the entry has
+const describeEntry = (item) => {
+  const lines = [item.name];
+  if (item.symlink_target) lines.push(`Link to ${item.symlink_target}`);
+  if (item.child_count != null) {
+    lines.push(`${item.child_count} ${item.child_count === 1 ? 'item' : 'items'}`);
+  }
+  if (item.size != null) lines.push(formatSize(item.size));
+  if (item.modified) lines.push(`Modified ${new Date(item.modified).toLocaleString()}`);
+  if (item.created) lines.push(`Created ${new Date(item.created).toLocaleString()}`);
+  if (item.git_status) lines.push(GIT_STATUS_LABELS[item.git_status]?.label);
+  return lines.filter(Boolean).join('\n');
+};
+
 const FileTreeItem = memo(function FileTreeItem({
   item,
   level = 0,
@@ -14,7 +52,8 @@ const FileTreeItem = memo(function FileTreeItem({
   selectedPath,
   expandedFolders,
   onToggleExpand,
-  onContextMenu
+  onContextMenu,
+  listOptions
 }) {
   const [children, setChildren] = useState([]);
   const [isLoading, setIsLoading] = useState(false);
@@ -32,7 +71,7 @@ const FileTreeItem = memo(function FileTreeItem({
     setError(null);
 
     try {
-      const entries = await invoke('read_directory', { path: item.path });
+      const entries = await invoke('read_directory', { path: item.path, options: listOptions });
       setChildren(entries);
       setHasLoaded(true);
     } catch (err) {
@@ -42,7 +81,7 @@ const FileTreeItem = memo(function FileTreeItem({
     } finally {
       setIsLoading(false);
     }
-  }, [item.path, item.is_directory, isLoading]);
+  }, [item.path, item.is_directory, isLoading, listOptions]);
 
   useEffect(() => {
     if (item.is_directory && isExpanded && !hasLoaded && !isLoading) {
@@ -205,7 +244,18 @@ const FileTreeItem = memo(function FileTreeItem({
           )}
         </div>
 
-        <span className="file-tree-item-name">{item.name}</span>
+        <span className="file-tree-item-name" title={describeEntry(item)}>
+          {listOptions.show_titles && item.title ? item.title : item.name}
+        </span>
+
+        {item.git_status && GIT_STATUS_LABELS[item.git_status] && (
+          <span
+            className={`file-tree-git-status git-status-${item.git_status}`}
+            title={GIT_STATUS_LABELS[item.git_status].label}
+          >
+            {GIT_STATUS_LABELS[item.git_status].letter}
+          </span>
+        )}
 
         <button
           className="delete-button"
@@ -250,6 +300,7 @@ const FileTreeItem = memo(function FileTreeItem({
               expandedFolders={expandedFolders}
               onToggleExpand={onToggleExpand}
               onContextMenu={onContextMenu}
+              listOptions={listOptions}
             />
           ))}
         </div>
@@ -258,7 +309,7 @@ const FileTreeItem = memo(function FileTreeItem({
   );
 });
 
-const FileTree = memo(function FileTree({ rootPath, onFileClick, selectedPath }) {
+const FileTree = memo(function FileTree({ rootPath, onFileClick, selectedPath, permanentDelete, listOptions, onChangeListOption }) {
   const [rootItems, setRootItems] = useState([]);
   const [isLoading, setIsLoading] = useState(false);
   const [error, setError] = useState(null);
@@ -268,6 +319,11 @@ const FileTree = memo(function FileTree({ rootPath, onFileClick, selectedPath })
   const [selectedFolder, setSelectedFolder] = useState(null);
   const [contextMenu, setContextMenu] = useState(null);
   const [treeKey, setTreeKey] = useState(0);
+  const [lastDeleted, setLastDeleted] = useState(null); // Name of the item last moved to the trash
+  const [renameItem, setRenameItem] = useState(null);
+  const [renamePlan, setRenamePlan] = useState(null); // Links a pending move would rewrite
+  const [filterMenuOpen, setFilterMenuOpen] = useState(false);
+  const filterRef = useRef(null);
 
   const loadRootDirectory = useCallback(async () => {
     if (!rootPath) {
@@ -279,7 +335,7 @@ const FileTree = memo(function FileTree({ rootPath, onFileClick, selectedPath })
     setError(null);
 
     try {
-      const entries = await invoke('read_directory', { path: rootPath });
+      const entries = await invoke('read_directory', { path: rootPath, options: listOptions });
       setRootItems(entries);
 
       // Recursively find all directories and expand them
@@ -289,7 +345,7 @@ const FileTree = memo(function FileTree({ rootPath, onFileClick, selectedPath })
           if (item.is_directory) {
             allFolders.add(item.path);
             try {
-              const children = await invoke('read_directory', { path: item.path });
+              const children = await invoke('read_directory', { path: item.path, options: listOptions });
               await findAllFolders(children);
             } catch (err) {
               console.error(`Failed to read directory ${item.path}:`, err);
@@ -306,12 +362,33 @@ const FileTree = memo(function FileTree({ rootPath, onFileClick, selectedPath })
     } finally {
       setIsLoading(false);
     }
-  }, [rootPath]);
+  }, [rootPath, listOptions]);
 
   useEffect(() => {
     loadRootDirectory();
+    // Folders loaded with the previous filters have to list themselves again
+    setTreeKey(prev => prev + 1);
   }, [loadRootDirectory]);
 
+  // Pick up files added, removed or renamed outside mdox
+  useEffect(() => {
+    if (!rootPath) return;
+
+    const appWindow = getCurrentWebviewWindow();
+    const refresh = async () => {
+      await loadRootDirectory();
+      setTreeKey(prev => prev + 1);
+    };
+
+    const unlisteners = ['file-created', 'file-deleted', 'file-renamed'].map((eventName) =>
+      appWindow.listen(eventName, refresh)
+    );
+
+    return () => {
+      unlisteners.forEach((unlisten) => unlisten.then(fn => fn()));
+    };
+  }, [rootPath, loadRootDirectory]);
+
   const handleToggleExpand = useCallback((path) => {
     setExpandedFolders((prev) => {
       const next = new Set(prev);
@@ -329,9 +406,11 @@ const FileTree = memo(function FileTree({ rootPath, onFileClick, selectedPath })
   }, []);
 
   const handleDelete = useCallback(async (item) => {
-    const confirmMessage = item.is_directory
-      ? `Are you sure you want to delete the folder "${item.name}" and all its contents?`
-      : `Are you sure you want to delete "${item.name}"?`;
+    const confirmMessage = !permanentDelete
+      ? `Move "${item.name}" to the trash?`
+      : item.is_directory
+        ? `Are you sure you want to permanently delete the folder "${item.name}" and all its contents?`
+        : `Are you sure you want to permanently delete "${item.name}"?`;
 
     const confirmed = await ask(confirmMessage, {
       title: 'Confirm Delete',
@@ -343,7 +422,9 @@ const FileTree = memo(function FileTree({ rootPath, onFileClick, selectedPath })
     }
 
     try {
-      await invoke('delete_file_or_directory', { path: item.path });
+      const undoable = await invoke('delete_file_or_directory', { path: item.path, permanent: permanentDelete });
+      // Only the most recent delete can be undone, and only where the trash allows it
+      setLastDeleted(undoable ? item.name : null);
       // Reload the directory
       await loadRootDirectory();
     } catch (err) {
@@ -353,13 +434,41 @@ const FileTree = memo(function FileTree({ rootPath, onFileClick, selectedPath })
         kind: 'error',
       });
     }
+  }, [loadRootDirectory, permanentDelete]);
+
+  const handleUndoDelete = useCallback(async () => {
+    setLastDeleted(null);
+
+    try {
+      await invoke('undo_delete');
+      await loadRootDirectory();
+      setTreeKey(prev => prev + 1);
+    } catch (err) {
+      console.error('Failed to undo delete:', err);
+      await ask(`Failed to undo delete: ${err}`, {
+        title: 'Error',
+        kind: 'error',
+      });
+    }
   }, [loadRootDirectory]);
 
-  const handleMove = useCallback(async (oldPath, newPath) => {
+  const moveItem = useCallback(async (oldPath, newPath, updateLinks) => {
     try {
-      await invoke('rename_file_or_directory', { oldPath, newPath });
+      if (updateLinks) {
+        const result = await invoke('rename_with_links', { root: rootPath, oldPath, newPath });
+        if (result.failed.length > 0) {
+          const failures = result.failed.map(({ path, message }) => `${path}: ${message}`).join('\n');
+          await ask(`Moved, but the links in these documents could not be updated:\n\n${failures}`, {
+            title: 'Some Links Not Updated',
+            kind: 'warning',
+          });
+        }
+      } else {
+        await invoke('rename_file_or_directory', { oldPath, newPath });
+      }
       // Reload the directory
       await loadRootDirectory();
+      setTreeKey(prev => prev + 1);
     } catch (err) {
       console.error('Failed to move:', err);
       await ask(`Failed to move: ${err}`, {
@@ -367,7 +476,42 @@ const FileTree = memo(function FileTree({ rootPath, onFileClick, selectedPath })
         kind: 'error',
       });
     }
-  }, [loadRootDirectory]);
+  }, [rootPath, loadRootDirectory]);
+
+  const handleMove = useCallback(async (oldPath, newPath) => {
+    try {
+      // Ask before rewriting other documents; moves that break no links go ahead
+      const plan = await invoke('preview_rename', { root: rootPath, oldPath, newPath });
+      if (plan.documents.length > 0 || plan.skipped.length > 0) {
+        setRenamePlan(plan);
+        return;
+      }
+    } catch (err) {
+      console.error('Failed to move:', err);
+      await ask(`Failed to move: ${err}`, {
+        title: 'Error',
+        kind: 'error',
+      });
+      return;
+    }
+
+    await moveItem(oldPath, newPath, false);
+  }, [rootPath, moveItem]);
+
+  const handleApplyRename = useCallback(async (updateLinks) => {
+    const plan = renamePlan;
+    setRenamePlan(null);
+    await moveItem(plan.old_path, plan.new_path, updateLinks);
+  }, [renamePlan, moveItem]);
+
+  const handleRename = useCallback(async (name) => {
+    const item = renameItem;
+    setRenameItem(null);
+    if (!item || name === item.name) return;
+
+    const parent = item.path.replace(/[/\\][^/\\]*$/, '');
+    await handleMove(item.path, `${parent}/${name}`);
+  }, [renameItem, handleMove]);
 
   const handleCreateFile = useCallback(async (fileName) => {
     if (!fileName || !rootPath) return;
@@ -473,6 +617,13 @@ const FileTree = memo(function FileTree({ rootPath, onFileClick, selectedPath })
     closeContextMenu();
   }, [contextMenu, closeContextMenu]);
 
+  const handleContextRename = useCallback(() => {
+    if (contextMenu?.item) {
+      setRenameItem(contextMenu.item);
+    }
+    closeContextMenu();
+  }, [contextMenu, closeContextMenu]);
+
   const handleContextDelete = useCallback(async () => {
     if (contextMenu?.item) {
       await handleDelete(contextMenu.item);
@@ -480,6 +631,19 @@ const FileTree = memo(function FileTree({ rootPath, onFileClick, selectedPath })
     closeContextMenu();
   }, [contextMenu, handleDelete, closeContextMenu]);
 
+  // Close the filter menu when clicking outside it
+  useEffect(() => {
+    if (!filterMenuOpen) return;
+
+    const handleClick = (e) => {
+      if (!filterRef.current?.contains(e.target)) {
+        setFilterMenuOpen(false);
+      }
+    };
+    document.addEventListener('click', handleClick);
+    return () => document.removeEventListener('click', handleClick);
+  }, [filterMenuOpen]);
+
   // Close context menu when clicking outside
   useEffect(() => {
     if (contextMenu) {
@@ -507,6 +671,52 @@ const FileTree = memo(function FileTree({ rootPath, onFileClick, selectedPath })
       onDrop={handleRootDrop}
     >
       <div className="file-tree-actions">
+        <div className="file-tree-filter" ref={filterRef}>
+          <button
+            className={`file-tree-action-button ${filterMenuOpen ? 'active' : ''}`}
+            onClick={() => setFilterMenuOpen(!filterMenuOpen)}
+            title="Filter"
+          >
+            <svg width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+              <polygon points="22 3 2 3 10 12.46 10 19 14 21 14 12.46 22 3"></polygon>
+            </svg>
+          </button>
+          {filterMenuOpen && (
+            <div className="context-menu file-tree-filter-menu">
+              {FILTER_OPTIONS.map(({ name, label }) => (
+                <button
+                  key={name}
+                  className="context-menu-item"
+                  onClick={() => onChangeListOption(name, !listOptions[name])}
+                >
+                  <svg width="14" height="14" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+                    {listOptions[name] && <polyline points="20 6 9 17 4 12"></polyline>}
+                  </svg>
+                  {label}
+                </button>
+              ))}
+              <div className="context-menu-separator" />
+              <button
+                className="context-menu-item"
+                onClick={() => onChangeListOption('sort_by', listOptions.sort_by === 'modified' ? 'name' : 'modified')}
+              >
+                <svg width="14" height="14" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+                  {listOptions.sort_by === 'modified' && <polyline points="20 6 9 17 4 12"></polyline>}
+                </svg>
+                Recently Modified First
+              </button>
+              <button
+                className="context-menu-item"
+                onClick={() => onChangeListOption('show_titles', !listOptions.show_titles)}
+              >
+                <svg width="14" height="14" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+                  {listOptions.show_titles && <polyline points="20 6 9 17 4 12"></polyline>}
+                </svg>
+                Show Document Titles
+              </button>
+            </div>
+          )}
+        </div>
         <button
           className="file-tree-action-button"
           onClick={() => setShowFileDialog(true)}
@@ -532,6 +742,25 @@ const FileTree = memo(function FileTree({ rootPath, onFileClick, selectedPath })
         </button>
       </div>
 
+      {lastDeleted && (
+        <div className="file-tree-undo">
+          <span className="file-tree-undo-message">Moved "{lastDeleted}" to the trash</span>
+          <button className="file-tree-undo-button" onClick={handleUndoDelete}>
+            Undo
+          </button>
+          <button
+            className="file-tree-action-button"
+            onClick={() => setLastDeleted(null)}
+            title="Dismiss"
+          >
+            <svg width="14" height="14" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+              <line x1="18" y1="6" x2="6" y2="18"></line>
+              <line x1="6" y1="6" x2="18" y2="18"></line>
+            </svg>
+          </button>
+        </div>
+      )}
+
       {isLoading && <div className="file-tree-loading">Loading...</div>}
       {error && <div className="file-tree-error">Failed to load directory</div>}
       {!isLoading && !error && rootItems.length === 0 && (
@@ -552,6 +781,7 @@ const FileTree = memo(function FileTree({ rootPath, onFileClick, selectedPath })
           expandedFolders={expandedFolders}
           onToggleExpand={handleToggleExpand}
           onContextMenu={handleContextMenu}
+          listOptions={listOptions}
         />
       ))}
 
@@ -583,12 +813,19 @@ const FileTree = memo(function FileTree({ rootPath, onFileClick, selectedPath })
               <div className="context-menu-separator" />
             </>
           )}
+          <button className="context-menu-item" onClick={handleContextRename}>
+            <svg width="14" height="14" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+              <path d="M12 20h9"></path>
+              <path d="M16.5 3.5a2.121 2.121 0 0 1 3 3L7 19l-4 1 1-4L16.5 3.5z"></path>
+            </svg>
+            Rename...
+          </button>
           <button className="context-menu-item context-menu-item-danger" onClick={handleContextDelete}>
             <svg width="14" height="14" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
               <polyline points="3 6 5 6 21 6"></polyline>
               <path d="M19 6v14a2 2 0 0 1-2 2H7a2 2 0 0 1-2-2V6m3 0V4a2 2 0 0 1 2-2h4a2 2 0 0 1 2 2v2"></path>
             </svg>
-            Delete
+            {permanentDelete ? 'Delete' : 'Move to Trash'}
           </button>
         </div>
       )}
@@ -608,6 +845,24 @@ const FileTree = memo(function FileTree({ rootPath, onFileClick, selectedPath })
         title="Create New Folder"
         placeholder="Enter folder name"
       />
+
+      <InputDialog
+        isOpen={renameItem !== null}
+        onClose={() => setRenameItem(null)}
+        onSubmit={handleRename}
+        title="Rename"
+        placeholder="Enter new name"
+        defaultValue={renameItem?.name || ''}
+        submitLabel="Rename"
+      />
+
+      <RenamePreview
+        plan={renamePlan}
+        rootPath={rootPath}
+        onApply={() => handleApplyRename(true)}
+        onMoveOnly={() => handleApplyRename(false)}
+        onClose={() => setRenamePlan(null)}
+      />
     </div>
   );
 });
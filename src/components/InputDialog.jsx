@@ -1,7 +1,7 @@
 import { memo, useState, useEffect, useRef } from 'react';
 import './InputDialog.css';
 
-const InputDialog = memo(function InputDialog({ isOpen, onClose, onSubmit, title, placeholder, defaultValue = '' }) {
+const InputDialog = memo(function InputDialog({ isOpen, onClose, onSubmit, title, placeholder, defaultValue = '', submitLabel = 'Create' }) {
   const [value, setValue] = useState(defaultValue);
   const inputRef = useRef(null);
 
@@ -51,7 +51,7 @@ const InputDialog = memo(function InputDialog({ isOpen, onClose, onSubmit, title
               Cancel
             </button>
             <button type="submit" className="input-dialog-button-submit" disabled={!value.trim()}>
-              Create
+              {submitLabel}
             </button>
           </div>
         </form>
@@ -0,0 +1,354 @@
+import { memo, useState, useEffect, useCallback, useMemo, useRef } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import "./LinkGraph.css";
+
+// Force layout tuning: how hard nodes push apart, how long links want to be,
+// and how quickly the layout settles
+const REPULSION = 900;
+const LINK_DISTANCE = 70;
+const LINK_STRENGTH = 0.04;
+const CENTER_STRENGTH = 0.01;
+const VELOCITY_DECAY = 0.6;
+const ALPHA_DECAY = 0.985;
+const ALPHA_MIN = 0.005;
+
+const MIN_ZOOM = 0.1;
+const MAX_ZOOM = 4;
+
+// One step of a simple force simulation, moving nodes in place
+const layoutStep = (nodes, links, alpha) => {
+  for (let i = 0; i < nodes.length; i++) {
+    const a = nodes[i];
+    for (let j = i + 1; j < nodes.length; j++) {
+      const b = nodes[j];
+      let dx = b.x - a.x;
+      let dy = b.y - a.y;
+      let distanceSquared = dx * dx + dy * dy;
+      if (distanceSquared === 0) {
+        dx = Math.random() - 0.5;
+        dy = Math.random() - 0.5;
+        distanceSquared = dx * dx + dy * dy;
+      }
+      const force = (REPULSION * alpha) / distanceSquared;
+      a.vx -= dx * force;
+      a.vy -= dy * force;
+      b.vx += dx * force;
+      b.vy += dy * force;
+    }
+  }
+
+  for (const { source, target } of links) {
+    const a = nodes[source];
+    const b = nodes[target];
+    const dx = b.x - a.x;
+    const dy = b.y - a.y;
+    const distance = Math.sqrt(dx * dx + dy * dy) || 1;
+    const force = ((distance - LINK_DISTANCE) / distance) * LINK_STRENGTH * alpha;
+    a.vx += dx * force;
+    a.vy += dy * force;
+    b.vx -= dx * force;
+    b.vy -= dy * force;
+  }
+
+  for (const node of nodes) {
+    if (node.fixed) {
+      node.vx = 0;
+      node.vy = 0;
+      continue;
+    }
+    node.vx -= node.x * CENTER_STRENGTH * alpha;
+    node.vy -= node.y * CENTER_STRENGTH * alpha;
+    node.vx *= VELOCITY_DECAY;
+    node.vy *= VELOCITY_DECAY;
+    node.x += node.vx;
+    node.y += node.vy;
+  }
+};
+
+const LinkGraph = memo(function LinkGraph({ isOpen, onClose, rootPath, currentFile, onFileSelect }) {
+  const [graph, setGraph] = useState(null);
+  const [isLoading, setIsLoading] = useState(false);
+  const [error, setError] = useState(null);
+  const [selectedTag, setSelectedTag] = useState("");
+  const [hovered, setHovered] = useState(null);
+  const [view, setView] = useState({ x: 0, y: 0, k: 1 });
+  const [, setFrame] = useState(0);
+
+  const svgRef = useRef(null);
+  const nodesRef = useRef([]);
+  const alphaRef = useRef(1);
+  const animationRef = useRef(null);
+  const dragRef = useRef(null);
+
+  const loadGraph = useCallback(async () => {
+    if (!rootPath) return;
+
+    setIsLoading(true);
+    setError(null);
+
+    try {
+      setGraph(await invoke("link_graph", { root: rootPath }));
+    } catch (err) {
+      console.error("Failed to load link graph:", err);
+      setError(typeof err === "string" ? err : err.message || "Unknown error occurred");
+      setGraph(null);
+    } finally {
+      setIsLoading(false);
+    }
+  }, [rootPath]);
+
+  useEffect(() => {
+    if (isOpen) {
+      loadGraph();
+    }
+  }, [isOpen, loadGraph]);
+
+  // Several links between the same two documents are drawn as one line
+  const { links, neighbours, tags } = useMemo(() => {
+    if (!graph) return { links: [], neighbours: [], tags: [] };
+
+    const indexByPath = new Map(graph.nodes.map((node, index) => [node.path, index]));
+    const seen = new Set();
+    const links = [];
+    const neighbours = graph.nodes.map(() => new Set());
+    for (const edge of graph.edges) {
+      const source = indexByPath.get(edge.source);
+      const target = indexByPath.get(edge.target);
+      if (source === undefined || target === undefined || source === target) continue;
+      neighbours[source].add(target);
+      neighbours[target].add(source);
+
+      const key = `${source}:${target}`;
+      if (seen.has(key)) continue;
+      seen.add(key);
+      links.push({ source, target });
+    }
+
+    const tags = [...new Set(graph.nodes.flatMap((node) => node.tags))].sort();
+    return { links, neighbours, tags };
+  }, [graph]);
+
+  const runLayout = useCallback(() => {
+    cancelAnimationFrame(animationRef.current);
+    const tick = () => {
+      // Large graphs take several steps per frame to settle in reasonable time
+      const steps = nodesRef.current.length > 300 ? 1 : 3;
+      for (let step = 0; step < steps && alphaRef.current > ALPHA_MIN; step++) {
+        layoutStep(nodesRef.current, links, alphaRef.current);
+        alphaRef.current *= ALPHA_DECAY;
+      }
+      setFrame((frame) => frame + 1);
+      if (alphaRef.current > ALPHA_MIN) {
+        animationRef.current = requestAnimationFrame(tick);
+      }
+    };
+    animationRef.current = requestAnimationFrame(tick);
+  }, [links]);
+
+  // Start the documents on a circle and let the forces spread them out
+  useEffect(() => {
+    if (!graph) return;
+
+    const radius = Math.max(100, Math.sqrt(graph.nodes.length) * 40);
+    nodesRef.current = graph.nodes.map((_, index) => {
+      const angle = (index / graph.nodes.length) * 2 * Math.PI;
+      return { x: radius * Math.cos(angle), y: radius * Math.sin(angle), vx: 0, vy: 0, fixed: false };
+    });
+    alphaRef.current = 1;
+    setView({ x: 0, y: 0, k: 1 });
+    runLayout();
+
+    return () => cancelAnimationFrame(animationRef.current);
+  }, [graph, runLayout]);
+
+  useEffect(() => {
+    if (!isOpen) {
+      cancelAnimationFrame(animationRef.current);
+      setSelectedTag("");
+      setHovered(null);
+    }
+  }, [isOpen]);
+
+  if (!isOpen) return null;
+
+  const toGraphPoint = (event) => {
+    const rect = svgRef.current.getBoundingClientRect();
+    return {
+      x: (event.clientX - rect.left - rect.width / 2 - view.x) / view.k,
+      y: (event.clientY - rect.top - rect.height / 2 - view.y) / view.k,
+    };
+  };
+
+  const handleWheel = (event) => {
+    const rect = svgRef.current.getBoundingClientRect();
+    const pointerX = event.clientX - rect.left - rect.width / 2;
+    const pointerY = event.clientY - rect.top - rect.height / 2;
+    setView((view) => {
+      const k = Math.min(MAX_ZOOM, Math.max(MIN_ZOOM, view.k * Math.exp(-event.deltaY * 0.002)));
+      // Keep the point under the pointer in place
+      return {
+        k,
+        x: pointerX - ((pointerX - view.x) * k) / view.k,
+        y: pointerY - ((pointerY - view.y) * k) / view.k,
+      };
+    });
+  };
+
+  const handlePointerDown = (event, nodeIndex = null) => {
+    event.stopPropagation();
+    event.currentTarget.setPointerCapture?.(event.pointerId);
+    dragRef.current = { nodeIndex, startX: event.clientX, startY: event.clientY, view, moved: false };
+    if (nodeIndex !== null) {
+      nodesRef.current[nodeIndex].fixed = true;
+    }
+  };
+
+  const handlePointerMove = (event) => {
+    const drag = dragRef.current;
+    if (!drag) return;
+
+    const dx = event.clientX - drag.startX;
+    const dy = event.clientY - drag.startY;
+    if (Math.abs(dx) + Math.abs(dy) > 3) drag.moved = true;
+
+    if (drag.nodeIndex === null) {
+      setView({ ...drag.view, x: drag.view.x + dx, y: drag.view.y + dy });
+    } else if (drag.moved) {
+      const node = nodesRef.current[drag.nodeIndex];
+      Object.assign(node, toGraphPoint(event));
+      alphaRef.current = Math.max(alphaRef.current, 0.3);
+      runLayout();
+    }
+  };
+
+  const handlePointerUp = () => {
+    const drag = dragRef.current;
+    dragRef.current = null;
+    if (!drag || drag.nodeIndex === null) return;
+
+    nodesRef.current[drag.nodeIndex].fixed = false;
+    // A click without dragging opens the document
+    if (!drag.moved) {
+      onFileSelect(graph.nodes[drag.nodeIndex].path, { isRootFile: false, addToRecent: false });
+    }
+  };
+
+  const relativePath = (path) => {
+    if (rootPath && path.startsWith(rootPath)) {
+      return path.slice(rootPath.length).replace(/^[/\\]/, "");
+    }
+    return path;
+  };
+
+  const isDimmed = (index) => {
+    if (selectedTag && !graph.nodes[index].tags.includes(selectedTag)) return true;
+    return hovered !== null && hovered !== index && !neighbours[hovered].has(index);
+  };
+
+  const nodes = nodesRef.current;
+  const hasLayout = graph && nodes.length === graph.nodes.length;
+
+  return (
+    <>
+      <div className="link-graph-overlay" onClick={onClose} />
+      <div className="link-graph">
+        <div className="link-graph-header">
+          <h2>Link Graph</h2>
+          <div className="link-graph-header-actions">
+            {tags.length > 0 && (
+              <select
+                className="link-graph-tags"
+                value={selectedTag}
+                onChange={(e) => setSelectedTag(e.target.value)}
+              >
+                <option value="">All tags</option>
+                {tags.map((tag) => (
+                  <option key={tag} value={tag}>
+                    {tag}
+                  </option>
+                ))}
+              </select>
+            )}
+            <button className="link-graph-reload" onClick={loadGraph} disabled={isLoading || !rootPath}>
+              Reload
+            </button>
+            <button className="link-graph-close" onClick={onClose}>
+              <svg width="20" height="20" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+                <line x1="18" y1="6" x2="6" y2="18"></line>
+                <line x1="6" y1="6" x2="18" y2="18"></line>
+              </svg>
+            </button>
+          </div>
+        </div>
+
+        <div className="link-graph-content">
+          {!rootPath ? (
+            <div className="link-graph-empty">Select a root directory in Settings or open a file first</div>
+          ) : isLoading && !graph ? (
+            <div className="link-graph-empty">Reading links...</div>
+          ) : error ? (
+            <div className="link-graph-error">{error}</div>
+          ) : graph && graph.nodes.length === 0 ? (
+            <div className="link-graph-empty">No Markdown files found</div>
+          ) : hasLayout && (
+            <svg
+              ref={svgRef}
+              className="link-graph-canvas"
+              onWheel={handleWheel}
+              onPointerDown={(e) => handlePointerDown(e)}
+              onPointerMove={handlePointerMove}
+              onPointerUp={handlePointerUp}
+              onPointerLeave={handlePointerUp}
+            >
+              <defs>
+                <marker id="link-graph-arrow" viewBox="0 0 10 10" refX="16" refY="5" markerWidth="6" markerHeight="6" orient="auto">
+                  <path d="M 0 0 L 10 5 L 0 10 z" className="link-graph-arrow" />
+                </marker>
+              </defs>
+              {/* Graph coordinates start at the center of the view */}
+              <svg x="50%" y="50%" overflow="visible">
+                <g transform={`translate(${view.x} ${view.y}) scale(${view.k})`}>
+                  <g className="link-graph-links">
+                    {links.map(({ source, target }) => (
+                      <line
+                        key={`${source}:${target}`}
+                        x1={nodes[source].x}
+                        y1={nodes[source].y}
+                        x2={nodes[target].x}
+                        y2={nodes[target].y}
+                        markerEnd="url(#link-graph-arrow)"
+                        className={isDimmed(source) || isDimmed(target) ? "dimmed" : undefined}
+                      />
+                    ))}
+                  </g>
+                  {graph.nodes.map((node, index) => (
+                    <g
+                      key={node.path}
+                      className={`link-graph-node${node.path === currentFile ? " current" : ""}${isDimmed(index) ? " dimmed" : ""}`}
+                      transform={`translate(${nodes[index].x} ${nodes[index].y})`}
+                      onPointerDown={(e) => handlePointerDown(e, index)}
+                      onPointerEnter={() => setHovered(index)}
+                      onPointerLeave={() => setHovered(null)}
+                    >
+                      <title>{`${node.title}\n${relativePath(node.path)}${node.tags.length ? `\n#${node.tags.join(" #")}` : ""}`}</title>
+                      <circle r={5 + Math.min(10, Math.sqrt(neighbours[index].size) * 2)} />
+                      {(view.k >= 0.6 || hovered === index) && <text y={-12}>{node.title}</text>}
+                    </g>
+                  ))}
+                </g>
+              </svg>
+            </svg>
+          )}
+        </div>
+
+        {graph && (
+          <div className="link-graph-footer">
+            {graph.nodes.length} documents, {links.length} links. Drag to move, scroll to zoom, click a document to open it.
+          </div>
+        )}
+      </div>
+    </>
+  );
+});
+
+export default LinkGraph;
@@ -0,0 +1,111 @@
+import { memo, useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import "./LinkReport.css";
+
+const KIND_LABELS = {
+  file: "Missing file",
+  anchor: "Missing anchor",
+  image: "Missing image",
+};
+
+const LinkReport = memo(function LinkReport({ isOpen, onClose, rootPath, onFileSelect }) {
+  const [report, setReport] = useState(null);
+  const [isLoading, setIsLoading] = useState(false);
+  const [error, setError] = useState(null);
+
+  const runCheck = useCallback(async () => {
+    if (!rootPath) return;
+
+    setIsLoading(true);
+    setError(null);
+
+    try {
+      const result = await invoke("check_links", { rootPath });
+      setReport(result);
+    } catch (err) {
+      console.error("Failed to check links:", err);
+      setError(typeof err === "string" ? err : err.message || "Unknown error occurred");
+      setReport(null);
+    } finally {
+      setIsLoading(false);
+    }
+  }, [rootPath]);
+
+  useEffect(() => {
+    if (isOpen) {
+      runCheck();
+    }
+  }, [isOpen, runCheck]);
+
+  if (!isOpen) return null;
+
+  const relativePath = (path) => {
+    if (rootPath && path.startsWith(rootPath)) {
+      return path.slice(rootPath.length).replace(/^[/\\]/, "");
+    }
+    return path;
+  };
+
+  return (
+    <>
+      <div className="link-report-overlay" onClick={onClose} />
+      <div className="link-report">
+        <div className="link-report-header">
+          <h2>Link Check</h2>
+          <div className="link-report-header-actions">
+            <button className="link-report-rerun" onClick={runCheck} disabled={isLoading || !rootPath}>
+              Re-run
+            </button>
+            <button className="link-report-close" onClick={onClose}>
+              <svg width="20" height="20" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+                <line x1="18" y1="6" x2="6" y2="18"></line>
+                <line x1="6" y1="6" x2="18" y2="18"></line>
+              </svg>
+            </button>
+          </div>
+        </div>
+
+        <div className="link-report-content">
+          {!rootPath ? (
+            <div className="link-report-empty">Select a root directory in Settings or open a file first</div>
+          ) : isLoading ? (
+            <div className="link-report-empty">Checking links...</div>
+          ) : error ? (
+            <div className="link-report-error">{error}</div>
+          ) : report && (
+            <>
+              <div className="link-report-summary">
+                Checked {report.links_checked} links in {report.files_checked} files, {report.broken.length} broken
+              </div>
+              {report.broken.length === 0 ? (
+                <div className="link-report-empty">No broken links found</div>
+              ) : (
+                <ul className="link-report-list">
+                  {report.broken.map((broken, index) => (
+                    <li
+                      key={index}
+                      onClick={() => onFileSelect(broken.file, { isRootFile: false, addToRecent: false })}
+                      title={broken.file}
+                    >
+                      <span className={`link-report-kind link-report-kind-${broken.kind}`}>
+                        {KIND_LABELS[broken.kind] || broken.kind}
+                      </span>
+                      <div className="link-report-details">
+                        <span className="link-report-target">{broken.target}</span>
+                        <span className="link-report-location">
+                          {relativePath(broken.file)}:{broken.line}:{broken.column}
+                        </span>
+                      </div>
+                    </li>
+                  ))}
+                </ul>
+              )}
+            </>
+          )}
+        </div>
+      </div>
+    </>
+  );
+});
+
+export default LinkReport;
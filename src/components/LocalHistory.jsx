@@ -0,0 +1,148 @@
+import { memo, useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import "./LocalHistory.css";
+
+const formatSize = (bytes) => (bytes < 1024 ? `${bytes} B` : `${(bytes / 1024).toFixed(1)} KB`);
+
+const diffLineClass = (line) => {
+  if (line.startsWith("@@")) return "local-history-diff-hunk";
+  if (line.startsWith("+")) return "local-history-diff-added";
+  if (line.startsWith("-")) return "local-history-diff-removed";
+  return undefined;
+};
+
+const LocalHistory = memo(function LocalHistory({ isOpen, onClose, filePath, isDirty, onRestore }) {
+  const [versions, setVersions] = useState([]);
+  const [selectedId, setSelectedId] = useState(null);
+  const [diff, setDiff] = useState(null);
+  const [isLoading, setIsLoading] = useState(false);
+  const [error, setError] = useState(null);
+
+  const loadVersions = useCallback(async () => {
+    if (!filePath) return;
+
+    setIsLoading(true);
+    setError(null);
+
+    try {
+      const result = await invoke("list_history", { path: filePath });
+      setVersions(result);
+      setSelectedId(result.length > 0 ? result[0].id : null);
+    } catch (err) {
+      console.error("Failed to load local history:", err);
+      setError(typeof err === "string" ? err : err.message || "Unknown error occurred");
+      setVersions([]);
+    } finally {
+      setIsLoading(false);
+    }
+  }, [filePath]);
+
+  useEffect(() => {
+    if (isOpen) {
+      loadVersions();
+    }
+  }, [isOpen, loadVersions]);
+
+  useEffect(() => {
+    if (!isOpen || !filePath || !selectedId) {
+      setDiff(null);
+      return;
+    }
+
+    invoke("diff_history_version", { path: filePath, id: selectedId })
+      .then(setDiff)
+      .catch((err) => {
+        console.error("Failed to diff version:", err);
+        setDiff(null);
+      });
+  }, [isOpen, filePath, selectedId]);
+
+  if (!isOpen) return null;
+
+  const handleRestore = async () => {
+    if (isDirty) {
+      const confirmed = window.confirm("You have unsaved changes. Do you want to discard them?");
+      if (!confirmed) return;
+    }
+
+    try {
+      const content = await invoke("restore_history_version", { path: filePath, id: selectedId });
+      onRestore(content);
+    } catch (err) {
+      console.error("Failed to restore version:", err);
+      setError(typeof err === "string" ? err : err.message || "Unknown error occurred");
+    }
+  };
+
+  // Skip the ---/+++ header; the list already says what is being compared
+  const diffLines = diff ? diff.split("\n").slice(2) : [];
+
+  return (
+    <>
+      <div className="local-history-overlay" onClick={onClose} />
+      <div className="local-history">
+        <div className="local-history-header">
+          <h2>Local History</h2>
+          <button className="local-history-close" onClick={onClose}>
+            <svg width="20" height="20" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+              <line x1="18" y1="6" x2="6" y2="18"></line>
+              <line x1="6" y1="6" x2="18" y2="18"></line>
+            </svg>
+          </button>
+        </div>
+
+        <div className="local-history-content">
+          {!filePath ? (
+            <div className="local-history-empty">Save the document to start its history</div>
+          ) : isLoading ? (
+            <div className="local-history-empty">Loading history...</div>
+          ) : error ? (
+            <div className="local-history-error">{error}</div>
+          ) : versions.length === 0 ? (
+            <div className="local-history-empty">No versions saved yet</div>
+          ) : (
+            <div className="local-history-body">
+              <ul className="local-history-list">
+                {versions.map((version) => (
+                  <li
+                    key={version.id}
+                    className={version.id === selectedId ? "selected" : undefined}
+                    onClick={() => setSelectedId(version.id)}
+                  >
+                    <span className="local-history-time">{new Date(version.timestamp).toLocaleString()}</span>
+                    <span className="local-history-size">{formatSize(version.size)}</span>
+                  </li>
+                ))}
+              </ul>
+              <div className="local-history-diff">
+                <div className="local-history-diff-title">Changes since this version</div>
+                {diffLines.length === 0 || diffLines.every((line) => line === "") ? (
+                  <div className="local-history-empty">Same as the file on disk</div>
+                ) : (
+                  <pre>
+                    {diffLines.map((line, index) => (
+                      <div key={index} className={diffLineClass(line)}>
+                        {line || " "}
+                      </div>
+                    ))}
+                  </pre>
+                )}
+              </div>
+            </div>
+          )}
+        </div>
+
+        <div className="local-history-actions">
+          <button onClick={onClose} className="local-history-btn-cancel">
+            Close
+          </button>
+          <button onClick={handleRestore} className="local-history-btn-restore" disabled={!selectedId}>
+            Restore Version
+          </button>
+        </div>
+      </div>
+    </>
+  );
+});
+
+export default LocalHistory;
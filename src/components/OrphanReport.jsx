@@ -0,0 +1,141 @@
+import { memo, useState, useEffect, useCallback, useRef } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import "./OrphanReport.css";
+
+const ENTRY_POINTS_KEY = "mdox-entry-points";
+const DEFAULT_ENTRY_POINTS = "README, index";
+
+const SECTIONS = [
+  { key: "orphans", label: "Orphaned", description: "No other document links here" },
+  { key: "unreachable", label: "Unreachable", description: "Only linked from documents the entry points don't lead to" },
+  { key: "dead_ends", label: "Dead ends", description: "Links to no other document" },
+];
+
+const OrphanReport = memo(function OrphanReport({ isOpen, onClose, rootPath, onFileSelect }) {
+  const [report, setReport] = useState(null);
+  const [isLoading, setIsLoading] = useState(false);
+  const [error, setError] = useState(null);
+  const [entryPoints, setEntryPoints] = useState(
+    () => localStorage.getItem(ENTRY_POINTS_KEY) ?? DEFAULT_ENTRY_POINTS
+  );
+
+  // Entry point edits apply on Re-run rather than on every keystroke
+  const entryPointsRef = useRef(entryPoints);
+
+  useEffect(() => {
+    entryPointsRef.current = entryPoints;
+    localStorage.setItem(ENTRY_POINTS_KEY, entryPoints);
+  }, [entryPoints]);
+
+  const runReport = useCallback(async () => {
+    if (!rootPath) return;
+
+    setIsLoading(true);
+    setError(null);
+
+    try {
+      const result = await invoke("orphan_report", {
+        root: rootPath,
+        entryPoints: entryPointsRef.current.split(",").map((entry) => entry.trim()).filter(Boolean),
+      });
+      setReport(result);
+    } catch (err) {
+      console.error("Failed to find orphaned documents:", err);
+      setError(typeof err === "string" ? err : err.message || "Unknown error occurred");
+      setReport(null);
+    } finally {
+      setIsLoading(false);
+    }
+  }, [rootPath]);
+
+  useEffect(() => {
+    if (isOpen) {
+      runReport();
+    }
+  }, [isOpen, runReport]);
+
+  if (!isOpen) return null;
+
+  const relativePath = (path) => {
+    if (rootPath && path.startsWith(rootPath)) {
+      return path.slice(rootPath.length).replace(/^[/\\]/, "");
+    }
+    return path;
+  };
+
+  return (
+    <>
+      <div className="orphan-report-overlay" onClick={onClose} />
+      <div className="orphan-report">
+        <div className="orphan-report-header">
+          <h2>Orphaned Documents</h2>
+          <div className="orphan-report-header-actions">
+            <button className="orphan-report-rerun" onClick={runReport} disabled={isLoading || !rootPath}>
+              Re-run
+            </button>
+            <button className="orphan-report-close" onClick={onClose}>
+              <svg width="20" height="20" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+                <line x1="18" y1="6" x2="6" y2="18"></line>
+                <line x1="6" y1="6" x2="18" y2="18"></line>
+              </svg>
+            </button>
+          </div>
+        </div>
+
+        <div className="orphan-report-entry-points">
+          <label htmlFor="orphan-report-entry-points">Entry points</label>
+          <input
+            id="orphan-report-entry-points"
+            type="text"
+            value={entryPoints}
+            onChange={(e) => setEntryPoints(e.target.value)}
+            onKeyDown={(e) => {
+              if (e.key === "Enter") runReport();
+            }}
+            placeholder="File names, e.g. README, index"
+          />
+        </div>
+
+        <div className="orphan-report-content">
+          {!rootPath ? (
+            <div className="orphan-report-empty">Select a root directory in Settings or open a file first</div>
+          ) : isLoading ? (
+            <div className="orphan-report-empty">Reading links...</div>
+          ) : error ? (
+            <div className="orphan-report-error">{error}</div>
+          ) : report && (
+            <>
+              <div className="orphan-report-summary">
+                Checked {report.files_checked} files: {report.orphans.length} orphaned, {report.unreachable.length} unreachable, {report.dead_ends.length} dead ends
+              </div>
+              {SECTIONS.filter(({ key }) => report[key].length > 0).map(({ key, label, description }) => (
+                <section key={key} className="orphan-report-section">
+                  <h3>
+                    {label} <span>{description}</span>
+                  </h3>
+                  <ul className="orphan-report-list">
+                    {report[key].map((document) => (
+                      <li
+                        key={document.path}
+                        onClick={() => onFileSelect(document.path, { isRootFile: false, addToRecent: false })}
+                        title={document.path}
+                      >
+                        <span className="orphan-report-title">{document.title}</span>
+                        <span className="orphan-report-path">{relativePath(document.path)}</span>
+                      </li>
+                    ))}
+                  </ul>
+                </section>
+              ))}
+              {SECTIONS.every(({ key }) => report[key].length === 0) && (
+                <div className="orphan-report-empty">Every document is linked and links onward</div>
+              )}
+            </>
+          )}
+        </div>
+      </div>
+    </>
+  );
+});
+
+export default OrphanReport;
@@ -0,0 +1,124 @@
+import { memo, useState, useEffect, useRef } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import "./QuickOpen.css";
+
+const MAX_RESULTS = 50;
+
+// Wait for a pause in typing before matching; the initial list shows at once
+const MATCH_DELAY = 75;
+
+// Splits text into runs, marking the characters at `positions`
+const highlight = (text, positions) => {
+  const matched = new Set(positions);
+  const parts = [];
+  Array.from(text).forEach((char, index) => {
+    const isMatch = matched.has(index);
+    const last = parts[parts.length - 1];
+    if (last && last.isMatch === isMatch) {
+      last.text += char;
+    } else {
+      parts.push({ text: char, isMatch });
+    }
+  });
+  return parts.map((part, index) => (part.isMatch ? <mark key={index}>{part.text}</mark> : <span key={index}>{part.text}</span>));
+};
+
+const QuickOpen = memo(function QuickOpen({ isOpen, onClose, rootPath, recentFiles, onFileSelect }) {
+  const [query, setQuery] = useState("");
+  const [results, setResults] = useState([]);
+  const [activeIndex, setActiveIndex] = useState(0);
+  const inputRef = useRef(null);
+  const listRef = useRef(null);
+
+  useEffect(() => {
+    if (isOpen) {
+      setQuery("");
+      inputRef.current?.focus();
+    }
+  }, [isOpen]);
+
+  useEffect(() => {
+    if (!isOpen || !rootPath) {
+      setResults([]);
+      return;
+    }
+
+    let cancelled = false;
+    const timer = setTimeout(() => {
+      invoke("quick_open", { root: rootPath, query, recent: recentFiles, limit: MAX_RESULTS })
+        .then((found) => {
+          if (!cancelled) {
+            setResults(found);
+            setActiveIndex(0);
+          }
+        })
+        .catch((err) => console.error("Failed to list files:", err));
+    }, query ? MATCH_DELAY : 0);
+
+    return () => {
+      cancelled = true;
+      clearTimeout(timer);
+    };
+  }, [isOpen, rootPath, query, recentFiles]);
+
+  // Keep the highlighted row in view while moving with the arrow keys
+  useEffect(() => {
+    listRef.current?.children[activeIndex]?.scrollIntoView({ block: "nearest" });
+  }, [activeIndex]);
+
+  if (!isOpen) return null;
+
+  const handleKeyDown = (e) => {
+    if (e.key === "ArrowDown") {
+      e.preventDefault();
+      setActiveIndex((index) => Math.min(index + 1, results.length - 1));
+    } else if (e.key === "ArrowUp") {
+      e.preventDefault();
+      setActiveIndex((index) => Math.max(index - 1, 0));
+    } else if (e.key === "Enter") {
+      e.preventDefault();
+      if (results[activeIndex]) onFileSelect(results[activeIndex].path);
+    } else if (e.key === "Escape") {
+      e.preventDefault();
+      onClose();
+    }
+  };
+
+  return (
+    <>
+      <div className="quick-open-overlay" onClick={onClose} />
+      <div className="quick-open">
+        <input
+          ref={inputRef}
+          type="text"
+          className="quick-open-input"
+          placeholder={rootPath ? "Go to file..." : "Select a root directory in Settings first"}
+          value={query}
+          onChange={(e) => setQuery(e.target.value)}
+          onKeyDown={handleKeyDown}
+          disabled={!rootPath}
+        />
+        {results.length > 0 && (
+          <ul className="quick-open-results" ref={listRef}>
+            {results.map((result, index) => (
+              <li
+                key={result.path}
+                className={index === activeIndex ? "active" : undefined}
+                onMouseEnter={() => setActiveIndex(index)}
+                onClick={() => onFileSelect(result.path)}
+              >
+                <span className="quick-open-title">
+                  {result.title ? highlight(result.title, result.title_positions) : highlight(result.relative_path.split("/").pop(), [])}
+                </span>
+                <span className="quick-open-path">{highlight(result.relative_path, result.path_positions)}</span>
+              </li>
+            ))}
+          </ul>
+        )}
+        {rootPath && query && results.length === 0 && <div className="quick-open-empty">No matching files</div>}
+      </div>
+    </>
+  );
+});
+
+export default QuickOpen;
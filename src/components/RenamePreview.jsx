@@ -0,0 +1,80 @@
+import { memo } from "react";
+import "./RenamePreview.css";
+
+const relativeTo = (rootPath, path) =>
+  rootPath && path.startsWith(rootPath) ? path.slice(rootPath.length).replace(/^[/\\]/, "") : path;
+
+const RenamePreview = memo(function RenamePreview({ plan, rootPath, onApply, onMoveOnly, onClose }) {
+  if (!plan) return null;
+
+  const linkCount = plan.documents.reduce((count, document) => count + document.edits.length, 0);
+
+  return (
+    <>
+      <div className="rename-preview-overlay" onClick={onClose} />
+      <div className="rename-preview">
+        <div className="rename-preview-header">
+          <h2>Update Links?</h2>
+          <button className="rename-preview-close" onClick={onClose}>
+            <svg width="20" height="20" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+              <line x1="18" y1="6" x2="6" y2="18"></line>
+              <line x1="6" y1="6" x2="18" y2="18"></line>
+            </svg>
+          </button>
+        </div>
+
+        <div className="rename-preview-content">
+          <p className="rename-preview-summary">
+            Moving <strong>{relativeTo(rootPath, plan.old_path)}</strong> to{" "}
+            <strong>{relativeTo(rootPath, plan.new_path)}</strong> changes {linkCount}{" "}
+            {linkCount === 1 ? "link" : "links"} in {plan.documents.length}{" "}
+            {plan.documents.length === 1 ? "file" : "files"}.
+          </p>
+
+          {plan.documents.map((document) => (
+            <div key={document.path} className="rename-preview-file">
+              <div className="rename-preview-file-name">{relativeTo(rootPath, document.new_path)}</div>
+              {document.edits.map((edit, index) => (
+                <div key={index} className="rename-preview-edit">
+                  <span className="rename-preview-line">{edit.line}</span>
+                  <span className="rename-preview-old">{edit.old_url}</span>
+                  <span className="rename-preview-arrow">→</span>
+                  <span className="rename-preview-new">{edit.new_url}</span>
+                </div>
+              ))}
+            </div>
+          ))}
+
+          {plan.skipped.length > 0 && (
+            <div className="rename-preview-file">
+              <div className="rename-preview-file-name">
+                {plan.skipped.length === 1 ? "This link needs" : "These links need"} updating by hand
+              </div>
+              {plan.skipped.map((link, index) => (
+                <div key={index} className="rename-preview-edit">
+                  <span className="rename-preview-line">{link.line}</span>
+                  <span className="rename-preview-skipped-file">{relativeTo(rootPath, link.path)}</span>
+                  <span className="rename-preview-skipped-url">{link.url}</span>
+                </div>
+              ))}
+            </div>
+          )}
+        </div>
+
+        <div className="rename-preview-actions">
+          <button onClick={onClose} className="rename-preview-btn-secondary">
+            Cancel
+          </button>
+          <button onClick={onMoveOnly} className="rename-preview-btn-secondary">
+            Move Only
+          </button>
+          <button onClick={onApply} className="rename-preview-btn-primary">
+            Move and Update Links
+          </button>
+        </div>
+      </div>
+    </>
+  );
+});
+
+export default RenamePreview;
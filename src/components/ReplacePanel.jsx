@@ -0,0 +1,283 @@
+import { memo, useState, useEffect, useRef, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import "./ReplacePanel.css";
+
+// Field names match the backend's FindOptions
+const TOGGLES = [
+  { name: "regex", label: ".*", title: "Regular Expression" },
+  { name: "case_sensitive", label: "Aa", title: "Match Case" },
+  { name: "whole_word", label: "W", title: "Whole Word" },
+];
+
+const SKIP_OPTIONS = [
+  { name: "skip_code", label: "Skip code" },
+  { name: "skip_front_matter", label: "Skip front matter" },
+];
+
+const matchKey = (path, offset) => `${path}:${offset}`;
+
+const ReplacePanel = memo(function ReplacePanel({ isOpen, onClose, rootPath, onFileSelect }) {
+  const [options, setOptions] = useState({
+    pattern: "",
+    regex: false,
+    case_sensitive: false,
+    whole_word: false,
+    skip_code: true,
+    skip_front_matter: false,
+  });
+  const [replacement, setReplacement] = useState("");
+  const [result, setResult] = useState(null);
+  const [selected, setSelected] = useState(new Set());
+  const [isBusy, setIsBusy] = useState(false);
+  const [error, setError] = useState(null);
+  const [summary, setSummary] = useState(null);
+  const inputRef = useRef(null);
+
+  useEffect(() => {
+    if (isOpen) {
+      inputRef.current?.focus();
+      inputRef.current?.select();
+    }
+  }, [isOpen]);
+
+  const runFind = useCallback(async () => {
+    if (!rootPath || !options.pattern) return;
+
+    setIsBusy(true);
+    setError(null);
+    setSummary(null);
+
+    try {
+      const found = await invoke("find_in_workspace", { root: rootPath, options, replacement });
+      setResult(found);
+      // Everything starts out selected
+      setSelected(new Set(found.files.flatMap((file) => file.matches.map((match) => matchKey(file.path, match.offset)))));
+    } catch (err) {
+      console.error("Failed to search workspace:", err);
+      setError(typeof err === "string" ? err : err.message || "Unknown error occurred");
+      setResult(null);
+    } finally {
+      setIsBusy(false);
+    }
+  }, [rootPath, options, replacement]);
+
+  if (!isOpen) return null;
+
+  const setOption = (name, value) => {
+    setOptions((prev) => ({ ...prev, [name]: value }));
+    setResult(null);
+  };
+
+  const toggleMatch = (key) => {
+    setSelected((prev) => {
+      const next = new Set(prev);
+      if (next.has(key)) {
+        next.delete(key);
+      } else {
+        next.add(key);
+      }
+      return next;
+    });
+  };
+
+  const toggleFile = (file) => {
+    const keys = file.matches.map((match) => matchKey(file.path, match.offset));
+    const allSelected = keys.every((key) => selected.has(key));
+    setSelected((prev) => {
+      const next = new Set(prev);
+      keys.forEach((key) => (allSelected ? next.delete(key) : next.add(key)));
+      return next;
+    });
+  };
+
+  const handleReplace = async () => {
+    const selections = result.files
+      .map((file) => ({
+        path: file.path,
+        offsets: file.matches.map((match) => match.offset).filter((offset) => selected.has(matchKey(file.path, offset))),
+      }))
+      .filter((selection) => selection.offsets.length > 0);
+
+    setIsBusy(true);
+    setError(null);
+
+    try {
+      const done = await invoke("replace_in_workspace", { root: rootPath, options, replacement, selections });
+      setSummary(done);
+      setResult(null);
+      setSelected(new Set());
+    } catch (err) {
+      console.error("Failed to replace:", err);
+      setError(typeof err === "string" ? err : err.message || "Unknown error occurred");
+    } finally {
+      setIsBusy(false);
+    }
+  };
+
+  const handleKeyDown = (e) => {
+    if (e.key === "Enter") {
+      e.preventDefault();
+      runFind();
+    } else if (e.key === "Escape") {
+      e.preventDefault();
+      onClose();
+    }
+  };
+
+  const relativePath = (path) => {
+    if (rootPath && path.startsWith(rootPath)) {
+      return path.slice(rootPath.length).replace(/^[/\\]/, "");
+    }
+    return path;
+  };
+
+  return (
+    <>
+      <div className="replace-panel-overlay" onClick={onClose} />
+      <div className="replace-panel">
+        <div className="replace-panel-header">
+          <h2>Replace in Workspace</h2>
+          <button className="replace-panel-close" onClick={onClose}>
+            <svg width="20" height="20" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+              <line x1="18" y1="6" x2="6" y2="18"></line>
+              <line x1="6" y1="6" x2="18" y2="18"></line>
+            </svg>
+          </button>
+        </div>
+
+        <div className="replace-panel-form">
+          <div className="replace-panel-row">
+            <input
+              ref={inputRef}
+              type="text"
+              placeholder="Find"
+              value={options.pattern}
+              onChange={(e) => setOption("pattern", e.target.value)}
+              onKeyDown={handleKeyDown}
+              disabled={!rootPath}
+            />
+            {TOGGLES.map(({ name, label, title }) => (
+              <button
+                key={name}
+                className={`replace-panel-toggle ${options[name] ? "active" : ""}`}
+                onClick={() => setOption(name, !options[name])}
+                title={title}
+              >
+                {label}
+              </button>
+            ))}
+          </div>
+          <div className="replace-panel-row">
+            <input
+              type="text"
+              placeholder={options.regex ? "Replace (use $1 or ${name} for groups)" : "Replace"}
+              value={replacement}
+              onChange={(e) => {
+                setReplacement(e.target.value);
+                setResult(null);
+              }}
+              onKeyDown={handleKeyDown}
+              disabled={!rootPath}
+            />
+            <button className="replace-panel-find" onClick={runFind} disabled={isBusy || !rootPath || !options.pattern}>
+              Find
+            </button>
+          </div>
+          <div className="replace-panel-row replace-panel-skip">
+            {SKIP_OPTIONS.map(({ name, label }) => (
+              <label key={name}>
+                <input type="checkbox" checked={options[name]} onChange={(e) => setOption(name, e.target.checked)} />
+                {label}
+              </label>
+            ))}
+          </div>
+        </div>
+
+        <div className="replace-panel-content">
+          {!rootPath ? (
+            <div className="replace-panel-empty">Select a root directory in Settings or open a file first</div>
+          ) : error ? (
+            <div className="replace-panel-error">{error}</div>
+          ) : summary ? (
+            <>
+              <div className="replace-panel-empty">
+                Replaced {summary.replacements} {summary.replacements === 1 ? "match" : "matches"} in {summary.files_changed}{" "}
+                {summary.files_changed === 1 ? "file" : "files"}
+              </div>
+              {summary.failed.length > 0 && (
+                <ul className="replace-panel-failed">
+                  {summary.failed.map((failure) => (
+                    <li key={failure.path} title={failure.path}>
+                      {relativePath(failure.path)}: {failure.message}
+                    </li>
+                  ))}
+                </ul>
+              )}
+            </>
+          ) : isBusy ? (
+            <div className="replace-panel-empty">Searching...</div>
+          ) : result && result.match_count === 0 ? (
+            <div className="replace-panel-empty">No matches</div>
+          ) : result && (
+            <>
+              <div className="replace-panel-summary">
+                {result.match_count} matches in {result.files.length} files
+                {result.truncated && " (stopped early, narrow the search to see the rest)"}
+              </div>
+              <ul className="replace-panel-files">
+                {result.files.map((file) => (
+                  <li key={file.path}>
+                    <label className="replace-panel-file" title={file.path}>
+                      <input
+                        type="checkbox"
+                        checked={file.matches.every((match) => selected.has(matchKey(file.path, match.offset)))}
+                        onChange={() => toggleFile(file)}
+                      />
+                      {relativePath(file.path)}
+                    </label>
+                    {file.matches.map((match) => {
+                      const key = matchKey(file.path, match.offset);
+                      return (
+                        <div key={key} className="replace-panel-match">
+                          <input type="checkbox" checked={selected.has(key)} onChange={() => toggleMatch(key)} />
+                          <span
+                            className="replace-panel-line"
+                            onClick={() => onFileSelect(file.path, match.line)}
+                            title="Open at this line"
+                          >
+                            {match.line}
+                          </span>
+                          <span className="replace-panel-text">
+                            {match.before}
+                            <del>{match.matched}</del>
+                            {match.replacement !== null && <ins>{match.replacement}</ins>}
+                            {match.after}
+                          </span>
+                        </div>
+                      );
+                    })}
+                  </li>
+                ))}
+              </ul>
+            </>
+          )}
+        </div>
+
+        <div className="replace-panel-actions">
+          <button onClick={onClose} className="replace-panel-btn-cancel">
+            Close
+          </button>
+          <button
+            onClick={handleReplace}
+            className="replace-panel-btn-replace"
+            disabled={isBusy || !result || selected.size === 0}
+          >
+            Replace {selected.size > 0 ? selected.size : ""} Selected
+          </button>
+        </div>
+      </div>
+    </>
+  );
+});
+
+export default ReplacePanel;
@@ -0,0 +1,71 @@
+import { memo } from "react";
+import "./SaveConflict.css";
+
+const MARKER_CLASSES = {
+  "<<<<<<<": "save-conflict-line-ours",
+  "|||||||": "save-conflict-line-base",
+  "=======": "save-conflict-line-separator",
+  ">>>>>>>": "save-conflict-line-theirs",
+};
+
+const SaveConflict = memo(function SaveConflict({
+  conflict,
+  fileName,
+  onOverwrite,
+  onUseMerged,
+  onReload,
+  onClose,
+}) {
+  if (!conflict) return null;
+
+  const lines = conflict.merged.split("\n");
+
+  return (
+    <>
+      <div className="save-conflict-overlay" onClick={onClose} />
+      <div className="save-conflict">
+        <div className="save-conflict-header">
+          <h2>{fileName} changed on disk</h2>
+          <button className="save-conflict-close" onClick={onClose}>
+            <svg width="20" height="20" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+              <line x1="18" y1="6" x2="6" y2="18"></line>
+              <line x1="6" y1="6" x2="18" y2="18"></line>
+            </svg>
+          </button>
+        </div>
+
+        <div className="save-conflict-content">
+          <p className="save-conflict-summary">
+            {conflict.has_conflicts
+              ? "Your edits and the changes on disk touch the same lines. The preview marks them: \"ours\" is your version, \"original\" is what you opened and \"theirs\" is the disk copy."
+              : "Your edits and the changes on disk don't overlap and merge cleanly."}
+          </p>
+          <pre className="save-conflict-preview">
+            {lines.map((line, index) => (
+              <div key={index} className={MARKER_CLASSES[line.slice(0, 7)] || undefined}>
+                {line || " "}
+              </div>
+            ))}
+          </pre>
+        </div>
+
+        <div className="save-conflict-actions">
+          <button onClick={onClose} className="save-conflict-btn-secondary">
+            Cancel
+          </button>
+          <button onClick={onReload} className="save-conflict-btn-secondary">
+            Discard My Changes
+          </button>
+          <button onClick={onOverwrite} className="save-conflict-btn-secondary">
+            Overwrite
+          </button>
+          <button onClick={onUseMerged} className="save-conflict-btn-primary">
+            {conflict.has_conflicts ? "Edit Merged" : "Use Merged"}
+          </button>
+        </div>
+      </div>
+    </>
+  );
+});
+
+export default SaveConflict;
@@ -1,9 +1,25 @@
-import { memo } from "react";
+import { memo, useState, useEffect } from "react";
 import "./Settings.css";
 
-const Settings = memo(function Settings({ isOpen, onClose, theme, onToggleTheme, rootDirectory, onSelectRootDirectory, onClearRootDirectory }) {
+const Settings = memo(function Settings({ isOpen, onClose, theme, onToggleTheme, rootDirectory, onSelectRootDirectory, onClearRootDirectory, permanentDelete, onTogglePermanentDelete, excludePatterns, onChangeExcludePatterns }) {
+  const [excludeText, setExcludeText] = useState("");
+
+  useEffect(() => {
+    if (isOpen) {
+      setExcludeText((excludePatterns || []).join("\n"));
+    }
+  }, [isOpen, excludePatterns]);
+
   if (!isOpen) return null;
 
+  const saveExcludePatterns = () => {
+    const patterns = excludeText
+      .split("\n")
+      .map((pattern) => pattern.trim())
+      .filter(Boolean);
+    onChangeExcludePatterns(patterns);
+  };
+
   return (
     <>
       <div className="settings-overlay" onClick={onClose} />
@@ -46,6 +62,36 @@ const Settings = memo(function Settings({ isOpen, onClose, theme, onToggleTheme,
                 )}
               </div>
             </div>
+            <div className="setting-item">
+              <div className="setting-info">
+                <label>Delete Permanently</label>
+                <span className="setting-description">
+                  {permanentDelete
+                    ? 'Deleted files and folders are removed for good'
+                    : 'Deleted files and folders go to the trash'}
+                </span>
+              </div>
+              <button className="setting-toggle" onClick={onTogglePermanentDelete}>
+                {permanentDelete ? 'On' : 'Off'}
+              </button>
+            </div>
+            <div className="setting-item setting-item-stacked">
+              <div className="setting-info">
+                <label>Excluded Files</label>
+                <span className="setting-description">
+                  Hidden from the file tree, one pattern per line, in .gitignore syntax (e.g. node_modules, *.log)
+                </span>
+              </div>
+              <textarea
+                className="setting-textarea"
+                value={excludeText}
+                onChange={(e) => setExcludeText(e.target.value)}
+                onBlur={saveExcludePatterns}
+                placeholder={"node_modules\ndist/"}
+                rows={4}
+                spellCheck={false}
+              />
+            </div>
           </div>
 
           <div className="settings-section">
@@ -7,16 +7,24 @@ const Sidebar = memo(function Sidebar({
   recentFiles,
   linkedDocs,
   isLoadingLinked,
+  backlinks,
+  isLoadingBacklinks,
+  onRefreshBacklinks,
+  onBacklinkSelect,
   currentFile,
   displayUrl,
   onFileSelect,
   onRemoveRecent,
   onClose,
-  rootDirectory
+  rootDirectory,
+  permanentDelete,
+  listOptions,
+  onChangeListOption
 }) {
   const [activeTab, setActiveTab] = useState("documents");
   const [width, setWidth] = useState(360);
   const [isResizing, setIsResizing] = useState(false);
+  const [collapsedLinks, setCollapsedLinks] = useState(() => new Set());
   const sidebarRef = useRef(null);
 
   const getFileName = (path) => {
@@ -77,6 +85,89 @@ const Sidebar = memo(function Sidebar({
     return false;
   };
 
+  // Each linked document goes under the one it was first reached from.
+  // Remote documents carry no parents and stay a flat list.
+  const linkedChildren = new Map();
+  for (const doc of linkedDocs) {
+    const parent = doc.depth > 1 ? doc.parents[0] : null;
+    if (!linkedChildren.has(parent)) linkedChildren.set(parent, []);
+    linkedChildren.get(parent).push(doc);
+  }
+
+  const toggleLinkedCollapsed = (path) => {
+    setCollapsedLinks((prev) => {
+      const next = new Set(prev);
+      if (next.has(path)) {
+        next.delete(path);
+      } else {
+        next.add(path);
+      }
+      return next;
+    });
+  };
+
+  const describeLinkedDoc = (doc) => {
+    const lines = [doc.path];
+    if (doc.link_text && doc.link_text !== doc.title) lines.push(`Linked as "${doc.link_text}"`);
+    if (doc.parents && doc.parents.length > 1) lines.push(`Linked from ${doc.parents.length} documents`);
+    if (doc.cyclic) lines.push("Part of a loop of links");
+    return lines.join("\n");
+  };
+
+  const renderLinkedDocs = (parent, depth) =>
+    (linkedChildren.get(parent) || []).map((doc) => {
+      const children = linkedChildren.get(doc.path);
+      const isCollapsed = collapsedLinks.has(doc.path);
+      return (
+        <li key={doc.path} className="linked-tree-item">
+          <div
+            className={`linked-tree-row ${doc.path === currentFile ? "active" : ""}`}
+            style={{ paddingLeft: `${depth * 16}px` }}
+            onClick={() => onFileSelect(doc.path, { isRootFile: false, addToRecent: false })}
+            title={describeLinkedDoc(doc)}
+          >
+            <button
+              className={`linked-tree-toggle ${children ? "" : "hidden"} ${isCollapsed ? "collapsed" : ""}`}
+              onClick={(e) => {
+                e.stopPropagation();
+                toggleLinkedCollapsed(doc.path);
+              }}
+              tabIndex={-1}
+            >
+              <svg width="12" height="12" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+                <polyline points="6 9 12 15 18 9"></polyline>
+              </svg>
+            </button>
+            <div className="file-info">
+              <svg width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+                <path d="M10 13a5 5 0 0 0 7.54.54l3-3a5 5 0 0 0-7.07-7.07l-1.72 1.71"></path>
+                <path d="M14 11a5 5 0 0 0-7.54-.54l-3 3a5 5 0 0 0 7.07 7.07l1.71-1.71"></path>
+              </svg>
+              <div className="file-details">
+                <span className="file-name">
+                  {doc.title}
+                  {doc.cyclic && <span className="linked-tree-cycle">↻</span>}
+                </span>
+                <span className="file-path">{getDirectory(doc.path)}</span>
+              </div>
+            </div>
+          </div>
+          {children && !isCollapsed && <ul className="linked-tree">{renderLinkedDocs(doc.path, depth + 1)}</ul>}
+        </li>
+      );
+    });
+
+  // Links from several places in one document are listed under it
+  const backlinkGroups = backlinks.reduce((groups, backlink) => {
+    const last = groups[groups.length - 1];
+    if (last && last.path === backlink.path) {
+      last.links.push(backlink);
+    } else {
+      groups.push({ path: backlink.path, title: backlink.title, links: [backlink] });
+    }
+    return groups;
+  }, []);
+
   useEffect(() => {
     if (!isResizing) return;
 
@@ -131,6 +222,15 @@ const Sidebar = memo(function Sidebar({
           >
             Linked
           </button>
+          <button
+            className={`sidebar-tab ${activeTab === "backlinks" ? "active" : ""}`}
+            onClick={() => {
+              setActiveTab("backlinks");
+              onRefreshBacklinks();
+            }}
+          >
+            Backlinks
+          </button>
         </div>
         <button className="sidebar-close" onClick={onClose}>
           <svg width="18" height="18" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
@@ -146,6 +246,9 @@ const Sidebar = memo(function Sidebar({
             rootPath={rootDirectory}
             onFileClick={onFileSelect}
             selectedPath={currentFile}
+            permanentDelete={permanentDelete}
+            listOptions={listOptions}
+            onChangeListOption={onChangeListOption}
           />
         )}
 
@@ -207,22 +310,50 @@ const Sidebar = memo(function Sidebar({
                 <span>This file doesn't link to other markdown files</span>
               </div>
             ) : (
-              <ul className="file-list">
-                {linkedDocs.map((doc, index) => (
-                  <li 
-                    key={index}
-                    className={doc.path === currentFile ? "active" : ""}
-                    onClick={() => onFileSelect(doc.path, { isRootFile: false, addToRecent: false })}
-                    title={doc.path}
-                  >
-                    <div className="file-info">
+              <ul className="linked-tree">{renderLinkedDocs(null, 0)}</ul>
+            )}
+          </>
+        )}
+
+        {activeTab === "backlinks" && (
+          <>
+            {isLoadingBacklinks && backlinks.length === 0 ? (
+              <div className="sidebar-loading">
+                <div className="spinner"></div>
+                <p>Finding backlinks...</p>
+              </div>
+            ) : backlinks.length === 0 ? (
+              <div className="sidebar-empty">
+                <p>No backlinks</p>
+                <span>No other document in the workspace links to this file</span>
+              </div>
+            ) : (
+              <ul className="file-list backlink-list">
+                {backlinkGroups.map((group) => (
+                  <li key={group.path} title={group.path}>
+                    <div className="file-info" onClick={() => onBacklinkSelect(group.path, group.links[0].line)}>
                       <svg width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
-                        <path d="M10 13a5 5 0 0 0 7.54.54l3-3a5 5 0 0 0-7.07-7.07l-1.72 1.71"></path>
-                        <path d="M14 11a5 5 0 0 0-7.54-.54l-3 3a5 5 0 0 0 7.07 7.07l1.71-1.71"></path>
+                        <polyline points="9 14 4 9 9 4"></polyline>
+                        <path d="M20 20v-7a4 4 0 0 0-4-4H4"></path>
                       </svg>
                       <div className="file-details">
-                        <span className="file-name">{doc.title}</span>
-                        <span className="file-path">{getDirectory(doc.path)}</span>
+                        <span className="file-name">{group.title}</span>
+                        <span className="file-path">{getDirectory(group.path)}</span>
+                        {group.links.map((link) => (
+                          <span
+                            key={`${link.line}:${link.column}`}
+                            className="backlink-context"
+                            title={`Line ${link.line}`}
+                            onClick={(e) => {
+                              e.stopPropagation();
+                              onBacklinkSelect(group.path, link.line);
+                            }}
+                          >
+                            {link.before}
+                            <mark>{link.link}</mark>
+                            {link.after}
+                          </span>
+                        ))}
                       </div>
                     </div>
                   </li>
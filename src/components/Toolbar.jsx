@@ -111,7 +111,7 @@ const Toolbar = memo(function Toolbar({
       </div>
 
       <div className="toolbar-center">
-        {(currentFile || isEditMode) ? (
+        {(currentFile || isEditMode || isDirty) ? (
           <span className="file-name">
             {isDirty && <span className="dirty-indicator">● </span>}
             {getFileName()}
@@ -122,7 +122,7 @@ const Toolbar = memo(function Toolbar({
       </div>
 
       <div className="toolbar-right">
-        {(currentFile || isEditMode) && (
+        {(currentFile || isEditMode || isDirty) && (
           <>
             {isEditMode && (
               <button 
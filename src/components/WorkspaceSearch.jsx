@@ -0,0 +1,148 @@
+import { memo, useState, useEffect, useRef } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import "./WorkspaceSearch.css";
+
+// Wait for a pause in typing before searching
+const SEARCH_DELAY = 200;
+
+const WorkspaceSearch = memo(function WorkspaceSearch({ isOpen, onClose, rootPath, onResultSelect }) {
+  const [query, setQuery] = useState("");
+  const [results, setResults] = useState([]);
+  const [isSearching, setIsSearching] = useState(false);
+  const [error, setError] = useState(null);
+  const inputRef = useRef(null);
+
+  // Build the index while the user starts typing
+  useEffect(() => {
+    if (!isOpen || !rootPath) return;
+
+    inputRef.current?.focus();
+    inputRef.current?.select();
+    invoke("search_workspace", { root: rootPath, query: "" })
+      .catch((err) => console.error("Failed to index workspace:", err));
+  }, [isOpen, rootPath]);
+
+  useEffect(() => {
+    if (!isOpen || !rootPath || !query.trim()) {
+      setResults([]);
+      setError(null);
+      return;
+    }
+
+    let cancelled = false;
+    const timer = setTimeout(async () => {
+      setIsSearching(true);
+      try {
+        const found = await invoke("search_workspace", { root: rootPath, query });
+        if (!cancelled) {
+          setResults(found);
+          setError(null);
+        }
+      } catch (err) {
+        console.error("Failed to search workspace:", err);
+        if (!cancelled) {
+          setError(typeof err === "string" ? err : err.message || "Unknown error occurred");
+          setResults([]);
+        }
+      } finally {
+        if (!cancelled) setIsSearching(false);
+      }
+    }, SEARCH_DELAY);
+
+    return () => {
+      cancelled = true;
+      clearTimeout(timer);
+    };
+  }, [isOpen, rootPath, query]);
+
+  if (!isOpen) return null;
+
+  const relativePath = (path) => {
+    if (rootPath && path.startsWith(rootPath)) {
+      return path.slice(rootPath.length).replace(/^[/\\]/, "");
+    }
+    return path;
+  };
+
+  const handleKeyDown = (e) => {
+    if (e.key === "Escape") {
+      e.preventDefault();
+      onClose();
+    } else if (e.key === "Enter" && results.length > 0) {
+      e.preventDefault();
+      const [first] = results;
+      onResultSelect(first.path, first.matches[0]?.line);
+    }
+  };
+
+  return (
+    <>
+      <div className="workspace-search-overlay" onClick={onClose} />
+      <div className="workspace-search">
+        <div className="workspace-search-header">
+          <svg width="18" height="18" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+            <circle cx="11" cy="11" r="8"></circle>
+            <line x1="21" y1="21" x2="16.65" y2="16.65"></line>
+          </svg>
+          <input
+            ref={inputRef}
+            type="text"
+            className="workspace-search-input"
+            placeholder="Search in workspace..."
+            value={query}
+            onChange={(e) => setQuery(e.target.value)}
+            onKeyDown={handleKeyDown}
+            disabled={!rootPath}
+          />
+          {isSearching && <span className="workspace-search-status">Searching...</span>}
+        </div>
+
+        <div className="workspace-search-content">
+          {!rootPath ? (
+            <div className="workspace-search-empty">Select a root directory in Settings or open a file first</div>
+          ) : error ? (
+            <div className="workspace-search-error">{error}</div>
+          ) : !query.trim() ? (
+            <div className="workspace-search-empty">Type to search every Markdown file in the workspace</div>
+          ) : results.length === 0 ? (
+            !isSearching && <div className="workspace-search-empty">No matches</div>
+          ) : (
+            <ul className="workspace-search-results">
+              {results.map((result) => (
+                <li key={result.path} className="workspace-search-result">
+                  <div
+                    className="workspace-search-result-header"
+                    onClick={() => onResultSelect(result.path, result.matches[0]?.line)}
+                    title={result.path}
+                  >
+                    <span className="workspace-search-title">{result.title}</span>
+                    <span className="workspace-search-path">{relativePath(result.path)}</span>
+                    {result.match_count > result.matches.length && (
+                      <span className="workspace-search-count">{result.match_count} lines</span>
+                    )}
+                  </div>
+                  {result.matches.map((match) => (
+                    <div
+                      key={match.line}
+                      className="workspace-search-match"
+                      onClick={() => onResultSelect(result.path, match.line)}
+                    >
+                      <span className="workspace-search-line">{match.line}</span>
+                      <span className="workspace-search-snippet">
+                        {match.snippet.map((part, index) =>
+                          part.highlight ? <mark key={index}>{part.text}</mark> : <span key={index}>{part.text}</span>
+                        )}
+                      </span>
+                    </div>
+                  ))}
+                </li>
+              ))}
+            </ul>
+          )}
+        </div>
+      </div>
+    </>
+  );
+});
+
+export default WorkspaceSearch;
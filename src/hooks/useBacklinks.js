@@ -0,0 +1,41 @@
+import { useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+export function useBacklinks(currentFilePath, rootPath, isRemoteFile = false) {
+  const [backlinks, setBacklinks] = useState([]);
+  const [isLoading, setIsLoading] = useState(false);
+  const [error, setError] = useState(null);
+
+  const findBacklinks = useCallback(async (filePath, root, isRemote) => {
+    // Remote documents aren't part of a local workspace
+    if (!filePath || !root || isRemote) {
+      setBacklinks([]);
+      return;
+    }
+
+    setIsLoading(true);
+    setError(null);
+
+    try {
+      const found = await invoke("find_backlinks", { root, path: filePath });
+      setBacklinks(found || []);
+    } catch (err) {
+      console.error("Failed to find backlinks:", err);
+      setError(err.toString());
+      setBacklinks([]);
+    } finally {
+      setIsLoading(false);
+    }
+  }, []);
+
+  useEffect(() => {
+    findBacklinks(currentFilePath, rootPath, isRemoteFile);
+  }, [currentFilePath, rootPath, isRemoteFile, findBacklinks]);
+
+  return {
+    backlinks,
+    isLoading,
+    error,
+    refresh: () => findBacklinks(currentFilePath, rootPath, isRemoteFile),
+  };
+}
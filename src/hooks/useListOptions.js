@@ -0,0 +1,35 @@
+import { useState, useEffect, useCallback } from "react";
+
+const LIST_OPTIONS_KEY = "mdox-list-options";
+
+// Field names match the backend's ListOptions
+const DEFAULT_LIST_OPTIONS = {
+  show_hidden: false,
+  respect_ignore_files: true,
+  markdown_only: false,
+  markdown_folders_only: false,
+  exclude: [],
+  sort_by: "name",
+  show_titles: false,
+};
+
+export function useListOptions() {
+  const [listOptions, setListOptions] = useState(() => {
+    try {
+      const saved = JSON.parse(localStorage.getItem(LIST_OPTIONS_KEY));
+      return { ...DEFAULT_LIST_OPTIONS, ...saved };
+    } catch {
+      return DEFAULT_LIST_OPTIONS;
+    }
+  });
+
+  useEffect(() => {
+    localStorage.setItem(LIST_OPTIONS_KEY, JSON.stringify(listOptions));
+  }, [listOptions]);
+
+  const setListOption = useCallback((name, value) => {
+    setListOptions((prev) => ({ ...prev, [name]: value }));
+  }, []);
+
+  return { listOptions, setListOption };
+}
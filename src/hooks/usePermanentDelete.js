@@ -0,0 +1,19 @@
+import { useState, useEffect } from "react";
+
+const PERMANENT_DELETE_KEY = "mdox-permanent-delete";
+
+export function usePermanentDelete() {
+  const [permanentDelete, setPermanentDelete] = useState(() => {
+    return localStorage.getItem(PERMANENT_DELETE_KEY) === "true";
+  });
+
+  useEffect(() => {
+    localStorage.setItem(PERMANENT_DELETE_KEY, String(permanentDelete));
+  }, [permanentDelete]);
+
+  const togglePermanentDelete = () => {
+    setPermanentDelete((prev) => !prev);
+  };
+
+  return { permanentDelete, togglePermanentDelete };
+}